@@ -0,0 +1,346 @@
+//! The measure and arrange passes of the containers: children are measured, laid out by [`Flex`]
+//! or [`GridTracks`] and moved where they were put. Nested containers are only laid out once they
+//! are given their size, while any other child is drawn once, at its own size.
+
+use usvg::{Group, Node, NodeExt, NodeKind, Rect, Transform};
+
+use crate::{
+    component::{Component, ComponentAction, RenderContext},
+    system::{Bounds, Flex, FlexItem, GridCell, GridTracks, Length, SizeLimit},
+};
+
+use super::{decoration::Decoration, text::clip};
+
+/// A size limit from a fixed, a least and a most length, less `inset`.
+pub(super) fn size_limit(
+    [fixed, min, max]: [&Option<Length>; 3],
+    inset: f64,
+    context: &RenderContext,
+) -> SizeLimit {
+    let px = |length: &Option<Length>| {
+        length.as_ref().map(|length| {
+            (length.translate_to_px(context.viewbox_width, context.viewbox_height) - inset).max(0.0)
+        })
+    };
+    SizeLimit {
+        fixed: px(fixed),
+        min: px(min),
+        max: px(max),
+    }
+}
+
+/// A child measured for its container.
+pub(super) enum Measured<'a> {
+    /// Drawn at its own size.
+    Drawn(Node, (f64, f64)),
+    /// A container, laid out once it is given its size.
+    Container(Box<Arrangement<'a>>),
+}
+
+impl<'a> Measured<'a> {
+    fn drawn(node: Node) -> Self {
+        let size = measure(&node);
+        Measured::Drawn(node, size)
+    }
+
+    /// Measures `child`, drawing it unless it is a container.
+    fn one(child: &'a mut Component, context: &RenderContext) -> eyre::Result<Self> {
+        Ok(match child {
+            Component::HBox(hbox) => Measured::Container(Box::new(hbox.measure(context)?)),
+            Component::VBox(vbox) => Measured::Container(Box::new(vbox.measure(context)?)),
+            Component::Grid(grid) => Measured::Container(Box::new(grid.measure(context)?)),
+            Component::Stack(stack) => Measured::Container(Box::new(stack.measure(context)?)),
+            child => Measured::drawn(child.render(context)?),
+        })
+    }
+
+    /// Measures `child` once, or once per element for `<for-each>`.
+    fn all(child: &'a mut Component, context: &RenderContext) -> eyre::Result<Vec<Self>> {
+        match child {
+            Component::ForEach(_) => Ok(child
+                .render_all(context)?
+                .into_iter()
+                .map(Measured::drawn)
+                .collect()),
+            child => Ok(vec![Measured::one(child, context)?]),
+        }
+    }
+
+    /// Measures an optional child, nothing taking no room.
+    pub(super) fn maybe(
+        child: Option<&'a mut Component>,
+        context: &RenderContext,
+    ) -> eyre::Result<Self> {
+        match child {
+            Some(child) => Measured::one(child, context),
+            None => Ok(Measured::drawn(Node::new(
+                NodeKind::Group(Group::default()),
+            ))),
+        }
+    }
+
+    /// Size the child takes unless it is given another.
+    pub(super) fn size(&self) -> (f64, f64) {
+        match self {
+            Measured::Drawn(_, size) => *size,
+            Measured::Container(arrangement) => arrangement.size,
+        }
+    }
+
+    /// Puts the child at `bounds` from `origin` into `parent`, laying a container out in them.
+    fn put(
+        self,
+        parent: &mut Node,
+        bounds: &Bounds,
+        origin: (f64, f64),
+        context: &RenderContext,
+    ) -> eyre::Result<()> {
+        let (node, size) = match self {
+            Measured::Drawn(node, size) => (node, size),
+            Measured::Container(arrangement) => {
+                arrangement.place(Some((bounds.width, bounds.height)), context)?
+            }
+        };
+        put(parent, node, bounds, size, origin);
+        Ok(())
+    }
+}
+
+enum Layout {
+    Flex(Flex, Vec<FlexItem>),
+    /// Tracks and cells.
+    Grid(GridTracks, Vec<GridCell>),
+}
+
+/// A container with its children measured, to be laid out and drawn in the size it is given.
+pub(super) struct Arrangement<'a> {
+    layout: Layout,
+    decoration: Decoration<'a>,
+    edges: [f64; 4],
+    children: Vec<Measured<'a>>,
+    /// Size the container takes unless it is given another, its edges included.
+    size: (f64, f64),
+}
+
+impl<'a> Arrangement<'a> {
+    fn new(
+        layout: Layout,
+        decoration: Decoration<'a>,
+        context: &RenderContext,
+        children: Vec<Measured<'a>>,
+    ) -> Self {
+        let mut arrangement = Arrangement {
+            layout,
+            edges: decoration.edges(context),
+            decoration,
+            children,
+            size: (0.0, 0.0),
+        };
+        let (_, width, height) = arrangement.layout(None);
+        arrangement.size = arrangement.outer(width, height);
+        arrangement
+    }
+
+    /// `width` by `height` inside the edges, with the edges.
+    fn outer(&self, width: f64, height: f64) -> (f64, f64) {
+        let [top, right, bottom, left] = self.edges;
+        (left + width + right, top + height + bottom)
+    }
+
+    /// Where the children go and the size inside the edges, when the container is given `size`.
+    fn layout(&self, size: Option<(f64, f64)>) -> (Vec<Bounds>, f64, f64) {
+        let [top, right, bottom, left] = self.edges;
+        let given = |limit: SizeLimit, size: Option<f64>, inset: f64| match size {
+            Some(size) => limit.given((size - inset).max(0.0)),
+            None => limit,
+        };
+        let (width, height) = size.unzip();
+        match &self.layout {
+            Layout::Flex(flex, items) => {
+                let layout = Flex {
+                    width: given(flex.width, width, left + right),
+                    height: given(flex.height, height, top + bottom),
+                    ..*flex
+                }
+                .layout(items);
+                (layout.items, layout.width, layout.height)
+            }
+            Layout::Grid(tracks, cells) => {
+                let layout = GridTracks {
+                    width: given(tracks.width, width, left + right),
+                    height: given(tracks.height, height, top + bottom),
+                    ..tracks.clone()
+                }
+                .layout(cells);
+                (layout.cells, layout.width, layout.height)
+            }
+        }
+    }
+
+    /// Lays the children out in `size`, or in the size the container takes when not given, and
+    /// draws them with the decoration. Gives the drawing with its size.
+    pub(super) fn place(
+        self,
+        size: Option<(f64, f64)>,
+        context: &RenderContext,
+    ) -> eyre::Result<(Node, (f64, f64))> {
+        let (bounds, width, height) = self.layout(size);
+        let size = self.outer(width, height);
+        let origin = (self.edges[3], self.edges[0]);
+
+        let mut result = Node::new(NodeKind::Group(Group::default()));
+        self.decoration.render(&mut result, size.0, size.1, context);
+        for (child, bounds) in self.children.into_iter().zip(&bounds) {
+            child.put(&mut result, bounds, origin, context)?;
+        }
+
+        Ok((result, size))
+    }
+}
+
+/// Measures `children` to be laid out by `flex` inside the edges of `decoration`.
+///
+/// `<margin>`, `<set-position>` and `<overlap>` are not drawn, but change how the next child is put.
+pub(super) fn arrange<'a>(
+    children: &'a mut [Component],
+    flex: Flex,
+    decoration: Decoration<'a>,
+    context: &RenderContext,
+) -> eyre::Result<Arrangement<'a>> {
+    let mut margin = 0.0;
+    let mut position = None;
+    let mut measured = Vec::new();
+    let mut items = Vec::new();
+    for child in children.iter_mut() {
+        let (child, overlap) = match child {
+            Component::Overlap { child } => (child.as_mut(), true),
+            child => (child, false),
+        };
+        let children = match child {
+            Component::Margin { size } => {
+                margin += size.translate_to_px(context.viewbox_width, context.viewbox_height);
+                continue;
+            }
+            Component::SetPosition { to } => {
+                position = Some(to.translate_to_px(context.viewbox_width, context.viewbox_height));
+                margin = 0.0;
+                continue;
+            }
+            Component::Item(item) => vec![item.measure(context)?],
+            _ => Measured::all(child, context)?
+                .into_iter()
+                .map(|child| {
+                    let (width, height) = child.size();
+                    (child, FlexItem::new(width, height))
+                })
+                .collect(),
+        };
+        for (child, item) in children {
+            items.push(FlexItem {
+                margin,
+                position,
+                overlap,
+                ..item
+            });
+            measured.push(child);
+            margin = 0.0;
+            position = None;
+        }
+    }
+    // Trailing `<margin>` and `<set-position>` still make the box longer.
+    if margin != 0.0 || position.is_some() {
+        items.push(FlexItem {
+            margin,
+            position,
+            ..FlexItem::new(0.0, 0.0)
+        });
+    }
+
+    Ok(Arrangement::new(
+        Layout::Flex(flex, items),
+        decoration,
+        context,
+        measured,
+    ))
+}
+
+/// Measures `children` to be laid out in the cells of `tracks` inside the edges of `decoration`.
+/// `stacked` puts every child in the first cell, over the ones before.
+///
+/// `<margin>` and `<set-position>` are left out, and `<overlap>` is taken as its child.
+pub(super) fn arrange_cells<'a>(
+    children: &'a mut [Component],
+    tracks: GridTracks,
+    stacked: bool,
+    decoration: Decoration<'a>,
+    context: &RenderContext,
+) -> eyre::Result<Arrangement<'a>> {
+    let mut measured = Vec::new();
+    let mut cells = Vec::new();
+    for child in children.iter_mut() {
+        let child = match child {
+            Component::Overlap { child } => child.as_mut(),
+            child => child,
+        };
+        match child {
+            Component::Margin { .. } | Component::SetPosition { .. } => {}
+            Component::Cell(cell) => {
+                let (child, cell) = cell.measure(context)?;
+                cells.push(cell);
+                measured.push(child);
+            }
+            _ => {
+                for child in Measured::all(child, context)? {
+                    let (width, height) = child.size();
+                    cells.push(GridCell::new(width, height));
+                    measured.push(child);
+                }
+            }
+        }
+    }
+    if stacked {
+        for cell in cells.iter_mut() {
+            *cell = GridCell {
+                column: Some(0),
+                row: Some(0),
+                column_span: 1,
+                row_span: 1,
+                ..*cell
+            };
+        }
+    }
+
+    Ok(Arrangement::new(
+        Layout::Grid(tracks, cells),
+        decoration,
+        context,
+        measured,
+    ))
+}
+
+/// Moves `node` drawn at `size` to `bounds` from `origin`, into `parent`.
+fn put(parent: &mut Node, node: Node, bounds: &Bounds, size: (f64, f64), origin: (f64, f64)) {
+    // Shrunk or capped children are cut at their bounds instead of running into the others.
+    let node = if bounds.width < size.0 || bounds.height < size.1 {
+        match Rect::new(0.0, 0.0, bounds.width, bounds.height) {
+            Some(rect) => clip(vec![node], rect),
+            None => return,
+        }
+    } else {
+        node
+    };
+
+    let mut child_transformer = Node::new(NodeKind::Group(Group {
+        transform: Transform::new_translate(origin.0 + bounds.x, origin.1 + bounds.y),
+        ..Group::default()
+    }));
+    child_transformer.append(node);
+    parent.append(child_transformer);
+}
+
+/// Size of a drawn child from where it is put, or nothing for an empty one.
+fn measure(node: &Node) -> (f64, f64) {
+    node.calculate_bbox().map_or((0.0, 0.0), |bbox| {
+        (bbox.right().max(0.0), bbox.bottom().max(0.0))
+    })
+}
@@ -0,0 +1,116 @@
+use std::rc::Rc;
+
+use serde::Deserialize;
+use usvg::{Group, Node, NodeKind, Path, PathData};
+
+use crate::{
+    component::{job::Job, ComponentAction, RenderContext},
+    system::{Color, Length, Paint},
+};
+
+use super::{decoration::rounded_rect, meter};
+
+/// A bar filled as far as a variable is from `min` to `max`.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Bar {
+    width: Length,
+    height: Length,
+
+    min: f64,
+    max: f64,
+
+    color: Paint,
+    track_color: Option<Paint>,
+    #[serde(default)]
+    direction: BarDirection,
+    #[serde(default)]
+    rounded: bool,
+
+    #[serde(default)]
+    ticks: usize,
+    tick_color: Option<Color>,
+
+    name: String,
+}
+
+/// Where the bar grows toward.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BarDirection {
+    #[default]
+    Right,
+    Left,
+    Up,
+    Down,
+}
+
+impl ComponentAction for Bar {
+    fn setup(&mut self) -> Vec<Job> {
+        Vec::new()
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        let width = self
+            .width
+            .translate_to_px(context.viewbox_width, context.viewbox_height);
+        let height = self
+            .height
+            .translate_to_px(context.viewbox_width, context.viewbox_height);
+        let fraction = meter::fraction(context, &self.name, self.min, self.max)?;
+        let radius = if self.rounded {
+            f64::min(width, height) / 2.0
+        } else {
+            0.0
+        };
+
+        let mut group = Node::new(NodeKind::Group(Group::default()));
+        if let Some(bounds) = meter::bounds(width, height) {
+            group.append(bounds);
+        }
+
+        if let Some(track_color) = &self.track_color {
+            let fill = meter::fill(track_color, &mut group, context);
+            group.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(rounded_rect(0.0, 0.0, width, height, radius)),
+                fill: Some(fill),
+                ..Default::default()
+            })));
+        }
+
+        if fraction > 0.0 {
+            let (x, y, w, h) = match self.direction {
+                BarDirection::Right => (0.0, 0.0, width * fraction, height),
+                BarDirection::Left => (width * (1.0 - fraction), 0.0, width * fraction, height),
+                BarDirection::Up => (0.0, height * (1.0 - fraction), width, height * fraction),
+                BarDirection::Down => (0.0, 0.0, width, height * fraction),
+            };
+            let fill = meter::fill(&self.color, &mut group, context);
+            group.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(rounded_rect(x, y, w, h, radius)),
+                fill: Some(fill),
+                ..Default::default()
+            })));
+        }
+
+        if self.ticks > 1 {
+            let mut data = PathData::new();
+            for i in 1..self.ticks {
+                let at = i as f64 / self.ticks as f64;
+                match self.direction {
+                    BarDirection::Right | BarDirection::Left => {
+                        data.push_move_to(width * at, 0.0);
+                        data.push_line_to(width * at, height);
+                    }
+                    BarDirection::Up | BarDirection::Down => {
+                        data.push_move_to(0.0, height * at);
+                        data.push_line_to(width, height * at);
+                    }
+                }
+            }
+            group.append(meter::ticks(data, &self.tick_color, context));
+        }
+
+        Ok(group)
+    }
+}
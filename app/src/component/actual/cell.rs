@@ -0,0 +1,74 @@
+use serde::Deserialize;
+use usvg::{Group, Node, NodeKind};
+
+use crate::{
+    component::{job::Job, Component, ComponentAction, RenderContext, UpdateContext},
+    system::{Align, GridCell},
+};
+
+use super::arrange::Measured;
+
+/// A child of `<grid>` or `<stack>` with where it goes and how it aligns, or an empty cell.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Cell {
+    /// Counted from 1, or the next free one.
+    column: Option<usize>,
+    row: Option<usize>,
+    #[serde(default = "default_span")]
+    column_span: usize,
+    #[serde(default = "default_span")]
+    row_span: usize,
+    justify: Option<Align>,
+    align: Option<Align>,
+
+    #[serde(rename = "$value")]
+    child: Option<Box<Component>>,
+}
+
+fn default_span() -> usize {
+    1
+}
+
+impl Cell {
+    /// Measures the child, with where it goes.
+    pub(super) fn measure(
+        &mut self,
+        context: &RenderContext,
+    ) -> eyre::Result<(Measured<'_>, GridCell)> {
+        let child = Measured::maybe(self.child.as_deref_mut(), context)?;
+        let (width, height) = child.size();
+        let cell = GridCell {
+            column: self.column.map(|column| column.saturating_sub(1)),
+            row: self.row.map(|row| row.saturating_sub(1)),
+            column_span: self.column_span,
+            row_span: self.row_span,
+            justify: self.justify,
+            align: self.align,
+            ..GridCell::new(width, height)
+        };
+        Ok((child, cell))
+    }
+}
+
+impl ComponentAction for Cell {
+    fn setup(&mut self) -> Vec<Job> {
+        self.child
+            .as_mut()
+            .map_or_else(Vec::new, |child| child.setup())
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) -> eyre::Result<()> {
+        match &mut self.child {
+            Some(child) => child.update(context),
+            None => Ok(()),
+        }
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        match &mut self.child {
+            Some(child) => child.render(context),
+            None => Ok(Node::new(NodeKind::Group(Group::default()))),
+        }
+    }
+}
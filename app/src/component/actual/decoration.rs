@@ -0,0 +1,116 @@
+//! What the containers share: padding, background and border around their children.
+
+use std::rc::Rc;
+
+use usvg::{Fill, Node, NodeKind, Path, PathData, Stroke, StrokeWidth};
+
+use crate::{
+    component::RenderContext,
+    system::{Color, Insets, Length, Paint},
+};
+
+pub(super) struct Decoration<'a> {
+    pub padding: Option<&'a Insets>,
+    pub background: Option<&'a Paint>,
+    pub border_color: Option<&'a Color>,
+    /// One pixel when only `border_color` is given.
+    pub border_width: Option<&'a Length>,
+    pub corner_radius: Option<&'a Length>,
+}
+
+impl Decoration<'_> {
+    fn border_width(&self, context: &RenderContext) -> f64 {
+        match (self.border_width, self.border_color) {
+            (Some(width), _) => {
+                width.translate_to_px(context.viewbox_width, context.viewbox_height)
+            }
+            (None, Some(_)) => 1.0,
+            (None, None) => 0.0,
+        }
+    }
+
+    /// Space taken by the border and the padding on the top, right, bottom and left side.
+    pub fn edges(&self, context: &RenderContext) -> [f64; 4] {
+        let border = self.border_width(context);
+        self.padding
+            .map_or([0.0; 4], |padding| {
+                padding.translate_to_px(context.viewbox_width, context.viewbox_height)
+            })
+            .map(|padding| padding + border)
+    }
+
+    /// Draws the background and the border of a box sized `width` by `height` into `parent`.
+    ///
+    /// The background is drawn even when there is none, unpainted, so that the box keeps its size.
+    pub fn render(&self, parent: &mut Node, width: f64, height: f64, context: &RenderContext) {
+        let radius = self.corner_radius.map_or(0.0, |radius| {
+            radius.translate_to_px(context.viewbox_width, context.viewbox_height)
+        });
+
+        let fill = self.background.map(|background| {
+            let (paint, opacity) = background.to_usvg_paint(parent, context.variables);
+            Fill {
+                paint,
+                opacity,
+                ..Default::default()
+            }
+        });
+        parent.append(Node::new(NodeKind::Path(Path {
+            data: Rc::new(rounded_rect(0.0, 0.0, width, height, radius)),
+            fill,
+            ..Default::default()
+        })));
+
+        let border = self.border_width(context);
+        if border > 0.0 {
+            let color = self
+                .border_color
+                .unwrap_or(&context.config.general.foreground_color)
+                .at(context.variables);
+            // Strokes are centered on the path, so it runs through the middle of the border.
+            let inset = border / 2.0;
+            parent.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(rounded_rect(
+                    inset,
+                    inset,
+                    width - border,
+                    height - border,
+                    radius - inset,
+                )),
+                stroke: Some(Stroke {
+                    paint: usvg::Paint::Color(color.to_usvg_color()),
+                    opacity: color.to_usvg_opacity(),
+                    width: StrokeWidth::new(border),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })));
+        }
+    }
+}
+
+/// A rectangle with corners rounded by `radius`, shrunk to fit when it is too small.
+pub(super) fn rounded_rect(x: f64, y: f64, width: f64, height: f64, radius: f64) -> PathData {
+    let r = radius.min(width / 2.0).min(height / 2.0).max(0.0);
+    let (right, bottom) = (x + width, y + height);
+
+    let mut data = PathData::new();
+    let corner = |data: &mut PathData, x: f64, y: f64| {
+        if r > 0.0 {
+            data.push_arc_to(r, r, 0.0, false, true, x, y)
+        } else {
+            data.push_line_to(x, y)
+        }
+    };
+    data.push_move_to(x + r, y);
+    data.push_line_to(right - r, y);
+    corner(&mut data, right, y + r);
+    data.push_line_to(right, bottom - r);
+    corner(&mut data, right - r, bottom);
+    data.push_line_to(x + r, bottom);
+    corner(&mut data, x, bottom - r);
+    data.push_line_to(x, y + r);
+    corner(&mut data, x + r, y);
+    data.push_close_path();
+    data
+}
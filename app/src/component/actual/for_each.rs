@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use declarrred::rt::Data;
+use serde::Deserialize;
+use usvg::{Group, Node, NodeKind};
+
+use crate::component::{job::Job, Component, ComponentAction, RenderContext, UpdateContext};
+
+/// Repeats its children once per element of a list variable.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ForEach {
+    items: String,
+    #[serde(rename = "as", default = "default_alias")]
+    alias: String,
+    #[serde(default = "default_index")]
+    index: String,
+
+    #[serde(rename = "$value")]
+    template: Vec<Component>,
+}
+
+fn default_alias() -> String {
+    "item".to_string()
+}
+
+fn default_index() -> String {
+    "index".to_string()
+}
+
+impl ForEach {
+    /// Renders the template once per element, for containers to lay them out.
+    pub fn render_each(&mut self, context: &RenderContext) -> eyre::Result<Vec<Node>> {
+        let items = match context.variables.get(&self.items) {
+            Some(Data::List(items)) => items,
+            // Not fetched yet.
+            Some(Data::Unknown) => return Ok(Vec::new()),
+            Some(data) => eyre::bail!("{} is not a list: {:?}", self.items, data),
+            None => eyre::bail!("There are no variable named {}", self.items),
+        };
+
+        let mut nodes = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let mut variables = context.variables.clone();
+            bind(&mut variables, &self.alias, item);
+            variables.insert(self.index.clone(), Data::U64(index as u64));
+
+            let context = RenderContext {
+                variables: &variables,
+                ..*context
+            };
+            let mut node = Node::new(NodeKind::Group(Group::default()));
+            for child in self.template.iter_mut() {
+                node.append(child.render(&context)?);
+            }
+            nodes.push(node);
+        }
+
+        Ok(nodes)
+    }
+}
+
+/// Exposes `data` as `name`, and the fields of maps as `name.field`.
+fn bind(variables: &mut HashMap<String, Data>, name: &str, data: &Data) {
+    if let Data::Map(fields) = data {
+        for (field, value) in fields {
+            bind(variables, &format!("{}.{}", name, field), value);
+        }
+    }
+    variables.insert(name.to_string(), data.clone());
+}
+
+impl ComponentAction for ForEach {
+    fn setup(&mut self) -> Vec<Job> {
+        self.template
+            .iter_mut()
+            .flat_map(|child| child.setup())
+            .collect()
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) -> eyre::Result<()> {
+        for child in self.template.iter_mut() {
+            child.update(context)?;
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        let mut result = Node::new(NodeKind::Group(Group::default()));
+        for node in self.render_each(context)? {
+            result.append(node);
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,117 @@
+use std::rc::Rc;
+
+use serde::Deserialize;
+use usvg::{Group, Node, NodeKind, Path, PathData};
+
+use crate::{
+    component::{job::Job, ComponentAction, RenderContext},
+    system::{Color, Length, Paint},
+};
+
+use super::meter;
+
+/// A ring, or an arc of it, swept as far as a variable is from `min` to `max`.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Gauge {
+    size: Length,
+    thickness: Length,
+
+    min: f64,
+    max: f64,
+
+    color: Paint,
+    track_color: Option<Paint>,
+    /// In degrees, 0 pointing to the top and rotating clockwise.
+    #[serde(default = "default_start_angle")]
+    start_angle: f64,
+    #[serde(default = "default_sweep")]
+    sweep: f64,
+    #[serde(default)]
+    rounded: bool,
+
+    #[serde(default)]
+    ticks: usize,
+    tick_color: Option<Color>,
+
+    name: String,
+}
+
+const fn default_start_angle() -> f64 {
+    -135.0
+}
+
+const fn default_sweep() -> f64 {
+    270.0
+}
+
+impl ComponentAction for Gauge {
+    fn setup(&mut self) -> Vec<Job> {
+        Vec::new()
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        let size = self
+            .size
+            .translate_to_px(context.viewbox_width, context.viewbox_height);
+        let thickness = self
+            .thickness
+            .translate_to_px(context.viewbox_width, context.viewbox_height)
+            .min(size / 2.0);
+        let fraction = meter::fraction(context, &self.name, self.min, self.max)?;
+
+        let center = size / 2.0;
+        let radius = (size - thickness) / 2.0;
+        let arc = |to: f64| {
+            let mut data = PathData::new();
+            let (x, y) = meter::polar(center, center, radius, self.start_angle);
+            data.push_move_to(x, y);
+            meter::push_arc(
+                &mut data,
+                center,
+                center,
+                radius,
+                self.start_angle,
+                self.start_angle + to * self.sweep,
+            );
+            data
+        };
+
+        let mut group = Node::new(NodeKind::Group(Group::default()));
+        if let Some(bounds) = meter::bounds(size, size) {
+            group.append(bounds);
+        }
+
+        if let Some(track_color) = &self.track_color {
+            let stroke = meter::stroke(track_color, &mut group, context, thickness, self.rounded);
+            group.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(arc(1.0)),
+                stroke: Some(stroke),
+                ..Default::default()
+            })));
+        }
+
+        if fraction > 0.0 {
+            let stroke = meter::stroke(&self.color, &mut group, context, thickness, self.rounded);
+            group.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(arc(fraction)),
+                stroke: Some(stroke),
+                ..Default::default()
+            })));
+        }
+
+        if self.ticks > 0 {
+            let mut data = PathData::new();
+            for i in 0..=self.ticks {
+                let angle = self.start_angle + self.sweep * i as f64 / self.ticks as f64;
+                let (x, y) = meter::polar(center, center, radius - thickness / 2.0, angle);
+                data.push_move_to(x, y);
+                let (x, y) = meter::polar(center, center, radius + thickness / 2.0, angle);
+                data.push_line_to(x, y);
+            }
+            group.append(meter::ticks(data, &self.tick_color, context));
+        }
+
+        Ok(group)
+    }
+}
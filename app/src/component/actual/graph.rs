@@ -1,52 +1,192 @@
-use std::{collections::LinkedList, rc::Rc};
+use std::{collections::VecDeque, rc::Rc, str::FromStr};
 
 use declarrred::rt::Data;
 use serde::Deserialize;
 use tracing_unwrap::OptionExt;
-use usvg::{
-    Fill, Group, Node, NodeKind, Opacity, Paint, Path, PathData, Rect, Stroke, StrokeWidth,
-};
+use usvg::{Fill, Group, Node, NodeKind, Opacity, Path, PathData, Rect, Stroke, StrokeWidth};
 
 use crate::{
     component::{job::Job, ComponentAction, RenderContext},
-    system::{Color, Length},
+    system::{Color, Length, Paint},
 };
 
+use super::{Text, TextAlign, TextFragment};
+
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Graph {
     width: Length,
     height: Length,
 
+    #[serde(default)]
     min: f64,
-    max: f64,
+    #[serde(default)]
+    max: GraphMax,
+    /// How much of a fallen auto max is kept each frame, from 0 to 1.
+    #[serde(default)]
+    decay: f64,
     #[serde(default = "default_sample_count")]
     sample_count: usize,
+    #[serde(default)]
+    stacked: bool,
+    #[serde(default)]
+    mode: GraphMode,
+    /// Marks the latest sample of each series with a dot.
+    #[serde(default)]
+    latest_dot: bool,
+    #[serde(default = "default_dot_radius")]
+    dot_radius: f64,
 
-    stroke_color: Color,
+    /// A series given on the graph itself, drawn below the `<series>` children.
+    name: Option<String>,
+    stroke_color: Option<Color>,
+    #[serde(default = "default_stroke_width")]
     stroke_width: f64,
-
-    fill_color: Option<Color>,
+    fill_color: Option<Paint>,
     #[serde(default = "default_fill_opacity")]
     fill_opacity: f64,
 
+    /// Prints the current max at the top left corner.
+    #[serde(default)]
+    axis_label: bool,
+    label_color: Option<Color>,
+    #[serde(default = "default_label_size")]
+    label_size: f64,
+
+    #[serde(rename = "$value", default)]
+    children: Vec<GraphChild>,
+
+    #[serde(skip)]
+    series: Vec<Series>,
+    #[serde(skip)]
+    lines: Vec<GuideLine>,
+    #[serde(skip)]
+    shown_max: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub enum GraphChild {
+    #[serde(rename = "series")]
+    Series(Series),
+    #[serde(rename = "line")]
+    Line(GuideLine),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Series {
     name: String,
 
+    stroke_color: Option<Color>,
+    #[serde(default = "default_stroke_width")]
+    stroke_width: f64,
+    fill_color: Option<Paint>,
+    #[serde(default = "default_fill_opacity")]
+    fill_opacity: f64,
+
     #[serde(skip)]
-    samples: LinkedList<f64>,
+    samples: VecDeque<f64>,
+}
+
+/// How samples are connected.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GraphMode {
+    #[default]
+    Line,
+    /// A monotone cubic curve, which never overshoots the samples.
+    Smooth,
+    /// Holds each sample until the next one.
+    Step,
+    Bars,
+}
+
+/// A horizontal line at a fixed value, like a baseline or a threshold.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GuideLine {
+    value: f64,
+    color: Option<Color>,
+    #[serde(default = "default_stroke_width")]
+    width: f64,
+    #[serde(default)]
+    dashed: bool,
+}
+
+/// `auto` follows the highest sample, `nice` rounds it up to 1, 2 or 5 times a power of ten.
+#[derive(Clone, Copy, Default)]
+pub enum GraphMax {
+    #[default]
+    Auto,
+    Nice,
+    Fixed(f64),
+}
+
+impl FromStr for GraphMax {
+    type Err = std::num::ParseFloatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(GraphMax::Auto),
+            "nice" => Ok(GraphMax::Nice),
+            _ => s.parse().map(GraphMax::Fixed),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GraphMax {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        GraphMax::from_str(&s).map_err(|_| {
+            serde::de::Error::custom(format!("{} is neither auto, nice nor a number", s))
+        })
+    }
 }
 
 const fn default_sample_count() -> usize {
     10
 }
 
+const fn default_stroke_width() -> f64 {
+    1.0
+}
+
 const fn default_fill_opacity() -> f64 {
     0.6
 }
 
+const fn default_label_size() -> f64 {
+    8.0
+}
+
+const fn default_dot_radius() -> f64 {
+    2.0
+}
+
 impl ComponentAction for Graph {
     fn setup<'a>(&'a mut self) -> Vec<Job> {
-        self.samples = LinkedList::from_iter(vec![f64::NAN; self.sample_count].into_iter());
+        if let Some(name) = self.name.take() {
+            self.series.push(Series {
+                name,
+                stroke_color: self.stroke_color.take(),
+                stroke_width: self.stroke_width,
+                fill_color: self.fill_color.take(),
+                fill_opacity: self.fill_opacity,
+                samples: VecDeque::new(),
+            });
+        }
+        for child in self.children.drain(..) {
+            match child {
+                GraphChild::Series(series) => self.series.push(series),
+                GraphChild::Line(line) => self.lines.push(line),
+            }
+        }
+        for series in self.series.iter_mut() {
+            series.samples = VecDeque::from(vec![f64::NAN; self.sample_count]);
+        }
         Vec::new()
     }
 
@@ -58,41 +198,57 @@ impl ComponentAction for Graph {
             .height
             .translate_to_px(context.viewbox_width, context.viewbox_height);
 
-        let data = context
-            .variables
-            .get(&self.name)
-            .unwrap_or(&Data::Unknown)
-            .as_float()?;
-        self.samples.pop_front();
-        self.samples.push_back(data);
-
-        let mut line = PathData::new();
-        let mut fill = PathData::new();
-        let mut first_point = None;
-
-        for (i, sample) in self.samples.iter().enumerate() {
-            if sample.is_nan() {
-                continue;
-            }
+        for series in self.series.iter_mut() {
+            // Missing samples are gaps rather than zeroes.
+            let data = match context.variables.get(&series.name) {
+                None | Some(Data::Unknown) => f64::NAN,
+                Some(data) => data.as_float()?,
+            };
+            series.samples.pop_front();
+            series.samples.push_back(data);
+        }
 
-            let x = i as f64 * width_px / (self.sample_count - 1) as f64;
-            let y = height_px - (sample - self.min) / (self.max - self.min) * height_px;
-
-            if first_point.is_none() {
-                line.push_move_to(x, y);
-                fill.push_move_to(x, height_px);
-                fill.push_line_to(x, y);
-                first_point = Some((x, y));
-            } else {
-                line.push_line_to(x, y);
-                fill.push_line_to(x, y);
+        // Series are stacked onto the ones before when `stacked`, otherwise onto the minimum.
+        let mut bases = vec![self.min; self.sample_count];
+        let mut stacks = Vec::with_capacity(self.series.len());
+        for series in self.series.iter() {
+            let tops: Vec<_> = series
+                .samples
+                .iter()
+                .zip(bases.iter())
+                .map(|(sample, base)| {
+                    if !self.stacked {
+                        *sample
+                    } else if sample.is_nan() {
+                        *base
+                    } else {
+                        base + sample
+                    }
+                })
+                .collect();
+            stacks.push((bases.clone(), tops.clone()));
+            if self.stacked {
+                bases = tops;
             }
         }
 
-        fill.push_line_to(width_px, height_px);
-        if let Some((x, _)) = first_point {
-            fill.push_line_to(x, height_px);
-        }
+        let highest = stacks
+            .iter()
+            .flat_map(|(_, tops)| tops.iter().copied())
+            .filter(|top| !top.is_nan())
+            .fold(f64::NEG_INFINITY, f64::max);
+        let max = self.max(highest);
+
+        // Bars are centered in slots of their own, while lines span the whole width.
+        let (step, offset, bar_width) = match self.mode {
+            GraphMode::Bars => {
+                let slot = width_px / self.sample_count as f64;
+                (slot, slot / 2.0, slot * 0.75)
+            }
+            _ => (width_px / (self.sample_count - 1) as f64, 0.0, 0.0),
+        };
+        let x = |i: usize| offset + i as f64 * step;
+        let y = |value: f64| height_px - (value - self.min) / (max - self.min) * height_px;
 
         let mut group = Node::new(NodeKind::Group(Group::default()));
 
@@ -103,28 +259,484 @@ impl ComponentAction for Graph {
             ..Default::default()
         })));
 
-        group.append(Node::new(NodeKind::Path(Path {
-            data: Rc::new(line),
-            stroke: Some(Stroke {
-                paint: Paint::Color(self.stroke_color.to_usvg_color()),
-                width: StrokeWidth::new(self.stroke_width),
+        for (series, (bases, tops)) in self.series.iter().zip(stacks) {
+            let points: Vec<_> = series
+                .samples
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| {
+                    (!sample.is_nan()).then(|| Point {
+                        x: x(i),
+                        top: y(tops[i]),
+                        base: y(bases[i]),
+                    })
+                })
+                .collect();
+            let (line, fill) = trace(&points, self.mode, bar_width);
+
+            let stroke_color = series
+                .stroke_color
+                .as_ref()
+                .unwrap_or(&context.config.general.foreground_color)
+                .at(context.variables);
+
+            let (fill_paint, fill_opacity) = match &series.fill_color {
+                Some(fill_color) => fill_color.to_usvg_paint(&mut group, context.variables),
+                None => (
+                    usvg::Paint::Color(stroke_color.to_usvg_color()),
+                    stroke_color.to_usvg_opacity(),
+                ),
+            };
+
+            group.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(fill),
+                fill: Some(Fill {
+                    paint: fill_paint,
+                    opacity: Opacity::new(series.fill_opacity) * fill_opacity,
+                    ..Default::default()
+                }),
                 ..Default::default()
-            }),
-            ..Default::default()
-        })));
+            })));
 
-        let fill_color = self.fill_color.as_ref().unwrap_or(&self.stroke_color);
+            group.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(line),
+                stroke: Some(Stroke {
+                    paint: usvg::Paint::Color(stroke_color.to_usvg_color()),
+                    opacity: stroke_color.to_usvg_opacity(),
+                    width: StrokeWidth::new(series.stroke_width),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })));
 
-        group.append(Node::new(NodeKind::Path(Path {
-            data: Rc::new(fill),
-            fill: Some(Fill {
-                paint: Paint::Color(fill_color.to_usvg_color()),
-                opacity: Opacity::new(self.fill_opacity),
+            if let (true, Some(Some(latest))) = (self.latest_dot, points.last()) {
+                group.append(Node::new(NodeKind::Path(Path {
+                    data: Rc::new(dot(latest.x, latest.top, self.dot_radius)),
+                    fill: Some(Fill {
+                        paint: usvg::Paint::Color(stroke_color.to_usvg_color()),
+                        opacity: stroke_color.to_usvg_opacity(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })));
+            }
+        }
+
+        for line in self.lines.iter() {
+            if line.value < self.min || line.value > max {
+                continue;
+            }
+            let color = line
+                .color
+                .as_ref()
+                .unwrap_or(&context.config.general.foreground_color)
+                .at(context.variables);
+
+            let mut data = PathData::new();
+            data.push_move_to(0.0, y(line.value));
+            data.push_line_to(width_px, y(line.value));
+            group.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(data),
+                stroke: Some(Stroke {
+                    paint: usvg::Paint::Color(color.to_usvg_color()),
+                    opacity: color.to_usvg_opacity(),
+                    width: StrokeWidth::new(line.width),
+                    dasharray: line
+                        .dashed
+                        .then(|| vec![line.width * 2.0, line.width * 2.0]),
+                    ..Default::default()
+                }),
                 ..Default::default()
-            }),
-            ..Default::default()
-        })));
+            })));
+        }
+
+        if self.axis_label {
+            let mut label = Text {
+                color: self.label_color.clone(),
+                text_align: TextAlign::Left,
+                font_size: Some(self.label_size),
+                font_family: None,
+                font_weight: None,
+                content: vec![TextFragment::Text(compact(max))],
+                ..Default::default()
+            };
+            group.append(label.render(context)?);
+        }
 
         Ok(group)
     }
 }
+
+impl Graph {
+    /// The value at the top of the graph, given the highest sample shown.
+    fn max(&mut self, highest: f64) -> f64 {
+        let nice = match self.max {
+            GraphMax::Fixed(max) => return max,
+            GraphMax::Auto => false,
+            GraphMax::Nice => true,
+        };
+
+        let target = if highest > self.min {
+            highest
+        } else {
+            self.min + 1.0
+        };
+        let shown = match self.shown_max {
+            Some(shown) if shown > target => target + (shown - target) * self.decay.clamp(0.0, 1.0),
+            _ => target,
+        };
+        self.shown_max = Some(shown);
+
+        if nice {
+            self.min + nice_ceil(shown - self.min)
+        } else {
+            shown
+        }
+    }
+}
+
+/// A sample placed on the graph, its area spanning from `base` up to `top`.
+#[derive(Clone, Copy)]
+struct Point {
+    x: f64,
+    top: f64,
+    base: f64,
+}
+
+/// The line through the tops of `points`, and the area down to their bases.
+///
+/// Both break at missing samples instead of bridging them.
+fn trace(points: &[Option<Point>], mode: GraphMode, bar_width: f64) -> (PathData, PathData) {
+    let mut line = PathData::new();
+    let mut fill = PathData::new();
+
+    let runs = points
+        .split(|point| point.is_none())
+        .map(|run| run.iter().flatten().copied().collect::<Vec<_>>())
+        .filter(|run| !run.is_empty());
+    for run in runs {
+        if let GraphMode::Bars = mode {
+            for point in run {
+                let (left, right) = (point.x - bar_width / 2.0, point.x + bar_width / 2.0);
+                line.push_move_to(left, point.top);
+                line.push_line_to(right, point.top);
+
+                fill.push_move_to(left, point.top);
+                fill.push_line_to(right, point.top);
+                fill.push_line_to(right, point.base);
+                fill.push_line_to(left, point.base);
+                fill.push_close_path();
+            }
+            continue;
+        }
+
+        let tops: Vec<_> = run.iter().map(|point| (point.x, point.top)).collect();
+        let bases: Vec<_> = run.iter().map(|point| (point.x, point.base)).collect();
+        let (first, last) = (tops[0], bases[bases.len() - 1]);
+
+        line.push_move_to(first.0, first.1);
+        push_through(&mut line, &tops, mode, false);
+
+        fill.push_move_to(first.0, first.1);
+        push_through(&mut fill, &tops, mode, false);
+        fill.push_line_to(last.0, last.1);
+        push_through(&mut fill, &bases, mode, true);
+        fill.push_close_path();
+    }
+
+    (line, fill)
+}
+
+/// Continues `data`, which is at the first of `points` (the last when `reverse`), through the rest.
+fn push_through(data: &mut PathData, points: &[(f64, f64)], mode: GraphMode, reverse: bool) {
+    let tangents = match mode {
+        GraphMode::Smooth => monotone_tangents(points),
+        _ => Vec::new(),
+    };
+
+    let mut segments: Vec<_> = (1..points.len()).map(|i| (i - 1, i)).collect();
+    if reverse {
+        segments = segments
+            .into_iter()
+            .rev()
+            .map(|(from, to)| (to, from))
+            .collect();
+    }
+    for (from, to) in segments {
+        let ((x0, y0), (x1, y1)) = (points[from], points[to]);
+        match mode {
+            GraphMode::Smooth => {
+                let h = (x1 - x0) / 3.0;
+                data.push_curve_to(
+                    x0 + h,
+                    y0 + tangents[from] * h,
+                    x1 - h,
+                    y1 - tangents[to] * h,
+                    x1,
+                    y1,
+                );
+            }
+            // The sample on the left holds until the one on the right.
+            GraphMode::Step if reverse => {
+                data.push_line_to(x0, y1);
+                data.push_line_to(x1, y1);
+            }
+            GraphMode::Step => {
+                data.push_line_to(x1, y0);
+                data.push_line_to(x1, y1);
+            }
+            GraphMode::Line | GraphMode::Bars => data.push_line_to(x1, y1),
+        }
+    }
+}
+
+/// Slopes at each point keeping a cubic Hermite spline monotone, by Fritsch and Carlson.
+fn monotone_tangents(points: &[(f64, f64)]) -> Vec<f64> {
+    let n = points.len();
+    if n < 2 {
+        return vec![0.0; n];
+    }
+
+    let secants: Vec<_> = points
+        .windows(2)
+        .map(|pair| (pair[1].1 - pair[0].1) / (pair[1].0 - pair[0].0))
+        .collect();
+    let mut tangents = vec![0.0; n];
+    tangents[0] = secants[0];
+    tangents[n - 1] = secants[n - 2];
+    for i in 1..n - 1 {
+        if secants[i - 1] * secants[i] > 0.0 {
+            tangents[i] = (secants[i - 1] + secants[i]) / 2.0;
+        }
+    }
+
+    for (i, secant) in secants.iter().enumerate() {
+        if *secant == 0.0 {
+            tangents[i] = 0.0;
+            tangents[i + 1] = 0.0;
+            continue;
+        }
+        let (a, b) = (tangents[i] / secant, tangents[i + 1] / secant);
+        let length = a.hypot(b);
+        if length > 3.0 {
+            tangents[i] = 3.0 / length * a * secant;
+            tangents[i + 1] = 3.0 / length * b * secant;
+        }
+    }
+
+    tangents
+}
+
+fn dot(x: f64, y: f64, radius: f64) -> PathData {
+    let mut data = PathData::new();
+    data.push_move_to(x - radius, y);
+    data.push_arc_to(radius, radius, 0.0, false, true, x + radius, y);
+    data.push_arc_to(radius, radius, 0.0, false, true, x - radius, y);
+    data.push_close_path();
+    data
+}
+
+/// Rounds up to 1, 2 or 5 times a power of ten.
+fn nice_ceil(value: f64) -> f64 {
+    let magnitude = 10f64.powf(value.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|step| step * magnitude)
+        .find(|nice| *nice >= value)
+        .unwrap_or(value)
+}
+
+/// Like `1.5k`, to fit in a tiny label.
+fn compact(value: f64) -> String {
+    let (value, suffix) = [(1e12, "T"), (1e9, "G"), (1e6, "M"), (1e3, "k")]
+        .into_iter()
+        .find(|(unit, _)| value.abs() >= *unit)
+        .map(|(unit, suffix)| (value / unit, suffix))
+        .unwrap_or((value, ""));
+    let digits = format!("{:.1}", value);
+    format!("{}{}", digits.trim_end_matches(".0"), suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use usvg::PathSegment;
+
+    use super::*;
+
+    fn point(x: f64, top: f64) -> Option<Point> {
+        Some(Point { x, top, base: 10.0 })
+    }
+
+    /// Each segment as its command and end point.
+    fn ends(data: &PathData) -> Vec<(char, f64, f64)> {
+        data.iter()
+            .map(|segment| match *segment {
+                PathSegment::MoveTo { x, y } => ('M', x, y),
+                PathSegment::LineTo { x, y } => ('L', x, y),
+                PathSegment::CurveTo { x, y, .. } => ('C', x, y),
+                PathSegment::ClosePath => ('Z', 0.0, 0.0),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn line_fills_down_to_bases() {
+        let (line, fill) = trace(
+            &[point(0.0, 4.0), point(1.0, 2.0), point(2.0, 6.0)],
+            GraphMode::Line,
+            0.0,
+        );
+
+        assert_eq!(
+            ends(&line),
+            [('M', 0.0, 4.0), ('L', 1.0, 2.0), ('L', 2.0, 6.0)]
+        );
+        assert_eq!(
+            ends(&fill),
+            [
+                ('M', 0.0, 4.0),
+                ('L', 1.0, 2.0),
+                ('L', 2.0, 6.0),
+                ('L', 2.0, 10.0),
+                ('L', 1.0, 10.0),
+                ('L', 0.0, 10.0),
+                ('Z', 0.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn gaps_break_line_and_fill() {
+        let (line, fill) = trace(
+            &[
+                point(0.0, 4.0),
+                point(1.0, 2.0),
+                None,
+                point(3.0, 6.0),
+                point(4.0, 8.0),
+            ],
+            GraphMode::Line,
+            0.0,
+        );
+
+        assert_eq!(
+            ends(&line),
+            [
+                ('M', 0.0, 4.0),
+                ('L', 1.0, 2.0),
+                ('M', 3.0, 6.0),
+                ('L', 4.0, 8.0),
+            ]
+        );
+        let fill = ends(&fill);
+        assert_eq!(fill.iter().filter(|(c, ..)| *c == 'M').count(), 2);
+        assert_eq!(fill.iter().filter(|(c, ..)| *c == 'Z').count(), 2);
+        assert!(fill.iter().all(|&(_, x, _)| x != 2.0));
+    }
+
+    #[test]
+    fn missing_samples_draw_nothing() {
+        let (line, fill) = trace(&[None, None, None], GraphMode::Smooth, 0.0);
+
+        assert!(line.is_empty());
+        assert!(fill.is_empty());
+    }
+
+    #[test]
+    fn step_holds_until_next_sample() {
+        let (line, fill) = trace(
+            &[point(0.0, 4.0), point(1.0, 2.0), point(2.0, 6.0)],
+            GraphMode::Step,
+            0.0,
+        );
+
+        assert_eq!(
+            ends(&line),
+            [
+                ('M', 0.0, 4.0),
+                ('L', 1.0, 4.0),
+                ('L', 1.0, 2.0),
+                ('L', 2.0, 2.0),
+                ('L', 2.0, 6.0),
+            ]
+        );
+        // Flat bases step back onto themselves.
+        assert!(ends(&fill)[5..9].iter().all(|&(_, _, y)| y == 10.0));
+    }
+
+    #[test]
+    fn smooth_passes_through_samples_without_overshooting() {
+        let samples = [
+            point(0.0, 9.0),
+            point(1.0, 8.0),
+            point(2.0, 1.0),
+            point(3.0, 1.0),
+            point(4.0, 5.0),
+        ];
+        let (line, _) = trace(&samples, GraphMode::Smooth, 0.0);
+
+        assert_eq!(line.len(), samples.len());
+        for (segment, pair) in line.iter().skip(1).zip(samples.windows(2)) {
+            let (from, to) = (pair[0].unwrap(), pair[1].unwrap());
+            let (low, high) = (from.top.min(to.top), from.top.max(to.top));
+            match *segment {
+                PathSegment::CurveTo { y1, y2, x, y, .. } => {
+                    assert_eq!((x, y), (to.x, to.top));
+                    assert!(
+                        (low..=high).contains(&y1),
+                        "{} not in {}..={}",
+                        y1,
+                        low,
+                        high
+                    );
+                    assert!(
+                        (low..=high).contains(&y2),
+                        "{} not in {}..={}",
+                        y2,
+                        low,
+                        high
+                    );
+                }
+                _ => panic!("Expected a curve, got {:?}", segment),
+            }
+        }
+    }
+
+    #[test]
+    fn bars_are_separate_rectangles() {
+        let (line, fill) = trace(
+            &[point(1.0, 4.0), None, point(3.0, 6.0)],
+            GraphMode::Bars,
+            1.0,
+        );
+
+        assert_eq!(
+            ends(&line),
+            [
+                ('M', 0.5, 4.0),
+                ('L', 1.5, 4.0),
+                ('M', 2.5, 6.0),
+                ('L', 3.5, 6.0),
+            ]
+        );
+        assert_eq!(
+            ends(&fill)[..5],
+            [
+                ('M', 0.5, 4.0),
+                ('L', 1.5, 4.0),
+                ('L', 1.5, 10.0),
+                ('L', 0.5, 10.0),
+                ('Z', 0.0, 0.0),
+            ]
+        );
+        assert_eq!(fill.len(), 10);
+    }
+
+    #[test]
+    fn nice_ceil_rounds_up() {
+        assert_eq!(nice_ceil(73.0), 100.0);
+        assert_eq!(nice_ceil(1.5), 2.0);
+        assert_eq!(nice_ceil(300.0), 500.0);
+        assert_eq!(nice_ceil(1000.0), 1000.0);
+    }
+}
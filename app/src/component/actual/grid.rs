@@ -0,0 +1,111 @@
+use serde::Deserialize;
+use usvg::Node;
+
+use crate::{
+    component::{job::Job, Component, ComponentAction, RenderContext, UpdateContext},
+    system::{Align, Color, GridTracks, Insets, Length, Paint, Track, TrackList},
+};
+
+use super::{
+    arrange::{arrange_cells, size_limit, Arrangement},
+    decoration::Decoration,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Grid {
+    /// One `auto` column when not given.
+    columns: Option<TrackList>,
+    /// As many `auto` rows as the cells need when not given.
+    rows: Option<TrackList>,
+    /// Space between the columns and between the rows, unless `column-gap` or `row-gap` is given.
+    gap: Option<Length>,
+    column_gap: Option<Length>,
+    row_gap: Option<Length>,
+    #[serde(default)]
+    justify: Align,
+    #[serde(default)]
+    align: Align,
+    /// Sizes of the grid, padding and border included.
+    width: Option<Length>,
+    height: Option<Length>,
+    min_width: Option<Length>,
+    max_width: Option<Length>,
+    min_height: Option<Length>,
+    max_height: Option<Length>,
+    padding: Option<Insets>,
+    background: Option<Paint>,
+    border_color: Option<Color>,
+    border_width: Option<Length>,
+    corner_radius: Option<Length>,
+
+    #[serde(rename = "$value")]
+    children: Vec<Component>,
+}
+
+impl ComponentAction for Grid {
+    fn setup(&mut self) -> Vec<Job> {
+        self.children
+            .iter_mut()
+            .flat_map(|child| child.setup())
+            .collect()
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) -> eyre::Result<()> {
+        for child in self.children.iter_mut() {
+            child.update(context)?;
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        Ok(self.measure(context)?.place(None, context)?.0)
+    }
+}
+
+impl Grid {
+    /// Measures the children, to be laid out once the grid is given its size.
+    pub(super) fn measure(&mut self, context: &RenderContext) -> eyre::Result<Arrangement<'_>> {
+        let decoration = Decoration {
+            padding: self.padding.as_ref(),
+            background: self.background.as_ref(),
+            border_color: self.border_color.as_ref(),
+            border_width: self.border_width.as_ref(),
+            corner_radius: self.corner_radius.as_ref(),
+        };
+        let [top, right, bottom, left] = decoration.edges(context);
+
+        let px = |length: Option<&Length>| {
+            length.map_or(0.0, |length| {
+                length.translate_to_px(context.viewbox_width, context.viewbox_height)
+            })
+        };
+        let tracks = |tracks: &Option<TrackList>| {
+            tracks
+                .as_ref()
+                .map(|tracks| tracks.translate_to_px(context.viewbox_width, context.viewbox_height))
+        };
+        let grid = GridTracks {
+            column_gap: px(self.column_gap.as_ref().or(self.gap.as_ref())),
+            row_gap: px(self.row_gap.as_ref().or(self.gap.as_ref())),
+            justify: self.justify,
+            align: self.align,
+            width: size_limit(
+                [&self.width, &self.min_width, &self.max_width],
+                left + right,
+                context,
+            ),
+            height: size_limit(
+                [&self.height, &self.min_height, &self.max_height],
+                top + bottom,
+                context,
+            ),
+            ..GridTracks::new(
+                tracks(&self.columns).unwrap_or_else(|| vec![Track::Auto]),
+                tracks(&self.rows).unwrap_or_default(),
+            )
+        };
+
+        arrange_cells(&mut self.children, grid, false, decoration, context)
+    }
+}
@@ -1,17 +1,40 @@
-use std::rc::Rc;
-
 use serde::Deserialize;
-use usvg::{Group, Node, NodeExt, NodeKind, Path, PathData, Rect, Transform};
+use usvg::Node;
 
 use crate::{
     component::{job::Job, Component, ComponentAction, RenderContext, UpdateContext},
-    system::VerticalAlignment,
+    system::{Align, Color, Direction, Flex, Insets, Justify, Length, Paint, VerticalAlignment},
+};
+
+use super::{
+    arrange::{arrange, size_limit, Arrangement},
+    decoration::Decoration,
 };
 
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct HBox {
+    #[serde(default)]
+    justify: Justify,
+    align: Option<Align>,
+    /// Older name of `align`.
     y_align: Option<VerticalAlignment>,
+    #[serde(default)]
+    wrap: bool,
+    /// Space between the children, on top of `<margin>`s, and between lines.
+    gap: Option<Length>,
+    /// Sizes of the box, padding and border included.
+    width: Option<Length>,
+    height: Option<Length>,
+    min_width: Option<Length>,
+    max_width: Option<Length>,
+    min_height: Option<Length>,
+    max_height: Option<Length>,
+    padding: Option<Insets>,
+    background: Option<Paint>,
+    border_color: Option<Color>,
+    border_width: Option<Length>,
+    corner_radius: Option<Length>,
 
     #[serde(rename = "$value")]
     children: Vec<Component>,
@@ -33,77 +56,46 @@ impl ComponentAction for HBox {
     }
 
     fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
-        let mut last_x_mod = 0.0;
-        let mut x = 0.0;
-        let mut container_height = 0.0;
-        let mut nodes = Vec::new();
-        let mut result = Node::new(NodeKind::Group(Group::default()));
-        for child in self.children.iter_mut() {
-            match child {
-                Component::Margin { size } => {
-                    let size = size.translate_to_px(context.viewbox_width, context.viewbox_height);
-                    x += size;
-                    last_x_mod = size;
-                }
-                Component::SetPosition { to } => {
-                    let to = to.translate_to_px(context.viewbox_width, context.viewbox_height);
-                    x = to;
-                    last_x_mod = 0.0;
-                }
-                Component::Overlap { child } => {
-                    let child_node = child.render(context)?;
-                    let bbox = child_node.calculate_bbox().unwrap();
-
-                    nodes.push((x - last_x_mod, child_node));
-
-                    x += f64::max(bbox.width() - last_x_mod, 0.0);
-                    container_height = f64::max(container_height, bbox.height());
-
-                    last_x_mod = f64::max(bbox.width(), last_x_mod);
-                }
-                _ => {
-                    let child_node = child.render(context)?;
-                    let bbox = child_node.calculate_bbox().unwrap();
-
-                    nodes.push((x, child_node));
-
-                    let dx = bbox.right();
-
-                    x += dx;
-                    container_height = f64::max(container_height, bbox.height());
-
-                    last_x_mod = dx;
-                }
-            }
-        }
-
-        result.append(Node::new(NodeKind::Path({
-            let mut path = Path::default();
-            path.data = Rc::new(PathData::from_rect(
-                Rect::new(0.0, 0.0, x, container_height).unwrap(),
-            ));
-            path
-        })));
-
-        for (x, node) in nodes {
-            let bbox = node.calculate_bbox().unwrap();
-
-            let mut child_transformer = Node::new(NodeKind::Group({
-                let mut group = Group::default();
-                group.transform = Transform::new_translate(
-                    x,
-                    self.y_align
-                        .as_ref()
-                        .unwrap_or(&VerticalAlignment::Top)
-                        .align(container_height, bbox.height()),
-                );
-                group
-            }));
-            child_transformer.append(node);
-
-            result.append(child_transformer);
-        }
+        Ok(self.measure(context)?.place(None, context)?.0)
+    }
+}
 
-        Ok(result)
+impl HBox {
+    /// Measures the children, to be laid out once the box is given its size.
+    pub(super) fn measure(&mut self, context: &RenderContext) -> eyre::Result<Arrangement<'_>> {
+        let decoration = Decoration {
+            padding: self.padding.as_ref(),
+            background: self.background.as_ref(),
+            border_color: self.border_color.as_ref(),
+            border_width: self.border_width.as_ref(),
+            corner_radius: self.corner_radius.as_ref(),
+        };
+        let [top, right, bottom, left] = decoration.edges(context);
+
+        let flex = Flex {
+            justify: self.justify,
+            align: self.align.unwrap_or(match self.y_align {
+                None | Some(VerticalAlignment::Top) => Align::Start,
+                Some(VerticalAlignment::Center) => Align::Center,
+                Some(VerticalAlignment::Bottom) => Align::End,
+            }),
+            gap: self.gap.as_ref().map_or(0.0, |gap| {
+                gap.translate_to_px(context.viewbox_width, context.viewbox_height)
+            }),
+            wrap: self.wrap,
+            width: size_limit(
+                [&self.width, &self.min_width, &self.max_width],
+                left + right,
+                context,
+            ),
+            height: size_limit(
+                [&self.height, &self.min_height, &self.max_height],
+                top + bottom,
+                context,
+            ),
+            ..Flex::new(Direction::Row)
+        };
+
+        arrange(&mut self.children, flex, decoration, context)
     }
 }
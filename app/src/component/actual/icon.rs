@@ -0,0 +1,94 @@
+use declarrred::rt::Data;
+use serde::Deserialize;
+use usvg::Node;
+
+use crate::{
+    component::{job::Job, ComponentAction, RenderContext},
+    system::{Color, IconSet},
+};
+
+use super::{Text, TextAlign, TextFragment};
+
+/// A glyph of an icon font by its name, or the one of a level picked by a variable.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Icon {
+    #[serde(default = "default_set")]
+    set: String,
+    /// Shown alone, or while `value` is not known.
+    name: Option<String>,
+
+    /// A numeric variable picking one of `levels`, spread evenly from `min` to `max`.
+    value: Option<String>,
+    /// Glyph names from the lowest level to the highest, separated by spaces.
+    #[serde(default)]
+    levels: String,
+    #[serde(default)]
+    min: f64,
+    #[serde(default = "default_max")]
+    max: f64,
+
+    color: Option<Color>,
+    font_size: Option<f64>,
+    /// Overrides the font family of the set.
+    font_family: Option<String>,
+
+    /// Kept between frames, only its glyph and font changing.
+    #[serde(skip)]
+    text: Option<Text>,
+}
+
+fn default_set() -> String {
+    "nerd".to_string()
+}
+
+const fn default_max() -> f64 {
+    100.0
+}
+
+impl Icon {
+    fn glyph_name(&self, context: &RenderContext) -> eyre::Result<&str> {
+        let levels: Vec<_> = self.levels.split_whitespace().collect();
+        let value = self
+            .value
+            .as_ref()
+            .and_then(|value| context.variables.get(value))
+            .filter(|data| !matches!(data, Data::Unknown));
+
+        match (value, levels.is_empty()) {
+            (Some(value), false) => {
+                let fraction =
+                    ((value.as_float()? - self.min) / (self.max - self.min)).clamp(0.0, 1.0);
+                let index = (fraction * levels.len() as f64) as usize;
+                Ok(levels[index.min(levels.len() - 1)])
+            }
+            _ => self
+                .name
+                .as_deref()
+                .ok_or_else(|| eyre::eyre!("<icon> requires name, or value and levels")),
+        }
+    }
+}
+
+impl ComponentAction for Icon {
+    fn setup(&mut self) -> Vec<Job> {
+        Vec::new()
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        let name = self.glyph_name(context)?;
+        let (glyph, font_family) = IconSet::resolve(&self.set, name)
+            .ok_or_else(|| eyre::eyre!("There are no icon named {} in {}", name, self.set))?;
+
+        let text = self.text.get_or_insert_with(|| Text {
+            color: self.color.clone(),
+            text_align: TextAlign::Left,
+            font_size: self.font_size,
+            font_weight: None,
+            ..Default::default()
+        });
+        text.font_family = self.font_family.clone().or(font_family);
+        text.content = vec![TextFragment::Text(glyph.to_string())];
+        text.render(context)
+    }
+}
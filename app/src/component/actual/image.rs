@@ -0,0 +1,263 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex, Weak},
+};
+
+use serde::Deserialize;
+use tracing_unwrap::OptionExt;
+use url::Url;
+use usvg::{
+    Align, AspectRatio, Color as UsvgColor, Group, ImageKind, ImageRendering, Node, NodeKind,
+    Opacity, Options, Path, PathData, Rect, Transform, Tree, ViewBox, Visibility,
+};
+
+use crate::{
+    component::{job::Job, ComponentAction, RenderContext},
+    system::{Color, Length},
+    util::load::load_job,
+};
+
+/// A PNG, JPEG, GIF or SVG image.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Image {
+    /// Paths relative to the widget are turned into `file:` URLs while loading.
+    src: Url,
+
+    width: Length,
+    height: Length,
+    #[serde(default)]
+    fit: ImageFit,
+
+    /// Paints every shape of an SVG image, for monochrome icons.
+    tint: Option<Color>,
+
+    #[serde(skip)]
+    loaded: Arc<Mutex<Option<LoadedImage>>>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageFit {
+    /// Scales the image to fit in, keeping its aspect ratio.
+    #[default]
+    Contain,
+    /// Stretches the image to the size.
+    Fill,
+}
+
+enum LoadedImage {
+    Png(Arc<Vec<u8>>),
+    Jpeg(Arc<Vec<u8>>),
+    Gif(Arc<Vec<u8>>),
+    Svg(Arc<Vec<u8>>),
+}
+
+thread_local! {
+    /// SVG images parsed on the thread rendering them, as their trees can't leave it.
+    static SVG_IMAGES: RefCell<Vec<SvgImage>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A parsed SVG image, with the paint it was last tinted with.
+struct SvgImage {
+    /// Gone once the image is dropped.
+    data: Weak<Vec<u8>>,
+    tree: Tree,
+    tint: Option<(UsvgColor, Opacity)>,
+}
+
+impl LoadedImage {
+    fn parse(data: Vec<u8>, options: &Options) -> eyre::Result<Self> {
+        Ok(match data.as_slice() {
+            [0x89, b'P', b'N', b'G', ..] => LoadedImage::Png(Arc::new(data)),
+            [0xFF, 0xD8, 0xFF, ..] => LoadedImage::Jpeg(Arc::new(data)),
+            [b'G', b'I', b'F', b'8', ..] => LoadedImage::Gif(Arc::new(data)),
+            _ => {
+                // Only to report a malformed image while setting up.
+                Tree::from_data(&data, &options.to_ref())?;
+                LoadedImage::Svg(Arc::new(data))
+            }
+        })
+    }
+}
+
+impl SvgImage {
+    /// The tree of `data` tinted with `tint`, parsed the first time it is asked for on this
+    /// thread.
+    fn tree(data: &Arc<Vec<u8>>, tint: Option<Color>, options: &Options) -> eyre::Result<Tree> {
+        SVG_IMAGES.with_borrow_mut(|images| {
+            images.retain(|image| image.data.strong_count() > 0);
+            let index = match images
+                .iter()
+                .position(|image| image.data.as_ptr() == Arc::as_ptr(data))
+            {
+                Some(index) => index,
+                None => {
+                    images.push(SvgImage {
+                        data: Arc::downgrade(data),
+                        tree: Tree::from_data(data, &options.to_ref())?,
+                        tint: None,
+                    });
+                    images.len() - 1
+                }
+            };
+            let image = &mut images[index];
+            if let Some(color) = tint {
+                image.tint(data, color, options)?;
+            }
+            Ok(image.tree.clone())
+        })
+    }
+
+    /// Tints the tree with `color`, parsing `data` again if it was tinted with another one, as
+    /// tinting multiplies the opacity.
+    fn tint(&mut self, data: &[u8], color: Color, options: &Options) -> eyre::Result<()> {
+        let paint = (color.to_usvg_color(), color.to_usvg_opacity());
+        if self.tint == Some(paint) {
+            return Ok(());
+        }
+        if self.tint.is_some() {
+            self.tree = Tree::from_data(data, &options.to_ref())?;
+        }
+        tint(&self.tree, color);
+        self.tint = Some(paint);
+        Ok(())
+    }
+}
+
+impl ComponentAction for Image {
+    fn setup(&mut self) -> Vec<Job> {
+        let loaded = self.loaded.clone();
+        vec![load_job(self.src.clone(), move |data, context| {
+            let image = LoadedImage::parse(data, &context.usvg_options)?;
+            if let Ok(mut loaded) = loaded.lock() {
+                *loaded = Some(image);
+            }
+            Ok(())
+        })]
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        let width = self
+            .width
+            .translate_to_px(context.viewbox_width, context.viewbox_height);
+        let height = self
+            .height
+            .translate_to_px(context.viewbox_width, context.viewbox_height);
+        let rect = Rect::new(0.0, 0.0, width, height).unwrap_or_log();
+
+        let mut group = Node::new(NodeKind::Group(Group::default()));
+        group.append(Node::new(NodeKind::Path(Path {
+            data: Rc::new(PathData::from_rect(rect)),
+            ..Default::default()
+        })));
+
+        let loaded = match self.loaded.lock() {
+            Ok(loaded) => loaded,
+            Err(_) => eyre::bail!("Image lock poisoned"),
+        };
+        let kind = match &*loaded {
+            Some(LoadedImage::Png(data)) => ImageKind::PNG(data.clone()),
+            Some(LoadedImage::Jpeg(data)) => ImageKind::JPEG(data.clone()),
+            Some(LoadedImage::Gif(data)) => ImageKind::GIF(data.clone()),
+            Some(LoadedImage::Svg(data)) => ImageKind::SVG(SvgImage::tree(
+                data,
+                self.tint.as_ref().map(|tint| tint.at(context.variables)),
+                context.usvg_options,
+            )?),
+            // Failed to load, which has been reported while setting up.
+            None => return Ok(group),
+        };
+
+        group.append(Node::new(NodeKind::Image(usvg::Image {
+            id: String::new(),
+            transform: Transform::default(),
+            visibility: Visibility::Visible,
+            view_box: ViewBox {
+                rect,
+                aspect: AspectRatio {
+                    defer: false,
+                    align: match self.fit {
+                        ImageFit::Contain => Align::XMidYMid,
+                        ImageFit::Fill => Align::None,
+                    },
+                    slice: false,
+                },
+            },
+            rendering_mode: ImageRendering::default(),
+            kind,
+        })));
+
+        Ok(group)
+    }
+}
+
+/// Replaces the paint of every fill and stroke with `color`, keeping their opacity.
+fn tint(tree: &Tree, color: Color) {
+    for mut node in tree.root().descendants() {
+        if let NodeKind::Path(ref mut path) = *node.borrow_mut() {
+            if let Some(fill) = path.fill.as_mut() {
+                fill.paint = usvg::Paint::Color(color.to_usvg_color());
+                fill.opacity = fill.opacity * color.to_usvg_opacity();
+            }
+            if let Some(stroke) = path.stroke.as_mut() {
+                stroke.paint = usvg::Paint::Color(color.to_usvg_color());
+                stroke.opacity = stroke.opacity * color.to_usvg_opacity();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const SVG: &[u8] = br##"<svg xmlns="http://www.w3.org/2000/svg" width="2" height="2">
+        <rect width="2" height="2" fill="#ff0000" fill-opacity="0.5"/>
+    </svg>"##;
+
+    fn fill(tree: &Tree) -> (UsvgColor, f64) {
+        let path = tree
+            .root()
+            .descendants()
+            .find_map(|node| match &*node.borrow() {
+                NodeKind::Path(path) => path.fill.clone(),
+                _ => None,
+            })
+            .unwrap();
+        match path.paint {
+            usvg::Paint::Color(color) => (color, path.opacity.value()),
+            _ => panic!("Expected a color"),
+        }
+    }
+
+    #[test]
+    fn tints_once_per_color_and_forgets_dropped_images() {
+        let options = Options::default();
+        let data = Arc::new(SVG.to_vec());
+        let green = Color::from_str("#00ff0080").unwrap();
+        let blue = Color::from_str("#0000ff").unwrap();
+
+        let tree = SvgImage::tree(&data, None, &options).unwrap();
+        assert_eq!(fill(&tree), (UsvgColor::new_rgb(255, 0, 0), 0.5));
+
+        let tree = SvgImage::tree(&data, Some(green.clone()), &options).unwrap();
+        let (color, opacity) = fill(&tree);
+        assert_eq!(color, UsvgColor::new_rgb(0, 255, 0));
+        assert!((opacity - 0.5 * 128.0 / 255.0).abs() < 1e-6);
+        let again = SvgImage::tree(&data, Some(green), &options).unwrap();
+        assert!((fill(&again).1 - opacity).abs() < 1e-6);
+
+        let tree = SvgImage::tree(&data, Some(blue), &options).unwrap();
+        assert_eq!(fill(&tree), (UsvgColor::new_rgb(0, 0, 255), 0.5));
+        SVG_IMAGES.with_borrow(|images| assert_eq!(images.len(), 1));
+
+        drop(data);
+        let other = Arc::new(SVG.to_vec());
+        SvgImage::tree(&other, None, &options).unwrap();
+        SVG_IMAGES.with_borrow(|images| assert_eq!(images.len(), 1));
+    }
+}
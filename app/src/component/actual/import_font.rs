@@ -1,13 +1,12 @@
 use std::io::Cursor;
 
-use async_stream::try_stream;
 use serde::Deserialize;
 use url::Url;
 
 use crate::component::job::Job;
-use crate::util::http;
+use crate::util::load::load_job;
 
-use crate::component::{ComponentAction, JobStage};
+use crate::component::ComponentAction;
 
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -24,46 +23,14 @@ pub enum FontImportStage {
 
 impl ComponentAction for ImportFont {
     fn setup(&mut self) -> Vec<Job> {
-        let url = self.url.clone();
-        vec![Box::pin(try_stream! {
-            let data = match url.scheme() {
-                "http" | "https" => {
-                    yield JobStage::Progress {
-                        label: format!("Reading {} from online...", url),
-                        value: 0.0
-                    };
-                    http::get(&url).await.map_err(|_| eyre::eyre!("Failed to request {}", url))?
-                }
-                "file" => {
-                    yield JobStage::Progress {
-                        label: format!("Reading {} from file...", url),
-                        value: 0.0
-                    };
-                    std::fs::read(
-                        url
-                            .to_file_path()
-                            .map_err(|_| eyre::eyre!("Failed to convert {} into path.", url))?,
-                    )?
-                }
-                scheme => {
-                    yield JobStage::Fail {
-                        label: format!("Unsupported url scheme: {}", scheme)
-                    };
-                    return
-                },
+        vec![load_job(self.url.clone(), |data, context| {
+            let data = if woff2_patched::decode::is_woff2(&data) {
+                woff2_patched::convert_woff2_to_ttf(&mut Cursor::new(data))?
+            } else {
+                data
             };
-            yield JobStage::Completed {
-                label: format!("Loaded {}!", url),
-                finalizer: Box::new(move |context| {
-                    let data = if woff2_patched::decode::is_woff2(&data) {
-                        woff2_patched::convert_woff2_to_ttf(&mut Cursor::new(data))?
-                    } else {
-                        data
-                    };
-                    context.usvg_options.fontdb.load_font_data(data);
-                    Ok(())
-                })
-            }
+            context.usvg_options.fontdb.load_font_data(data);
+            Ok(())
         })]
     }
 }
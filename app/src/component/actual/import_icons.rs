@@ -0,0 +1,30 @@
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    component::{job::Job, ComponentAction},
+    system::IconSet,
+    util::load::load_job,
+};
+
+/// Adds the glyph names of a map like the `glyphnames.json` of Nerd Fonts to an icon set.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ImportIcons {
+    set: String,
+    url: Url,
+    font_family: Option<String>,
+}
+
+impl ComponentAction for ImportIcons {
+    fn setup(&mut self) -> Vec<Job> {
+        let set = self.set.clone();
+        let font_family = self.font_family.clone();
+        vec![load_job(self.url.clone(), move |data, _| {
+            let mut icons = IconSet::parse(&data)?;
+            icons.font_family = font_family;
+            icons.install(&set);
+            Ok(())
+        })]
+    }
+}
@@ -0,0 +1,83 @@
+use serde::Deserialize;
+use usvg::{Group, Node, NodeKind};
+
+use crate::{
+    component::{job::Job, Component, ComponentAction, RenderContext, UpdateContext},
+    system::{Align, FlexItem, Length},
+};
+
+use super::arrange::{size_limit, Measured};
+
+/// A child of `<hbox>` or `<vbox>` with how it grows, shrinks and aligns, or space without a child.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Item {
+    #[serde(default)]
+    grow: f64,
+    #[serde(default = "default_shrink")]
+    shrink: f64,
+    width: Option<Length>,
+    height: Option<Length>,
+    min_width: Option<Length>,
+    max_width: Option<Length>,
+    min_height: Option<Length>,
+    max_height: Option<Length>,
+    align: Option<Align>,
+
+    #[serde(rename = "$value")]
+    child: Option<Box<Component>>,
+}
+
+fn default_shrink() -> f64 {
+    1.0
+}
+
+impl Item {
+    /// Measures the child, with how it is laid out.
+    pub(super) fn measure(
+        &mut self,
+        context: &RenderContext,
+    ) -> eyre::Result<(Measured<'_>, FlexItem)> {
+        let child = Measured::maybe(self.child.as_deref_mut(), context)?;
+        let (width, height) = child.size();
+        let item = FlexItem {
+            grow: self.grow,
+            shrink: self.shrink,
+            width_limit: size_limit(
+                [&self.width, &self.min_width, &self.max_width],
+                0.0,
+                context,
+            ),
+            height_limit: size_limit(
+                [&self.height, &self.min_height, &self.max_height],
+                0.0,
+                context,
+            ),
+            align: self.align,
+            ..FlexItem::new(width, height)
+        };
+        Ok((child, item))
+    }
+}
+
+impl ComponentAction for Item {
+    fn setup(&mut self) -> Vec<Job> {
+        self.child
+            .as_mut()
+            .map_or_else(Vec::new, |child| child.setup())
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) -> eyre::Result<()> {
+        match &mut self.child {
+            Some(child) => child.update(context),
+            None => Ok(()),
+        }
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        match &mut self.child {
+            Some(child) => child.render(context),
+            None => Ok(Node::new(NodeKind::Group(Group::default()))),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! What `<bar>`, `<gauge>` and `<pie>` share: a variable from `min` to `max`, drawn as a fraction.
+
+use std::rc::Rc;
+
+use declarrred::rt::Data;
+use usvg::{Fill, LineCap, Node, NodeKind, Path, PathData, Rect, Stroke, StrokeWidth};
+
+use crate::{
+    component::RenderContext,
+    system::{Color, Paint},
+};
+
+/// How far the variable `name` is from `min` to `max`, clamped into `0..=1`.
+pub(super) fn fraction(
+    context: &RenderContext,
+    name: &str,
+    min: f64,
+    max: f64,
+) -> eyre::Result<f64> {
+    let value = context
+        .variables
+        .get(name)
+        .unwrap_or(&Data::Unknown)
+        .as_float()?;
+    let fraction = (value - min) / (max - min);
+
+    Ok(if fraction.is_nan() {
+        0.0
+    } else {
+        fraction.clamp(0.0, 1.0)
+    })
+}
+
+/// An unpainted rectangle, so that the size does not depend on the value.
+pub(super) fn bounds(width: f64, height: f64) -> Option<Node> {
+    Some(Node::new(NodeKind::Path(Path {
+        data: Rc::new(PathData::from_rect(Rect::new(0.0, 0.0, width, height)?)),
+        ..Default::default()
+    })))
+}
+
+pub(super) fn fill(paint: &Paint, parent: &mut Node, context: &RenderContext) -> Fill {
+    let (paint, opacity) = paint.to_usvg_paint(parent, context.variables);
+    Fill {
+        paint,
+        opacity,
+        ..Default::default()
+    }
+}
+
+pub(super) fn stroke(
+    paint: &Paint,
+    parent: &mut Node,
+    context: &RenderContext,
+    width: f64,
+    rounded: bool,
+) -> Stroke {
+    let (paint, opacity) = paint.to_usvg_paint(parent, context.variables);
+    Stroke {
+        paint,
+        opacity,
+        width: StrokeWidth::new(width),
+        linecap: if rounded {
+            LineCap::Round
+        } else {
+            LineCap::Butt
+        },
+        ..Default::default()
+    }
+}
+
+/// One pixel wide lines, in `tick_color` or the foreground color.
+pub(super) fn ticks(data: PathData, tick_color: &Option<Color>, context: &RenderContext) -> Node {
+    let color = tick_color
+        .as_ref()
+        .unwrap_or(&context.config.general.foreground_color)
+        .at(context.variables);
+    Node::new(NodeKind::Path(Path {
+        data: Rc::new(data),
+        stroke: Some(Stroke {
+            paint: usvg::Paint::Color(color.to_usvg_color()),
+            opacity: color.to_usvg_opacity(),
+            width: StrokeWidth::new(1.0),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }))
+}
+
+/// The point at `angle` degrees on a circle, 0 pointing to the top and rotating clockwise.
+pub(super) fn polar(cx: f64, cy: f64, r: f64, angle: f64) -> (f64, f64) {
+    let angle = angle.to_radians();
+    (cx + r * angle.sin(), cy - r * angle.cos())
+}
+
+/// Continues `data` along a circle from its current point at `from` degrees to `to`.
+pub(super) fn push_arc(data: &mut PathData, cx: f64, cy: f64, r: f64, from: f64, to: f64) {
+    // A single arc cannot go all the way around.
+    if (to - from).abs() > 180.0 {
+        let middle = (from + to) / 2.0;
+        push_arc(data, cx, cy, r, from, middle);
+        push_arc(data, cx, cy, r, middle, to);
+        return;
+    }
+
+    let (x, y) = polar(cx, cy, r, to);
+    data.push_arc_to(r, r, 0.0, false, to > from, x, y);
+}
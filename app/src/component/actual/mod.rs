@@ -1,18 +1,45 @@
+pub use bar::*;
+pub use cell::*;
 pub use fetch_data::*;
+pub use for_each::*;
+pub use gauge::*;
 pub use graph::*;
+pub use grid::*;
 pub use group::*;
 pub use hbox::*;
+pub use icon::*;
+pub use image::*;
+pub use item::*;
 pub use import_font::*;
+pub use import_icons::*;
+pub use pie::*;
+pub use plugin::*;
+pub use stack::*;
 pub use text::*;
 pub use variable::*;
 pub use vbox::*;
 pub use r#if::*;
 
+mod arrange;
+mod bar;
+mod cell;
+mod decoration;
 mod fetch_data;
+mod for_each;
+mod gauge;
 mod graph;
+mod grid;
 mod group;
 mod hbox;
+mod icon;
+mod image;
+mod item;
 mod import_font;
+mod import_icons;
+mod meter;
+mod pie;
+mod plugin;
+mod stack;
 mod text;
 mod variable;
 mod vbox;
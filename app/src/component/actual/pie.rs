@@ -0,0 +1,104 @@
+use std::rc::Rc;
+
+use serde::Deserialize;
+use usvg::{Group, Node, NodeKind, Path, PathData};
+
+use crate::{
+    component::{job::Job, ComponentAction, RenderContext},
+    system::{Color, Length, Paint},
+};
+
+use super::meter;
+
+/// A disc, with a slice as large as a variable is from `min` to `max`.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Pie {
+    size: Length,
+
+    min: f64,
+    max: f64,
+
+    color: Paint,
+    track_color: Option<Paint>,
+    /// In degrees, 0 pointing to the top and rotating clockwise.
+    #[serde(default)]
+    start_angle: f64,
+
+    #[serde(default)]
+    ticks: usize,
+    tick_color: Option<Color>,
+
+    name: String,
+}
+
+impl ComponentAction for Pie {
+    fn setup(&mut self) -> Vec<Job> {
+        Vec::new()
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        let size = self
+            .size
+            .translate_to_px(context.viewbox_width, context.viewbox_height);
+        let fraction = meter::fraction(context, &self.name, self.min, self.max)?;
+
+        let center = size / 2.0;
+        let slice = |fraction: f64| {
+            let mut data = PathData::new();
+            let (x, y) = meter::polar(center, center, center, self.start_angle);
+            if fraction < 1.0 {
+                data.push_move_to(center, center);
+                data.push_line_to(x, y);
+            } else {
+                data.push_move_to(x, y);
+            }
+            meter::push_arc(
+                &mut data,
+                center,
+                center,
+                center,
+                self.start_angle,
+                self.start_angle + fraction * 360.0,
+            );
+            data.push_close_path();
+            data
+        };
+
+        let mut group = Node::new(NodeKind::Group(Group::default()));
+        if let Some(bounds) = meter::bounds(size, size) {
+            group.append(bounds);
+        }
+
+        if let Some(track_color) = &self.track_color {
+            let fill = meter::fill(track_color, &mut group, context);
+            group.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(slice(1.0)),
+                fill: Some(fill),
+                ..Default::default()
+            })));
+        }
+
+        if fraction > 0.0 {
+            let fill = meter::fill(&self.color, &mut group, context);
+            group.append(Node::new(NodeKind::Path(Path {
+                data: Rc::new(slice(fraction)),
+                fill: Some(fill),
+                ..Default::default()
+            })));
+        }
+
+        if self.ticks > 1 {
+            let mut data = PathData::new();
+            for i in 0..self.ticks {
+                let angle = self.start_angle + 360.0 * i as f64 / self.ticks as f64;
+                data.push_move_to(center, center);
+                let (x, y) = meter::polar(center, center, center, angle);
+                data.push_line_to(x, y);
+            }
+            group.append(meter::ticks(data, &self.tick_color, context));
+        }
+
+        Ok(group)
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        RwLock,
+    },
+};
+
+use async_stream::try_stream;
+use declarrred::rt::DataFormat;
+use once_cell::sync::Lazy;
+use plugrrred::host::{ForeignComponent, ForeignComponentFactory};
+use serde::Deserialize;
+use usvg::{Group, Node, NodeKind, Options, Paint, Tree};
+
+use crate::{
+    component::{
+        action::DataQueryVariable, job::Job, ComponentAction, JobStage, RenderContext,
+        UpdateContext,
+    },
+    util::json,
+};
+
+static FACTORIES: Lazy<RwLock<HashMap<String, ForeignComponentFactory>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Makes `<plugin component="name">` available. Must be called before widgets are set up.
+pub fn register_plugin_component(name: String, factory: ForeignComponentFactory) {
+    FACTORIES
+        .write()
+        .expect("Plugin component registry poisoned")
+        .insert(name, factory);
+}
+
+/// A component provided by a native plugin, given the other attributes.
+#[derive(Deserialize)]
+pub struct PluginComponent {
+    component: String,
+    #[serde(flatten)]
+    attributes: BTreeMap<String, String>,
+
+    #[serde(skip)]
+    instance: Option<ForeignComponent>,
+}
+
+impl PluginComponent {
+    fn instantiate(&mut self) -> eyre::Result<Vec<DataQueryVariable>> {
+        let factories = FACTORIES
+            .read()
+            .map_err(|_| eyre::eyre!("Plugin component registry poisoned"))?;
+        let factory = factories
+            .get(&self.component)
+            .ok_or_else(|| eyre::eyre!("No plugin provides component {}", self.component))?;
+        let instance = self.instance.insert(factory.create(&self.attributes)?);
+
+        instance
+            .setup()?
+            .into_iter()
+            .map(|query| {
+                Ok(DataQueryVariable {
+                    name: query.name,
+                    source: query.source,
+                    query: query.query,
+                    format: serde_json::from_value::<DataFormat>(query.format.into())?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl ComponentAction for PluginComponent {
+    fn setup(&mut self) -> Vec<Job> {
+        let component = self.component.clone();
+        let queries = self.instantiate();
+        vec![Box::pin(try_stream! {
+            let queries = queries?;
+            yield JobStage::Completed {
+                label: format!("Adding data queries of {}...", component),
+                finalizer: Box::new(move |context| {
+                    context.data_queries.extend(queries);
+                    Ok(())
+                })
+            }
+        })]
+    }
+
+    fn update(&mut self, _context: &mut UpdateContext) -> eyre::Result<()> {
+        if let Some(instance) = &mut self.instance {
+            instance.update()?;
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        let instance = self
+            .instance
+            .as_mut()
+            .ok_or_else(|| eyre::eyre!("Component {} is not set up", self.component))?;
+        let content = instance.render(&plugrrred::RenderContext {
+            viewbox_width: context.viewbox_width,
+            viewbox_height: context.viewbox_height,
+            foreground_color: context.config.general.foreground_color.to_string(),
+            font_family: context.config.general.font_family.clone(),
+            variables: context
+                .variables
+                .iter()
+                .map(|(name, data)| (name.clone(), json::from_data(data)))
+                .collect(),
+        })?;
+
+        let svg = format!(
+            r#"<svg version="1.1" width="{width}" height="{height}" xmlns="http://www.w3.org/2000/svg">{content}</svg>"#,
+            width = context.viewbox_width,
+            height = context.viewbox_height,
+            content = content,
+        );
+        graft(&svg, context.usvg_options)
+    }
+}
+
+/// Parses `svg` into a group to be drawn in another tree, taking its defs along to be hoisted
+/// there.
+fn graft(svg: &str, options: &Options) -> eyre::Result<Node> {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let tree = Tree::from_str(svg, &options.to_ref())?;
+    // Plugins name their defs as they like, which may collide with others in the widget.
+    let prefix = format!(
+        "measurrred-plugin-{}-",
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    for mut node in tree.root().descendants() {
+        prefix_ids(&mut node.borrow_mut(), &prefix);
+    }
+
+    let mut group = Node::new(NodeKind::Group(Group::default()));
+    let defs = tree.defs().children().collect::<Vec<_>>();
+    let children = tree.root().children().skip(1).collect::<Vec<_>>();
+    for mut child in defs.into_iter().chain(children) {
+        child.detach();
+        group.append(child);
+    }
+
+    Ok(group)
+}
+
+/// Prefixes the id of a def and every reference to one with `prefix`.
+fn prefix_ids(kind: &mut NodeKind, prefix: &str) {
+    let id = |id: &mut String| id.insert_str(0, prefix);
+    let paint = |paint: &mut Paint| {
+        if let Paint::Link(link) = paint {
+            id(link);
+        }
+    };
+    match kind {
+        NodeKind::LinearGradient(gradient) => id(&mut gradient.id),
+        NodeKind::RadialGradient(gradient) => id(&mut gradient.id),
+        NodeKind::Pattern(pattern) => id(&mut pattern.id),
+        NodeKind::Filter(filter) => id(&mut filter.id),
+        NodeKind::ClipPath(clip_path) => {
+            id(&mut clip_path.id);
+            clip_path.clip_path.iter_mut().for_each(id);
+        }
+        NodeKind::Mask(mask) => {
+            id(&mut mask.id);
+            mask.mask.iter_mut().for_each(id);
+        }
+        NodeKind::Group(group) => {
+            group.clip_path.iter_mut().for_each(id);
+            group.mask.iter_mut().for_each(id);
+            group.filter.iter_mut().for_each(id);
+            group.filter_fill.iter_mut().for_each(paint);
+            group.filter_stroke.iter_mut().for_each(paint);
+        }
+        NodeKind::Path(path) => {
+            path.fill.iter_mut().for_each(|fill| paint(&mut fill.paint));
+            path.stroke
+                .iter_mut()
+                .for_each(|stroke| paint(&mut stroke.paint));
+        }
+        NodeKind::Svg(_) | NodeKind::Defs | NodeKind::Image(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use usvg::{NodeExt, Svg};
+
+    use crate::system::hoist_paint_servers;
+
+    use super::*;
+
+    const SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4">
+        <defs>
+            <linearGradient id="fill">
+                <stop offset="0" stop-color="#ff0000"/>
+                <stop offset="1" stop-color="#0000ff"/>
+            </linearGradient>
+            <clipPath id="clip"><rect width="2" height="2"/></clipPath>
+        </defs>
+        <rect width="4" height="4" fill="url(#fill)" clip-path="url(#clip)"/>
+    </svg>"##;
+
+    #[test]
+    fn keeps_defs_apart() {
+        let options = Options::default();
+        let mut tree = Tree::create(Svg {
+            size: usvg::Size::new(4.0, 4.0).unwrap(),
+            view_box: usvg::ViewBox {
+                rect: usvg::Rect::new(0.0, 0.0, 4.0, 4.0).unwrap(),
+                aspect: usvg::AspectRatio::default(),
+            },
+        });
+        let mut root = Node::new(NodeKind::Group(Group::default()));
+        root.append(graft(SVG, &options).unwrap());
+        root.append(graft(SVG, &options).unwrap());
+        hoist_paint_servers(&root, &mut tree);
+
+        let defs: Vec<_> = tree
+            .defs()
+            .children()
+            .map(|def| def.id().to_string())
+            .collect();
+        let links: Vec<_> = root
+            .descendants()
+            .filter_map(|node| match &*node.borrow() {
+                NodeKind::Path(path) => match path.fill.as_ref().map(|fill| &fill.paint) {
+                    Some(Paint::Link(link)) => Some(link.clone()),
+                    _ => None,
+                },
+                NodeKind::Group(group) => group.clip_path.clone(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(defs.iter().collect::<HashSet<_>>().len(), 4);
+        assert_eq!(links.len(), 4);
+        for link in &links {
+            assert!(defs.contains(link), "{} is not in {:?}", link, defs);
+        }
+        assert!(tree
+            .defs()
+            .descendants()
+            .any(|node| matches!(*node.borrow(), NodeKind::Path(_))));
+    }
+}
@@ -0,0 +1,90 @@
+use serde::Deserialize;
+use usvg::Node;
+
+use crate::{
+    component::{job::Job, Component, ComponentAction, RenderContext, UpdateContext},
+    system::{Align, Color, GridTracks, Insets, Length, Paint, Track},
+};
+
+use super::{
+    arrange::{arrange_cells, size_limit, Arrangement},
+    decoration::Decoration,
+};
+
+/// Children drawn over each other, the later on top, as large as the largest of them.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Stack {
+    #[serde(default)]
+    justify: Align,
+    #[serde(default)]
+    align: Align,
+    /// Sizes of the stack, padding and border included.
+    width: Option<Length>,
+    height: Option<Length>,
+    min_width: Option<Length>,
+    max_width: Option<Length>,
+    min_height: Option<Length>,
+    max_height: Option<Length>,
+    padding: Option<Insets>,
+    background: Option<Paint>,
+    border_color: Option<Color>,
+    border_width: Option<Length>,
+    corner_radius: Option<Length>,
+
+    #[serde(rename = "$value")]
+    children: Vec<Component>,
+}
+
+impl ComponentAction for Stack {
+    fn setup(&mut self) -> Vec<Job> {
+        self.children
+            .iter_mut()
+            .flat_map(|child| child.setup())
+            .collect()
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) -> eyre::Result<()> {
+        for child in self.children.iter_mut() {
+            child.update(context)?;
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        Ok(self.measure(context)?.place(None, context)?.0)
+    }
+}
+
+impl Stack {
+    /// Measures the children, to be laid out once the stack is given its size.
+    pub(super) fn measure(&mut self, context: &RenderContext) -> eyre::Result<Arrangement<'_>> {
+        let decoration = Decoration {
+            padding: self.padding.as_ref(),
+            background: self.background.as_ref(),
+            border_color: self.border_color.as_ref(),
+            border_width: self.border_width.as_ref(),
+            corner_radius: self.corner_radius.as_ref(),
+        };
+        let [top, right, bottom, left] = decoration.edges(context);
+
+        // A single cell taking all the room there is, so children align within the whole stack.
+        let grid = GridTracks {
+            justify: self.justify,
+            align: self.align,
+            width: size_limit(
+                [&self.width, &self.min_width, &self.max_width],
+                left + right,
+                context,
+            ),
+            height: size_limit(
+                [&self.height, &self.min_height, &self.max_height],
+                top + bottom,
+                context,
+            ),
+            ..GridTracks::new(vec![Track::Fraction(1.0)], vec![Track::Fraction(1.0)])
+        };
+
+        arrange_cells(&mut self.children, grid, true, decoration, context)
+    }
+}
@@ -1,15 +1,21 @@
-use std::rc::Rc;
+use std::{fmt, rc::Rc, sync::Arc};
 
-use serde::Deserialize;
+use serde::{
+    de::{EnumAccess, VariantAccess, Visitor},
+    Deserialize, Deserializer,
+};
 use tracing_unwrap::OptionExt;
 use usvg::{
-    fontdb::{Family, Query},
-    Group, Node, NodeExt, NodeKind, Path, PathData, Rect, Tree,
+    Align, AspectRatio, Fill, Group, ImageKind, ImageRendering, Node, NodeKind, Opacity, Path,
+    PathData, Rect, Size, Svg, Transform, Tree, ViewBox, Visibility,
 };
 
-use crate::component::{ComponentAction, RenderContext};
+use crate::{
+    component::{ComponentAction, RenderContext},
+    system::{Color, Length, TextLayout},
+};
 
-use super::EitherVariable;
+use super::Variable;
 
 #[derive(Clone, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -25,10 +31,33 @@ impl Default for TextAlign {
     }
 }
 
-#[derive(Deserialize)]
+impl TextAlign {
+    /// How far to move what is aligned, given `room` left around it.
+    fn offset(&self, room: f64) -> f64 {
+        match self {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => room / 2.0,
+            TextAlign::Right => room,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextOverflow {
+    /// Cuts the text at `max-width`.
+    #[default]
+    Clip,
+    /// Cuts the text before the last cluster that fits along with an ellipsis.
+    Ellipsis,
+    /// Scrolls the text through `max-width` like a marquee, a step each frame.
+    Scroll,
+}
+
+#[derive(Default, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Text {
-    pub color: Option<String>,
+    pub color: Option<Color>,
 
     #[serde(default)]
     pub text_align: TextAlign,
@@ -36,129 +65,515 @@ pub struct Text {
     pub font_size: Option<f64>,
     pub font_family: Option<String>,
     pub font_weight: Option<String>,
+    /// Asks for digits of the same width, where the font has them.
+    #[serde(default)]
+    pub tabular_figures: bool,
+
+    pub max_width: Option<Length>,
+    pub min_width: Option<Length>,
+    /// Text as wide as the widest expected content, like `000.00`, kept as the least width.
+    pub reserve: Option<String>,
+    #[serde(default)]
+    pub overflow: TextOverflow,
+    /// Pixels the marquee of `overflow="scroll"` moves each frame.
+    #[serde(default = "default_scroll_step")]
+    pub scroll_step: f64,
+    /// Space between the end of the text and its next turn in the marquee.
+    #[serde(default = "default_scroll_gap")]
+    pub scroll_gap: f64,
 
     #[serde(rename = "$value")]
-    pub content: Vec<EitherVariable<String>>,
+    pub content: Vec<TextFragment>,
+
+    #[serde(skip)]
+    pub(super) scrolled: f64,
 }
 
-impl ComponentAction for Text {
-    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
-        // resvg lacks dominant-baseline support ;(
-        let font_size = self.font_size.unwrap_or(16.0);
-        let font_family = self
-            .font_family
-            .as_ref()
-            .unwrap_or(&context.config.general.font_family);
+const fn default_scroll_step() -> f64 {
+    1.0
+}
 
-        let font_id = match context.usvg_options.fontdb.query(&Query {
-            families: &[Family::Name(font_family)],
-            ..Default::default()
-        }) {
-            Some(font_id) => font_id,
-            None => {
-                eyre::bail!("Failed to find font {}{}", &font_family, {
-                    let mut families = context
-                        .usvg_options
-                        .fontdb
-                        .faces()
-                        .iter()
-                        .map(|face| face.family.clone())
-                        .collect::<std::collections::HashSet<_>>()
-                        .into_iter()
-                        .collect::<Vec<_>>();
-                    families
-                        .sort_by_key(|family| strsim::damerau_levenshtein(&font_family, &family));
-                    if families.len() > 0 {
-                        format!(
-                            ", you may wanted to use one of these fonts: {}",
-                            families[0..5].join(", ")
-                        )
-                    } else {
-                        ".".to_string()
+const fn default_scroll_gap() -> f64 {
+    16.0
+}
+
+/// Text, a `<variable>`, or a `<span>` styling its own content.
+pub enum TextFragment {
+    Text(String),
+    Variable(Variable),
+    Span(Span),
+}
+
+impl<'de> Deserialize<'de> for TextFragment {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FragmentVisitor;
+
+        impl<'de> Visitor<'de> for FragmentVisitor {
+            type Value = TextFragment;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("text, <variable> or <span>")
+            }
+
+            fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+            where
+                A: EnumAccess<'de>,
+            {
+                // quick-xml names the variant after the element, or after the text itself.
+                let (name, access): (String, _) = data.variant()?;
+                match name.as_str() {
+                    "variable" => access.newtype_variant().map(TextFragment::Variable),
+                    "span" => access.newtype_variant().map(TextFragment::Span),
+                    _ => {
+                        access.unit_variant()?;
+                        Ok(TextFragment::Text(name))
                     }
-                });
+                }
             }
+        }
+
+        deserializer.deserialize_enum("TextFragment", &["variable", "span"], FragmentVisitor)
+    }
+}
+
+/// Content styled apart from the rest of `<text>`, inheriting what it doesn't set.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Span {
+    pub color: Option<Color>,
+    pub font_size: Option<f64>,
+    pub font_family: Option<String>,
+    pub font_weight: Option<String>,
+    pub tabular_figures: Option<bool>,
+
+    #[serde(rename = "$value", default)]
+    pub content: Vec<TextFragment>,
+}
+
+#[derive(Clone, PartialEq)]
+struct TextStyle {
+    color: usvg::Color,
+    opacity: Opacity,
+    font_family: String,
+    font_weight: u16,
+    font_size: f64,
+    tabular_figures: bool,
+}
+
+impl TextStyle {
+    fn inherit(&self, span: &Span, context: &RenderContext) -> TextStyle {
+        let color = span.color.as_ref().map(|color| color.at(context.variables));
+        TextStyle {
+            color: color.as_ref().map_or(self.color, Color::to_usvg_color),
+            opacity: color.as_ref().map_or(self.opacity, Color::to_usvg_opacity),
+            font_family: span
+                .font_family
+                .clone()
+                .unwrap_or_else(|| self.font_family.clone()),
+            font_weight: span
+                .font_weight
+                .as_deref()
+                .map_or(self.font_weight, parse_font_weight),
+            font_size: span.font_size.unwrap_or(self.font_size),
+            tabular_figures: span.tabular_figures.unwrap_or(self.tabular_figures),
+        }
+    }
+
+    fn shape(&self, context: &RenderContext, text: &str) -> eyre::Result<Arc<TextLayout>> {
+        Ok(TextLayout::shape(
+            &context.usvg_options.fontdb,
+            &self.font_family,
+            self.font_weight,
+            self.font_size,
+            self.tabular_figures,
+            text,
+        )?)
+    }
+}
+
+/// `normal`, `bold` or a number, taking `bolder` and `lighter` against `normal`.
+fn parse_font_weight(weight: &str) -> u16 {
+    match weight {
+        "normal" => 400,
+        "bold" | "bolder" => 700,
+        "lighter" => 100,
+        weight => weight.parse().unwrap_or(400),
+    }
+}
+
+/// Text in one style before it is shaped.
+struct Piece {
+    style: TextStyle,
+    text: String,
+    /// Text the piece is kept at least as wide as, which keeps it apart from its neighbours.
+    reserve: Option<String>,
+}
+
+/// Appends `text` to the pieces, collapsing whitespace into a space across them like SVG does.
+fn push(pieces: &mut Vec<Piece>, style: &TextStyle, text: &str, reserve: Option<&str>) {
+    if let Some(reserve) = reserve {
+        pieces.push(Piece {
+            style: style.clone(),
+            text: String::new(),
+            reserve: Some(reserve.to_string()),
+        });
+    }
+    for c in text.chars() {
+        let after_space = pieces
+            .iter()
+            .rev()
+            .find_map(|piece| piece.text.chars().last())
+            .unwrap_or(' ')
+            == ' ';
+        let c = match c {
+            c if !c.is_whitespace() => c,
+            _ if after_space => continue,
+            _ => ' ',
         };
-        let (height, ascender) = context
-            .usvg_options
-            .fontdb
-            .with_face_data(font_id, |data, face_index| -> eyre::Result<_> {
-                let font = ttf_parser::Face::from_slice(data, face_index)?;
-                let scale = font_size / font.units_per_em() as f64;
-                Ok((scale * font.height() as f64, scale * font.ascender() as f64))
-            })
-            .unwrap_or_log()?;
-
-        let svg = format!(
-            r#"
-                <svg version="1.1" width="100" height="{font_size}" xmlns="http://www.w3.org/2000/svg">
-                    <text
-                        id="root"
-                        dy="{dy}"
-                        fill="{color}"
-                        font-size="{font_size}"
-                        font-family="{font_family}"
-                        text-anchor="{text_anchor}"
-                        {font_weight}
-                    >
-                        {content}
-                    </text>
-                </svg>
-                "#,
-            dy = ascender,
-            content = self
-                .content
+        match pieces.last_mut() {
+            Some(last) if last.style == *style && (reserve.is_some() || last.reserve.is_none()) => {
+                last.text.push(c)
+            }
+            _ => pieces.push(Piece {
+                style: style.clone(),
+                text: c.to_string(),
+                reserve: None,
+            }),
+        }
+    }
+}
+
+/// Drops the whitespace at the end of the pieces.
+fn trim_end(pieces: &mut Vec<Piece>) {
+    while let Some(piece) = pieces.last_mut() {
+        piece.text.truncate(piece.text.trim_end().len());
+        if !piece.text.is_empty() || piece.reserve.is_some() {
+            break;
+        }
+        pieces.pop();
+    }
+}
+
+fn collect(
+    fragments: &[TextFragment],
+    style: &TextStyle,
+    context: &RenderContext,
+    pieces: &mut Vec<Piece>,
+) {
+    for fragment in fragments {
+        match fragment {
+            TextFragment::Text(text) => push(pieces, style, text, None),
+            TextFragment::Variable(variable) => push(
+                pieces,
+                style,
+                &variable.format(context).unwrap_or_default(),
+                variable.reserve(),
+            ),
+            TextFragment::Span(span) => collect(
+                &span.content,
+                &style.inherit(span, context),
+                context,
+                pieces,
+            ),
+        }
+    }
+}
+
+/// A shaped piece, starting at `x` with its glyphs `offset` further.
+struct Run {
+    piece: Piece,
+    layout: Arc<TextLayout>,
+    x: f64,
+    offset: f64,
+}
+
+/// Runs shaped one after another, on the lowest baseline among them.
+struct Line {
+    /// The style of `<text>`, which keeps the height even without any run in it.
+    style: TextStyle,
+    runs: Vec<Run>,
+    width: f64,
+    height: f64,
+    ascender: f64,
+}
+
+impl Line {
+    fn new(
+        style: TextStyle,
+        pieces: Vec<Piece>,
+        align: &TextAlign,
+        context: &RenderContext,
+    ) -> eyre::Result<Line> {
+        let strut = style.shape(context, "")?;
+        let mut line = Line {
+            style,
+            runs: Vec::with_capacity(pieces.len()),
+            width: 0.0,
+            height: strut.height,
+            ascender: strut.ascender,
+        };
+        let mut descender = strut.height - strut.ascender;
+        for piece in pieces {
+            let layout = piece.style.shape(context, &piece.text)?;
+            line.ascender = line.ascender.max(layout.ascender);
+            descender = descender.max(layout.height - layout.ascender);
+
+            let width = match &piece.reserve {
+                Some(reserve) => piece.style.shape(context, reserve)?.width.max(layout.width),
+                None => layout.width,
+            };
+            let x = line.width;
+            line.width += width;
+            line.runs.push(Run {
+                piece,
+                offset: align.offset(width - layout.width),
+                layout,
+                x,
+            });
+        }
+        line.height = line.ascender + descender;
+
+        Ok(line)
+    }
+
+    /// Cuts the line before the last cluster that fits in `max_width` along with an ellipsis.
+    fn ellipsize(
+        self,
+        max_width: f64,
+        align: &TextAlign,
+        context: &RenderContext,
+    ) -> eyre::Result<Line> {
+        let mut pieces = Vec::new();
+        for run in self.runs {
+            let Run {
+                piece, layout, x, ..
+            } = run;
+            let ellipsis = piece.style.shape(context, "\u{2026}")?;
+            if x + layout.width + ellipsis.width <= max_width {
+                push(
+                    &mut pieces,
+                    &piece.style,
+                    &piece.text,
+                    piece.reserve.as_deref(),
+                );
+                continue;
+            }
+
+            let end = layout
+                .carets
                 .iter()
-                .flat_map(|fragment| fragment.format(context))
-                .collect::<Vec<_>>()
-                .join(""),
-            color = self
-                .color
-                .as_ref()
-                .unwrap_or(&context.config.general.foreground_color.to_string()),
-            font_size = font_size,
-            font_family = font_family,
-            text_anchor = match self.text_align {
-                TextAlign::Left => "start",
-                TextAlign::Center => "middle",
-                TextAlign::Right => "end",
-            },
-            font_weight = self
+                .take_while(|(_, caret)| x + caret + ellipsis.width <= max_width)
+                .last()
+                .map_or(0, |&(offset, _)| offset);
+            push(&mut pieces, &piece.style, &piece.text[..end], None);
+            trim_end(&mut pieces);
+            push(&mut pieces, &piece.style, "\u{2026}", None);
+            break;
+        }
+        Line::new(self.style, pieces, align, context)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.runs.iter().all(|run| run.layout.outline.is_empty())
+    }
+
+    fn glyphs(&self, x: f64) -> Vec<Node> {
+        self.runs
+            .iter()
+            .filter(|run| !run.layout.outline.is_empty())
+            .map(|run| {
+                Node::new(NodeKind::Path(Path {
+                    transform: Transform::new_translate(
+                        x + run.x + run.offset,
+                        self.ascender - run.layout.ascender,
+                    ),
+                    fill: Some(Fill {
+                        paint: usvg::Paint::Color(run.piece.style.color),
+                        opacity: run.piece.style.opacity,
+                        ..Default::default()
+                    }),
+                    data: Rc::new(run.layout.outline.clone()),
+                    ..Default::default()
+                }))
+            })
+            .collect()
+    }
+}
+
+impl Text {
+    fn style(&self, context: &RenderContext) -> TextStyle {
+        let color = self
+            .color
+            .as_ref()
+            .unwrap_or(&context.config.general.foreground_color)
+            .at(context.variables);
+        TextStyle {
+            color: color.to_usvg_color(),
+            opacity: color.to_usvg_opacity(),
+            font_family: self
+                .font_family
+                .clone()
+                .unwrap_or_else(|| context.config.general.font_family.clone()),
+            font_weight: self
                 .font_weight
                 .as_ref()
                 .or(context.config.general.font_weight.as_ref())
-                .map(|weight| format!(r#"font-weight="{}""#, weight))
-                .unwrap_or_default()
-        );
-        let tree = Tree::from_str(&svg, &context.usvg_options.to_ref())?;
-        let node = tree.node_by_id("root").unwrap();
-
-        let width = match &*node.borrow() {
-            NodeKind::Path(path) => path.text_bbox.unwrap().width(),
-            _ => node.calculate_bbox().unwrap().width(),
+                .map_or(400, |weight| parse_font_weight(weight)),
+            font_size: self.font_size.unwrap_or(16.0),
+            tabular_figures: self.tabular_figures,
+        }
+    }
+
+    fn line(&self, context: &RenderContext) -> eyre::Result<Line> {
+        let style = self.style(context);
+        let mut pieces = Vec::new();
+        collect(&self.content, &style, context, &mut pieces);
+        trim_end(&mut pieces);
+        Line::new(style, pieces, &self.text_align, context)
+    }
+}
+
+impl ComponentAction for Text {
+    fn render(&mut self, context: &RenderContext) -> eyre::Result<Node> {
+        let mut line = self.line(context)?;
+
+        let px =
+            |length: &Length| length.translate_to_px(context.viewbox_width, context.viewbox_height);
+        let max_width = self
+            .max_width
+            .as_ref()
+            .map(px)
+            .filter(|max_width| line.width > *max_width);
+        if let (Some(max_width), TextOverflow::Ellipsis) = (max_width, self.overflow) {
+            line = line.ellipsize(max_width, &self.text_align, context)?;
+        }
+        let reserved = match &self.reserve {
+            Some(reserve) => line.style.shape(context, reserve)?.width,
+            None => 0.0,
         };
+        let width = max_width
+            .unwrap_or(line.width)
+            .max(reserved)
+            .max(self.min_width.as_ref().map_or(0.0, px));
 
-        let rect = Node::new(NodeKind::Path(Path {
-            data: Rc::new(PathData::from_rect(
-                Rect::new(
-                    match self.text_align {
-                        TextAlign::Left => 0.0,
-                        TextAlign::Center => -width / 2.0,
-                        TextAlign::Right => -width,
-                    },
-                    0.0,
-                    width,
-                    height,
-                )
-                .unwrap_or_log(),
-            )),
-            ..Default::default()
-        }));
+        let x = -self.text_align.offset(width);
+
+        // Keeps the advance and the line height even when there is no ink, like spaces.
+        let mut bounds = PathData::new();
+        bounds.push_move_to(x, 0.0);
+        bounds.push_line_to(x + width, 0.0);
+        bounds.push_line_to(x + width, line.height);
+        bounds.push_line_to(x, line.height);
+        bounds.push_close_path();
 
         let mut group = Node::new(NodeKind::Group(Group::default()));
-        group.append(rect);
-        group.append(node);
+        group.append(Node::new(NodeKind::Path(Path {
+            data: Rc::new(bounds),
+            ..Default::default()
+        })));
+        if line.is_empty() {
+            return Ok(group);
+        }
+
+        if line.width <= width {
+            self.scrolled = 0.0;
+            for node in line.glyphs(x + self.text_align.offset(width - line.width)) {
+                group.append(node);
+            }
+            return Ok(group);
+        }
+
+        let rect = match Rect::new(x, 0.0, width, line.height) {
+            Some(rect) => rect,
+            // There is no room for any of it.
+            None => return Ok(group),
+        };
+        let nodes = if let TextOverflow::Scroll = self.overflow {
+            let period = line.width + self.scroll_gap;
+            self.scrolled = (self.scrolled + self.scroll_step).rem_euclid(period);
+            let mut nodes = line.glyphs(-self.scrolled);
+            nodes.extend(line.glyphs(period - self.scrolled));
+            nodes
+        } else {
+            line.glyphs(0.0)
+        };
+        group.append(clip(nodes, rect));
 
         Ok(group)
     }
 }
+
+/// Shows `nodes` only within `rect`, where they start.
+///
+/// They are put in a nested SVG image, since the bounding box of a clipped group
+/// still counts what is clipped out.
+pub(super) fn clip(nodes: Vec<Node>, rect: Rect) -> Node {
+    // An image renders at its size rounded, so the nested one matches it to stay unscaled.
+    let size = Size::new(
+        rect.width().round().max(1.0),
+        rect.height().round().max(1.0),
+    )
+    .unwrap_or_log();
+    let tree = Tree::create(Svg {
+        size,
+        view_box: ViewBox {
+            rect: size.to_rect(0.0, 0.0),
+            aspect: AspectRatio::default(),
+        },
+    });
+    for node in nodes {
+        tree.root().append(node);
+    }
+
+    Node::new(NodeKind::Image(usvg::Image {
+        id: String::new(),
+        transform: Transform::default(),
+        visibility: Visibility::Visible,
+        view_box: ViewBox {
+            rect,
+            aspect: AspectRatio {
+                defer: false,
+                align: Align::XMinYMin,
+                slice: true,
+            },
+        },
+        rendering_mode: ImageRendering::default(),
+        kind: ImageKind::SVG(tree),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use usvg::Options;
+
+    use crate::config::MeasurrredConfig;
+
+    use super::*;
+
+    #[test]
+    fn draws_nothing_without_room() {
+        let config: MeasurrredConfig =
+            toml::from_str(include_str!("../../../../measurrred.config.toml")).unwrap();
+        let mut usvg_options = Options::default();
+        usvg_options.fontdb.load_system_fonts();
+        let family = usvg_options.fontdb.faces()[0].family.clone();
+        let variables = HashMap::new();
+        let context = RenderContext::new(100.0, 40.0, &usvg_options, &config, &variables);
+
+        for overflow in ["clip", "ellipsis", "scroll"] {
+            let mut text: Text = quick_xml::de::from_str(&format!(
+                r#"<text font-family="{}" max-width="0px" overflow="{}">hello</text>"#,
+                family, overflow
+            ))
+            .unwrap();
+            let node = text.render(&context).unwrap();
+            // Only the bounds are left.
+            assert_eq!(node.children().count(), 1, "overflow={}", overflow);
+        }
+    }
+}
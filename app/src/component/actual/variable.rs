@@ -20,6 +20,9 @@ pub struct Variable {
     #[serde(default = "default_divide_by")]
     divide_by: FromStrT<f64>,
     format: DataFormat,
+
+    /// Text as wide as the widest expected value, like `000.00`, kept as the least width.
+    reserve: Option<String>,
 }
 
 fn default_precision() -> FromStrT<usize> {
@@ -35,11 +38,17 @@ impl Variable {
         context.variables.get(&self.name).cloned()
     }
 
+    pub fn reserve(&self) -> Option<&str> {
+        self.reserve.as_deref()
+    }
+
     pub fn format(&self, context: &RenderContext) -> Option<String> {
         let data = context.variables.get(&self.name)?;
 
         let content = match self.format {
-            DataFormat::String => data.as_string().ok()?.to_string(),
+            DataFormat::String | DataFormat::List | DataFormat::Map => {
+                data.as_string().ok()?.to_string()
+            }
             DataFormat::I32 | DataFormat::I64 | DataFormat::Int => {
                 format!("{}", data.as_i64().ok()? / self.divide_by.0 as i64)
             }
@@ -21,23 +21,48 @@ pub enum Component {
     HBox(HBox),
     #[serde(rename = "vbox")]
     VBox(VBox),
+    #[serde(rename = "grid")]
+    Grid(Grid),
+    #[serde(rename = "stack")]
+    Stack(Stack),
     #[serde(rename = "fetch-data")]
     FetchData(FetchData),
     #[serde(rename = "graph")]
     Graph(Graph),
+    #[serde(rename = "bar")]
+    Bar(Bar),
+    #[serde(rename = "gauge")]
+    Gauge(Gauge),
+    #[serde(rename = "pie")]
+    Pie(Pie),
     #[serde(rename = "group")]
     Group(Group),
+    #[serde(rename = "image")]
+    Image(Image),
+    #[serde(rename = "icon")]
+    Icon(Icon),
 
     #[serde(rename = "import-font")]
     ImportFont(ImportFont),
+    #[serde(rename = "import-icons")]
+    ImportIcons(ImportIcons),
 
     #[serde(rename = "if")]
     If(If),
+    #[serde(rename = "for-each")]
+    ForEach(ForEach),
+
+    #[serde(rename = "plugin")]
+    Plugin(PluginComponent),
 
     #[serde(rename = "margin")]
     Margin { size: Length },
     #[serde(rename = "set-position")]
     SetPosition { to: Length },
+    #[serde(rename = "item")]
+    Item(Item),
+    #[serde(rename = "cell")]
+    Cell(Cell),
     #[serde(rename = "overlap")]
     Overlap {
         #[serde(rename = "$value")]
@@ -51,11 +76,23 @@ impl fmt::Debug for Component {
             Self::Text(_) => write!(f, "<text>"),
             Self::HBox(_) => write!(f, "<hbox>"),
             Self::VBox(_) => write!(f, "<vbox>"),
+            Self::Grid(_) => write!(f, "<grid>"),
+            Self::Stack(_) => write!(f, "<stack>"),
             Self::FetchData(_) => write!(f, "<fetch-data>"),
             Self::Graph(_) => write!(f, "<graph>"),
+            Self::Bar(_) => write!(f, "<bar>"),
+            Self::Gauge(_) => write!(f, "<gauge>"),
+            Self::Pie(_) => write!(f, "<pie>"),
             Self::Group(_) => write!(f, "<group>"),
+            Self::Image(_) => write!(f, "<image>"),
+            Self::Icon(_) => write!(f, "<icon>"),
             Self::ImportFont(_) => write!(f, "<import-font>"),
+            Self::ImportIcons(_) => write!(f, "<import-icons>"),
             Self::If(_) => write!(f, "<if>"),
+            Self::ForEach(_) => write!(f, "<for-each>"),
+            Self::Plugin(_) => write!(f, "<plugin>"),
+            Self::Item(_) => write!(f, "<item>"),
+            Self::Cell(_) => write!(f, "<cell>"),
             Self::Margin { size } => write!(f, "<margin size={}>", size),
             Self::SetPosition { to } => write!(f, "<set-position to={}>", to),
             Self::Overlap { child } => write!(f, "<overlap>{:?}</overlap>", child),
@@ -63,17 +100,39 @@ impl fmt::Debug for Component {
     }
 }
 
+impl Component {
+    /// Renders the component once, or once per element for `<for-each>`.
+    pub fn render_all(&mut self, context: &RenderContext) -> eyre::Result<Vec<Node>> {
+        match self {
+            Component::ForEach(for_each) => for_each.render_each(context),
+            _ => Ok(vec![self.render(context)?]),
+        }
+    }
+}
+
 impl ComponentAction for Component {
     fn setup(&mut self) -> Vec<Job> {
         match self {
             Component::Text(text) => text.setup(),
             Component::HBox(hbox) => hbox.setup(),
             Component::VBox(vbox) => vbox.setup(),
+            Component::Grid(grid) => grid.setup(),
+            Component::Stack(stack) => stack.setup(),
             Component::FetchData(data_text) => data_text.setup(),
             Component::Graph(data_graph) => data_graph.setup(),
+            Component::Bar(bar) => bar.setup(),
+            Component::Gauge(gauge) => gauge.setup(),
+            Component::Pie(pie) => pie.setup(),
             Component::Group(group) => group.setup(),
+            Component::Image(image) => image.setup(),
+            Component::Icon(icon) => icon.setup(),
             Component::ImportFont(import_font) => import_font.setup(),
+            Component::ImportIcons(import_icons) => import_icons.setup(),
             Component::If(r#if) => r#if.setup(),
+            Component::ForEach(for_each) => for_each.setup(),
+            Component::Plugin(plugin) => plugin.setup(),
+            Component::Item(item) => item.setup(),
+            Component::Cell(cell) => cell.setup(),
             Component::Overlap { child } => child.setup(),
             Component::Margin { .. } | Component::SetPosition { .. } => Vec::new(),
         }
@@ -84,11 +143,23 @@ impl ComponentAction for Component {
             Component::Text(text) => text.update(context),
             Component::HBox(hbox) => hbox.update(context),
             Component::VBox(vbox) => vbox.update(context),
+            Component::Grid(grid) => grid.update(context),
+            Component::Stack(stack) => stack.update(context),
             Component::FetchData(data_text) => data_text.update(context),
             Component::Graph(data_graph) => data_graph.update(context),
+            Component::Bar(bar) => bar.update(context),
+            Component::Gauge(gauge) => gauge.update(context),
+            Component::Pie(pie) => pie.update(context),
             Component::Group(group) => group.update(context),
+            Component::Image(image) => image.update(context),
+            Component::Icon(icon) => icon.update(context),
             Component::ImportFont(import_font) => import_font.update(context),
+            Component::ImportIcons(import_icons) => import_icons.update(context),
             Component::If(r#if) => r#if.update(context),
+            Component::ForEach(for_each) => for_each.update(context),
+            Component::Plugin(plugin) => plugin.update(context),
+            Component::Item(item) => item.update(context),
+            Component::Cell(cell) => cell.update(context),
             Component::Overlap { child } => child.update(context),
             Component::Margin { .. } | Component::SetPosition { .. } => Ok(()),
         }
@@ -99,15 +170,27 @@ impl ComponentAction for Component {
             Component::Text(text) => text.render(context),
             Component::HBox(hbox) => hbox.render(context),
             Component::VBox(vbox) => vbox.render(context),
+            Component::Grid(grid) => grid.render(context),
+            Component::Stack(stack) => stack.render(context),
             Component::FetchData(fetch_data) => fetch_data.render(context),
             Component::Graph(graph) => graph.render(context),
+            Component::Bar(bar) => bar.render(context),
+            Component::Gauge(gauge) => gauge.render(context),
+            Component::Pie(pie) => pie.render(context),
             Component::Group(group) => group.render(context),
+            Component::Image(image) => image.render(context),
+            Component::Icon(icon) => icon.render(context),
             Component::If(r#if) => r#if.render(context),
+            Component::ForEach(for_each) => for_each.render(context),
+            Component::Plugin(plugin) => plugin.render(context),
+            Component::Item(item) => item.render(context),
+            Component::Cell(cell) => cell.render(context),
             Component::Overlap { child } => child.render(context),
 
-            Component::ImportFont(_) | Component::SetPosition { .. } | Component::Margin { .. } => {
-                Ok(Node::new(NodeKind::Group(usvg::Group::default())))
-            }
+            Component::ImportFont(_)
+            | Component::ImportIcons(_)
+            | Component::SetPosition { .. }
+            | Component::Margin { .. } => Ok(Node::new(NodeKind::Group(usvg::Group::default()))),
         }
     }
 }
@@ -1,15 +1,34 @@
-use std::{fs::File, io::Read};
+use std::{collections::HashMap, fs::File, io::Read, path::PathBuf};
 
-use serde::{Deserialize, Serialize};
+use knowhw::network::InterfaceFilter;
+use serde::{Deserialize, Deserializer, Serialize};
 use thiserror::Error;
+use url::Url;
 
-use crate::system::Color;
+use crate::{
+    data_source::exec::ExecOutputFormat,
+    system::{Color, ThemeMode},
+};
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct MeasurrredConfig {
     pub general: GeneralSection,
     pub viewbox_tuning: ViewboxTuningSection,
+    #[serde(default)]
+    pub network: NetworkSection,
+    #[serde(default)]
+    pub http: HashMap<String, HttpSourceSection>,
+    #[serde(default)]
+    pub exec: HashMap<String, ExecSourceSection>,
+    #[serde(default)]
+    pub prometheus: HashMap<String, PrometheusSourceSection>,
+    #[serde(default)]
+    pub plugin: PluginSection,
+    #[serde(default)]
+    pub exporter: ExporterSection,
+    #[serde(default)]
+    pub theme: ThemeSection,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -28,6 +47,189 @@ pub struct ViewboxTuningSection {
     pub respect_tray_area_when_right_align: bool,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct NetworkSection {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub only_up: bool,
+    #[serde(default = "default_network_skip_loopback")]
+    pub skip_loopback: bool,
+    #[serde(default)]
+    pub skip_virtual: bool,
+}
+
+fn default_network_skip_loopback() -> bool {
+    true
+}
+
+impl Default for NetworkSection {
+    fn default() -> Self {
+        NetworkSection {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            only_up: false,
+            skip_loopback: default_network_skip_loopback(),
+            skip_virtual: false,
+        }
+    }
+}
+
+impl NetworkSection {
+    pub fn to_interface_filter(&self) -> InterfaceFilter {
+        InterfaceFilter {
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            only_up: self.only_up,
+            skip_loopback: self.skip_loopback,
+            skip_virtual: self.skip_virtual,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpSourceSection {
+    pub url: Url,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Milliseconds between successful polls.
+    #[serde(default = "default_http_interval")]
+    pub interval: u64,
+    /// Upper bound in milliseconds of the delay between retries after failures.
+    #[serde(default = "default_http_max_backoff")]
+    pub max_backoff: u64,
+}
+
+fn default_http_interval() -> u64 {
+    60_000
+}
+
+fn default_http_max_backoff() -> u64 {
+    600_000
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExecSourceSection {
+    /// The program followed by its arguments.
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub output: ExecOutputFormat,
+    /// Milliseconds between the beginnings of two runs.
+    #[serde(
+        default = "default_exec_interval",
+        deserialize_with = "deserialize_interval"
+    )]
+    pub interval: u64,
+    /// Milliseconds to wait before killing the command.
+    #[serde(default = "default_exec_timeout")]
+    pub timeout: u64,
+}
+
+fn default_exec_interval() -> u64 {
+    5_000
+}
+
+fn default_exec_timeout() -> u64 {
+    3_000
+}
+
+/// Rejects `0`, which would run the command over and over without a break.
+fn deserialize_interval<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match u64::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom("interval must be at least 1")),
+        interval => Ok(interval),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PrometheusSourceSection {
+    pub url: Url,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Milliseconds between scrapes.
+    #[serde(default = "default_prometheus_interval")]
+    pub interval: u64,
+}
+
+fn default_prometheus_interval() -> u64 {
+    15_000
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PluginSection {
+    /// Milliseconds to wait for a plugin to respond.
+    #[serde(default = "default_plugin_timeout")]
+    pub timeout: u64,
+}
+
+fn default_plugin_timeout() -> u64 {
+    1_000
+}
+
+impl Default for PluginSection {
+    fn default() -> Self {
+        PluginSection {
+            timeout: default_plugin_timeout(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExporterSection {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Port on localhost to serve the metrics on.
+    #[serde(default = "default_exporter_port")]
+    pub port: u16,
+}
+
+fn default_exporter_port() -> u16 {
+    9813
+}
+
+impl Default for ExporterSection {
+    fn default() -> Self {
+        ExporterSection {
+            enabled: false,
+            port: default_exporter_port(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ThemeSection {
+    /// The theme `theme:` colors refer to, if exists.
+    #[serde(default = "default_theme_file")]
+    pub file: PathBuf,
+    #[serde(default)]
+    pub variant: ThemeMode,
+}
+
+fn default_theme_file() -> PathBuf {
+    PathBuf::from("theme.toml")
+}
+
+impl Default for ThemeSection {
+    fn default() -> Self {
+        ThemeSection {
+            file: default_theme_file(),
+            variant: ThemeMode::default(),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ConfigLoadError {
     #[error("I/O Failed")]
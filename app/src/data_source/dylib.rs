@@ -0,0 +1,38 @@
+use std::sync::Mutex;
+
+use declarrred::rt::{Data, DataFormat};
+use plugrrred::host::ForeignDataSource;
+
+use crate::util::json;
+
+use super::{BoxedDataSource, DataSource};
+
+/// A data source registered by a native plugin.
+pub struct DylibDataSource(Mutex<ForeignDataSource>);
+
+impl DylibDataSource {
+    pub fn boxed(source: ForeignDataSource) -> BoxedDataSource {
+        Box::new(DylibDataSource(Mutex::new(source)))
+    }
+}
+
+impl DataSource for DylibDataSource {
+    fn update(&self) -> eyre::Result<()> {
+        self.0
+            .lock()
+            .map_err(|_| eyre::eyre!("Plugin lock poisoned"))?
+            .update()?;
+        Ok(())
+    }
+
+    fn query(&mut self, query: &str, preferred_format: &DataFormat) -> eyre::Result<Data> {
+        let format = serde_json::to_value(preferred_format)?;
+        let value = self
+            .0
+            .get_mut()
+            .map_err(|_| eyre::eyre!("Plugin lock poisoned"))?
+            .query(query, format.as_str().unwrap_or_default())?;
+
+        Ok(json::to_data(&value).to_format(preferred_format)?)
+    }
+}
@@ -0,0 +1,491 @@
+#[cfg(windows)]
+use std::ptr::null;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+    process::{Child, Command, Stdio},
+    str::FromStr,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, RwLock, Weak,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use declarrred::rt::{Data, DataFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+#[cfg(windows)]
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{CloseHandle, HANDLE},
+        System::JobObjects::{AssignProcessToJobObject, CreateJobObjectW, TerminateJobObject},
+    },
+};
+
+use crate::util::json::{self, JsonPath};
+
+use super::{BoxedDataSource, DataSource};
+
+/// How stdout of the command is turned into variables.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExecOutputFormat {
+    /// The whole trimmed stdout is a single value.
+    #[default]
+    Plain,
+    /// Each `key=value` line is a value.
+    KeyValue,
+    /// Stdout is a JSON document.
+    Json,
+}
+
+enum ExecOutput {
+    Plain(String),
+    KeyValue(BTreeMap<String, String>),
+    Json(Value),
+}
+
+#[derive(Debug, Error)]
+pub enum ExecError {
+    #[error("Command is empty")]
+    EmptyCommand,
+
+    #[error("Failed to spawn the command: {0}")]
+    Spawn(#[from] std::io::Error),
+
+    #[error("Command timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error("Command exited with {0}")]
+    ExitStatus(std::process::ExitStatus),
+
+    #[error("Stdout reader stopped without reading it")]
+    Reader,
+
+    #[error("Stdout is not valid UTF-8")]
+    NotUtf8(#[from] std::string::FromUtf8Error),
+
+    #[error("Stdout is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Runs a command on its own thread, so a slow command never stalls the rendering.
+pub struct ExecDataSource {
+    state: Arc<RwLock<ExecState>>,
+    paths: HashMap<String, JsonPath>,
+}
+
+#[derive(Default)]
+struct ExecState {
+    /// Of the last successful run.
+    output: Option<ExecOutput>,
+    /// Why the latest run failed, until `update` reports it.
+    error: Option<String>,
+}
+
+impl ExecDataSource {
+    pub fn new(
+        command: Vec<String>,
+        output_format: ExecOutputFormat,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Self {
+        let state = Arc::new(RwLock::new(ExecState::default()));
+        let weak = Arc::downgrade(&state);
+        thread::spawn(move || poll(weak, command, output_format, interval, timeout));
+
+        ExecDataSource {
+            state,
+            paths: HashMap::new(),
+        }
+    }
+
+    pub fn boxed(
+        command: Vec<String>,
+        output_format: ExecOutputFormat,
+        interval: Duration,
+        timeout: Duration,
+    ) -> BoxedDataSource {
+        Box::new(ExecDataSource::new(
+            command,
+            output_format,
+            interval,
+            timeout,
+        ))
+    }
+}
+
+impl DataSource for ExecDataSource {
+    fn update(&self) -> eyre::Result<()> {
+        let error = self
+            .state
+            .write()
+            .map_err(|_| eyre::eyre!("Exec data source lock poisoned"))?
+            .error
+            .take();
+
+        match error {
+            Some(e) => Err(eyre::eyre!(e)),
+            None => Ok(()),
+        }
+    }
+
+    fn query(&mut self, query: &str, preferred_format: &DataFormat) -> eyre::Result<Data> {
+        let state = self
+            .state
+            .read()
+            .map_err(|_| eyre::eyre!("Exec data source lock poisoned"))?;
+
+        let data = match &state.output {
+            None => Data::Unknown,
+            Some(ExecOutput::Plain(value)) => Data::String(value.clone()),
+            Some(ExecOutput::KeyValue(values)) if query.is_empty() => Data::Map(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Data::String(value.clone())))
+                    .collect(),
+            ),
+            Some(ExecOutput::KeyValue(values)) => values
+                .get(query)
+                .map(|value| Data::String(value.clone()))
+                .unwrap_or(Data::Unknown),
+            Some(ExecOutput::Json(body)) => {
+                if !self.paths.contains_key(query) {
+                    self.paths
+                        .insert(query.to_string(), JsonPath::from_str(query)?);
+                }
+                self.paths[query]
+                    .find(body)
+                    .map(json::to_data)
+                    .unwrap_or(Data::Unknown)
+            }
+        };
+
+        Ok(data.to_format(preferred_format)?)
+    }
+}
+
+fn poll(
+    state: Weak<RwLock<ExecState>>,
+    command: Vec<String>,
+    output_format: ExecOutputFormat,
+    interval: Duration,
+    timeout: Duration,
+) {
+    loop {
+        let begin = Instant::now();
+        let result = run(&command, timeout).and_then(|stdout| parse(stdout, output_format));
+
+        // The data source has been dropped.
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+        match state.write() {
+            Ok(mut state) => match result {
+                Ok(output) => state.output = Some(output),
+                Err(e) => state.error = Some(format!("Failed to run {:?}: {}", command, e)),
+            },
+            Err(_) => return,
+        }
+        drop(state);
+
+        thread::sleep(interval.saturating_sub(begin.elapsed()));
+    }
+}
+
+fn run(command: &[String], timeout: Duration) -> Result<Vec<u8>, ExecError> {
+    let deadline = Instant::now() + timeout;
+    let (program, args) = command.split_first().ok_or(ExecError::EmptyCommand)?;
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW, otherwise every run flashes a console window.
+        command.creation_flags(0x08000000);
+    }
+    let mut tree = ProcessTree::spawn(&mut command)?;
+
+    // Drain stdout on another thread, or a chatty command blocks on a full pipe.
+    let mut stdout = tree.child.stdout.take().expect("stdout is piped");
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = sender.send(stdout.read_to_end(&mut buffer).map(|_| buffer));
+    });
+
+    let status = loop {
+        if let Some(status) = tree.child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            tree.kill();
+            return Err(ExecError::Timeout(timeout));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    if !status.success() {
+        return Err(ExecError::ExitStatus(status));
+    }
+
+    // What the command started in the background may hold stdout open after it exited.
+    match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(stdout) => Ok(stdout?),
+        Err(RecvTimeoutError::Timeout) => {
+            tree.kill();
+            Err(ExecError::Timeout(timeout))
+        }
+        Err(RecvTimeoutError::Disconnected) => Err(ExecError::Reader),
+    }
+}
+
+/// A command with every process it starts, which `Child::kill` alone would leave running.
+struct ProcessTree {
+    child: Child,
+    #[cfg(windows)]
+    job: Option<HANDLE>,
+}
+
+impl ProcessTree {
+    fn spawn(command: &mut Command) -> std::io::Result<Self> {
+        // A process group of its own, which whatever the command starts joins.
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        let child = command.spawn()?;
+
+        // Processes started before the command joins the job escape it, which is hardly ever.
+        #[cfg(windows)]
+        let job = unsafe {
+            use std::os::windows::io::AsRawHandle;
+
+            CreateJobObjectW(null(), PCWSTR(null()))
+                .ok()
+                .and_then(|job| {
+                    if AssignProcessToJobObject(job, HANDLE(child.as_raw_handle() as isize))
+                        .as_bool()
+                    {
+                        Some(job)
+                    } else {
+                        CloseHandle(job);
+                        None
+                    }
+                })
+        };
+
+        Ok(ProcessTree {
+            child,
+            #[cfg(windows)]
+            job,
+        })
+    }
+
+    fn kill(&mut self) {
+        #[cfg(windows)]
+        if let Some(job) = self.job {
+            unsafe { TerminateJobObject(job, 1) };
+        }
+        // The group is named after the command, and outlives it as long as anything is left in it.
+        #[cfg(unix)]
+        unsafe {
+            libc::killpg(self.child.id() as libc::pid_t, libc::SIGKILL);
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(windows)]
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        if let Some(job) = self.job {
+            unsafe { CloseHandle(job) };
+        }
+    }
+}
+
+fn parse(stdout: Vec<u8>, output_format: ExecOutputFormat) -> Result<ExecOutput, ExecError> {
+    Ok(match output_format {
+        ExecOutputFormat::Plain => ExecOutput::Plain(String::from_utf8(stdout)?.trim().to_string()),
+        ExecOutputFormat::KeyValue => ExecOutput::KeyValue(
+            String::from_utf8(stdout)?
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .collect(),
+        ),
+        ExecOutputFormat::Json => ExecOutput::Json(serde_json::from_slice(&stdout)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `windows` for `cmd`, `unix` for `sh`.
+    fn shell(windows: &str, unix: &str) -> Vec<String> {
+        if cfg!(windows) {
+            vec!["cmd".to_string(), "/C".to_string(), windows.to_string()]
+        } else {
+            vec!["sh".to_string(), "-c".to_string(), unix.to_string()]
+        }
+    }
+
+    fn source(output: ExecOutput) -> ExecDataSource {
+        ExecDataSource {
+            state: Arc::new(RwLock::new(ExecState {
+                output: Some(output),
+                error: None,
+            })),
+            paths: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn parses_output() {
+        let output = parse(b"  42\r\n".to_vec(), ExecOutputFormat::Plain).unwrap();
+        assert!(matches!(output, ExecOutput::Plain(value) if value == "42"));
+
+        let output = parse(
+            b"branch = main\nahead=2\nnot a pair\nurl=https://a/?b=c\n".to_vec(),
+            ExecOutputFormat::KeyValue,
+        )
+        .unwrap();
+        match output {
+            ExecOutput::KeyValue(values) => {
+                assert_eq!(values.len(), 3);
+                assert_eq!(values["branch"], "main");
+                assert_eq!(values["ahead"], "2");
+                assert_eq!(values["url"], "https://a/?b=c");
+            }
+            _ => panic!("Expected key-value output"),
+        }
+
+        let output = parse(br#"{"depth":3}"#.to_vec(), ExecOutputFormat::Json).unwrap();
+        assert!(matches!(output, ExecOutput::Json(body) if body["depth"] == 3));
+
+        assert!(matches!(
+            parse(vec![0xff, 0xfe], ExecOutputFormat::Plain),
+            Err(ExecError::NotUtf8(_))
+        ));
+        assert!(matches!(
+            parse(b"{".to_vec(), ExecOutputFormat::Json),
+            Err(ExecError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn queries_output() {
+        let mut plain = source(ExecOutput::Plain("42".to_string()));
+        let value = plain.query("ignored", &DataFormat::Int).unwrap();
+        assert_eq!(value.as_i64().unwrap(), 42);
+
+        let mut key_value = source(ExecOutput::KeyValue(BTreeMap::from([(
+            "branch".to_string(),
+            "main".to_string(),
+        )])));
+        let branch = key_value.query("branch", &DataFormat::String).unwrap();
+        assert_eq!(branch.as_string().unwrap(), "main");
+        let every = key_value.query("", &DataFormat::Map).unwrap();
+        assert_eq!(every.as_map().unwrap().len(), 1);
+        let missing = key_value.query("tag", &DataFormat::String).unwrap();
+        assert!(matches!(missing, Data::Unknown));
+
+        let mut json = source(ExecOutput::Json(serde_json::json!({"queue": {"depth": 3}})));
+        let depth = json.query("$.queue.depth", &DataFormat::Int).unwrap();
+        assert_eq!(depth.as_i64().unwrap(), 3);
+        assert!(json.query("queue.depth", &DataFormat::Int).is_err());
+    }
+
+    #[test]
+    fn reports_failed_runs_once() {
+        let source = source(ExecOutput::Plain("42".to_string()));
+        assert!(source.update().is_ok());
+
+        source.state.write().unwrap().error = Some("Failed to run".to_string());
+        assert!(source.update().is_err());
+        assert!(source.update().is_ok());
+    }
+
+    #[test]
+    fn reads_stdout_and_exit_status() {
+        let stdout = run(&shell("echo hello", "echo hello"), Duration::from_secs(10)).unwrap();
+        assert_eq!(String::from_utf8(stdout).unwrap().trim(), "hello");
+
+        assert!(matches!(
+            run(&shell("exit 3", "exit 3"), Duration::from_secs(10)),
+            Err(ExecError::ExitStatus(_))
+        ));
+        assert!(matches!(
+            run(&[], Duration::from_secs(10)),
+            Err(ExecError::EmptyCommand)
+        ));
+        assert!(matches!(
+            run(
+                &["measurrred-no-such-program".to_string()],
+                Duration::from_secs(10)
+            ),
+            Err(ExecError::Spawn(_))
+        ));
+    }
+
+    #[test]
+    fn kills_slow_commands() {
+        let begin = Instant::now();
+        let result = run(
+            &shell("ping -n 30 127.0.0.1", "sleep 30"),
+            Duration::from_millis(300),
+        );
+        assert!(matches!(result, Err(ExecError::Timeout(_))));
+        assert!(begin.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn does_not_wait_for_what_commands_leave_behind() {
+        let begin = Instant::now();
+        let result = run(
+            &shell("start /B ping -n 30 127.0.0.1", "sleep 30 &"),
+            Duration::from_millis(300),
+        );
+        assert!(matches!(result, Err(ExecError::Timeout(_))));
+        assert!(begin.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn kills_what_commands_start() {
+        let pid_file = std::env::temp_dir().join(format!("measurrred-exec-{}", std::process::id()));
+        let result = run(
+            &shell(
+                "",
+                &format!("sleep 30 & echo $! > '{}'; wait", pid_file.display()),
+            ),
+            Duration::from_millis(300),
+        );
+        assert!(matches!(result, Err(ExecError::Timeout(_))));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let _ = std::fs::remove_file(&pid_file);
+        let stat = format!("/proc/{}/stat", pid.trim());
+        let begin = Instant::now();
+        // Killed, and gone once reaped by whoever adopted it.
+        while std::fs::read_to_string(&stat).is_ok_and(|stat| !stat.contains(") Z ")) {
+            assert!(
+                begin.elapsed() < Duration::from_secs(5),
+                "sleep is still running"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, RwLock, Weak},
+    time::{Duration, Instant},
+};
+
+use async_std::task;
+use declarrred::rt::{Data, DataFormat};
+use serde_json::Value;
+use tracing::warn;
+use url::Url;
+
+use crate::util::{
+    http,
+    json::{self, JsonPath},
+};
+
+use super::{BoxedDataSource, DataSource};
+
+/// Polls a JSON document in the background, so querying never waits for the network.
+pub struct HttpDataSource {
+    state: Arc<RwLock<HttpState>>,
+    paths: HashMap<String, JsonPath>,
+}
+
+#[derive(Default)]
+struct HttpState {
+    body: Option<Value>,
+    failures: u32,
+    fetch_seconds: Option<f64>,
+}
+
+impl HttpDataSource {
+    pub fn new(
+        url: Url,
+        headers: Vec<(String, String)>,
+        interval: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        let state = Arc::new(RwLock::new(HttpState::default()));
+        task::spawn(poll(
+            Arc::downgrade(&state),
+            url,
+            headers,
+            interval,
+            max_backoff,
+        ));
+
+        HttpDataSource {
+            state,
+            paths: HashMap::new(),
+        }
+    }
+
+    pub fn boxed(
+        url: Url,
+        headers: Vec<(String, String)>,
+        interval: Duration,
+        max_backoff: Duration,
+    ) -> BoxedDataSource {
+        Box::new(HttpDataSource::new(url, headers, interval, max_backoff))
+    }
+}
+
+impl DataSource for HttpDataSource {
+    fn update(&self) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    fn query(&mut self, query: &str, preferred_format: &DataFormat) -> eyre::Result<Data> {
+        if !self.paths.contains_key(query) {
+            self.paths
+                .insert(query.to_string(), JsonPath::from_str(query)?);
+        }
+        let path = &self.paths[query];
+        let state = self
+            .state
+            .read()
+            .map_err(|_| eyre::eyre!("HTTP data source lock poisoned"))?;
+
+        let data = state
+            .body
+            .as_ref()
+            .and_then(|body| path.find(body))
+            .map(json::to_data)
+            .unwrap_or(Data::Unknown);
+
+        Ok(data.to_format(preferred_format)?)
+    }
+
+    fn fetch_seconds(&self) -> Option<f64> {
+        self.state.read().ok()?.fetch_seconds
+    }
+}
+
+async fn poll(
+    state: Weak<RwLock<HttpState>>,
+    url: Url,
+    headers: Vec<(String, String)>,
+    interval: Duration,
+    max_backoff: Duration,
+) {
+    loop {
+        let begin = Instant::now();
+        let result = match http::fetch(&url, &headers).await {
+            Ok(body) => serde_json::from_slice::<Value>(&body).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let fetch_seconds = begin.elapsed().as_secs_f64();
+
+        // The data source has been dropped.
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+        let delay = match state.write() {
+            Ok(mut state) => {
+                state.fetch_seconds = Some(fetch_seconds);
+                match result {
+                    Ok(body) => {
+                        state.body = Some(body);
+                        state.failures = 0;
+                        interval
+                    }
+                    Err(e) => {
+                        state.failures += 1;
+                        let delay = backoff(interval, max_backoff, state.failures);
+                        warn!(
+                            "Failed to poll {} ({} times in a row), retrying in {}s: {}",
+                            url,
+                            state.failures,
+                            delay.as_secs_f64(),
+                            e
+                        );
+                        delay
+                    }
+                }
+            }
+            Err(_) => return,
+        };
+        drop(state);
+
+        task::sleep(delay).await;
+    }
+}
+
+/// Doubles the interval on every consecutive failure, up to `max_backoff`.
+fn backoff(interval: Duration, max_backoff: Duration, failures: u32) -> Duration {
+    interval
+        .checked_mul(2u32.saturating_pow(failures))
+        .unwrap_or(Duration::MAX)
+        .min(max_backoff.max(interval))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    fn serve(responses: Vec<(u16, &'static str)>) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let _ = stream.read(&mut [0; 4096]);
+                let (status, body) = responses[i.min(responses.len() - 1)];
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+            }
+        });
+        url
+    }
+
+    async fn wait_until(source: &HttpDataSource, predicate: impl Fn(&HttpState) -> bool) {
+        for _ in 0..250 {
+            if predicate(&source.state.read().unwrap()) {
+                return;
+            }
+            task::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Timed out while waiting for the stub server");
+    }
+
+    #[async_std::test]
+    async fn extracts_queries_in_requested_format() {
+        let url = serve(vec![(
+            200,
+            r#"{"current":{"temp_c":21.5,"condition":{"text":"Sunny"}},"jobs":[{"name":"build","ok":true}]}"#,
+        )]);
+        let mut source = HttpDataSource::new(
+            url,
+            Vec::new(),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        wait_until(&source, |state| state.body.is_some()).await;
+
+        let temp = source.query("/current/temp_c", &DataFormat::Float).unwrap();
+        assert_eq!(temp.as_float().unwrap(), 21.5);
+        let condition = source
+            .query("$.current.condition.text", &DataFormat::String)
+            .unwrap();
+        assert_eq!(condition.as_string().unwrap(), "Sunny");
+        let ok = source.query("$.jobs[0]['ok']", &DataFormat::Bool).unwrap();
+        assert!(ok.as_bool().unwrap());
+        let jobs = source.query("$.jobs", &DataFormat::List).unwrap();
+        assert_eq!(jobs.as_list().unwrap().len(), 1);
+        let missing = source.query("$.missing", &DataFormat::Float).unwrap();
+        assert!(matches!(missing, Data::Unknown));
+        assert!(source.query("current.temp_c", &DataFormat::Float).is_err());
+    }
+
+    #[async_std::test]
+    async fn keeps_last_body_while_backing_off() {
+        let url = serve(vec![(200, r#"{"value":1}"#), (500, "{}")]);
+        let mut source = HttpDataSource::new(
+            url,
+            Vec::new(),
+            Duration::from_millis(20),
+            Duration::from_millis(80),
+        );
+        wait_until(&source, |state| state.failures >= 2).await;
+
+        let value = source.query("/value", &DataFormat::Int).unwrap();
+        assert_eq!(value.as_i64().unwrap(), 1);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let interval = Duration::from_secs(10);
+        let max_backoff = Duration::from_secs(60);
+        assert_eq!(backoff(interval, max_backoff, 1), Duration::from_secs(20));
+        assert_eq!(backoff(interval, max_backoff, 2), Duration::from_secs(40));
+        assert_eq!(backoff(interval, max_backoff, 3), max_backoff);
+        assert_eq!(backoff(interval, max_backoff, 100), max_backoff);
+        assert_eq!(backoff(interval, Duration::from_secs(1), 1), interval);
+    }
+}
@@ -1,6 +1,17 @@
+pub mod dylib;
+pub mod exec;
+pub mod http;
+pub mod plugin;
+pub mod prometheus;
+
 use declarrred::rt::{Data, DataFormat};
 use knowhw::Knowhw;
 
+pub use self::{
+    dylib::DylibDataSource, exec::ExecDataSource, http::HttpDataSource, plugin::PluginDataSource,
+    prometheus::PrometheusDataSource,
+};
+
 pub struct KnowhwDataSource<T: Knowhw>(pub T);
 
 impl<T: Knowhw> KnowhwDataSource<T>
@@ -30,6 +41,11 @@ pub trait DataSource {
     fn update(&self) -> eyre::Result<()>;
 
     fn query(&mut self, query: &str, preferred_format: &DataFormat) -> eyre::Result<Data>;
+
+    /// Time taken by the latest fetch, for sources fetching off the render thread.
+    fn fetch_seconds(&self) -> Option<f64> {
+        None
+    }
 }
 
 pub type BoxedDataSource = Box<dyn DataSource + Send + Sync>;
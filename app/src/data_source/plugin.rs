@@ -0,0 +1,532 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use declarrred::rt::{Data, DataFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::util::json;
+
+use super::DataSource;
+
+/// `plugin.toml` in a plugin directory.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct PluginManifest {
+    /// The program followed by its arguments, resolved against the plugin directory.
+    command: Vec<String>,
+}
+
+/// The answer of a plugin to `describe`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PluginDescription {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub queries: Vec<PluginQueryDescription>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PluginQueryDescription {
+    pub query: String,
+    #[serde(default)]
+    pub format: Option<DataFormat>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method", rename_all = "kebab-case")]
+enum Request<'a> {
+    Describe,
+    Update,
+    Query {
+        query: &'a str,
+        format: &'a DataFormat,
+    },
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    id: u64,
+    #[serde(flatten)]
+    request: Request<'a>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    id: u64,
+    #[serde(default)]
+    result: Value,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Cannot deserialize plugin.toml: {0}")]
+    Manifest(#[from] toml::de::Error),
+
+    #[error("Command in plugin.toml is empty")]
+    EmptyCommand,
+
+    #[error("Malformed message: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Plugin did not respond in {0:?}")]
+    Timeout(Duration),
+
+    #[error("Plugin has exited")]
+    Exited,
+
+    #[error("Plugin responded with an error: {0}")]
+    Plugin(String),
+
+    #[error("Mutex lock poisoned")]
+    MutexLockPoisoned,
+}
+
+struct PluginIo {
+    stdin: ChildStdin,
+    responses: Receiver<String>,
+    next_id: u64,
+    timeout: Duration,
+}
+
+/// A query asked by a widget, with the latest answer of the plugin to it.
+struct PluginQuery {
+    query: String,
+    format: DataFormat,
+    answer: Option<Result<Data, String>>,
+}
+
+#[derive(Default)]
+struct PluginState {
+    queries: Vec<PluginQuery>,
+    update_error: Option<String>,
+    fetch_seconds: Option<f64>,
+}
+
+/// A data source implemented by a child process, speaking newline-delimited JSON over stdio.
+///
+/// Requests are sent on a thread of its own, so a slow plugin never stalls the rendering.
+pub struct PluginDataSource {
+    description: PluginDescription,
+    child: Child,
+    state: Arc<Mutex<PluginState>>,
+    refresh: SyncSender<()>,
+}
+
+impl PluginDataSource {
+    /// Starts the plugin at `path`, which is either a directory with `plugin.toml` or an
+    /// executable. Returns `None` for anything else.
+    pub fn load(path: &Path, timeout: Duration) -> Result<Option<Self>, PluginError> {
+        let (program, args, directory) = if path.is_dir() {
+            let manifest = path.join("plugin.toml");
+            if !manifest.is_file() {
+                return Ok(None);
+            }
+            let manifest: PluginManifest = toml::from_slice(&fs::read(manifest)?)?;
+            let (program, args) = manifest
+                .command
+                .split_first()
+                .ok_or(PluginError::EmptyCommand)?;
+            let local = path.join(program);
+            let program = if local.is_file() {
+                local
+            } else {
+                PathBuf::from(program)
+            };
+            (program, args.to_vec(), path.to_path_buf())
+        } else if is_executable(path) {
+            let directory = path.parent().unwrap_or(path).to_path_buf();
+            (path.to_path_buf(), Vec::new(), directory)
+        } else {
+            return Ok(None);
+        };
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(directory)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            // CREATE_NO_WINDOW, otherwise the plugin opens a console window.
+            command.creation_flags(0x08000000);
+        }
+        let mut child = command.spawn()?;
+
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        let (sender, responses) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        let label = path.to_string_lossy().to_string();
+        let stderr_label = label.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                warn!("[{}] {}", stderr_label, line);
+            }
+        });
+
+        let mut io = PluginIo {
+            stdin,
+            responses,
+            next_id: 0,
+            timeout,
+        };
+        let description = io
+            .request(Request::Describe, &label)
+            .and_then(|description| Ok(serde_json::from_value(description)?));
+        let description: PluginDescription = match description {
+            Ok(description) => description,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        };
+
+        let state = Arc::new(Mutex::new(PluginState::default()));
+        // Holds one refresh at most, so refreshes coming while the plugin is busy are skipped.
+        let (refresh, refreshes) = mpsc::sync_channel(1);
+        let served = state.clone();
+        let name = description.name.clone();
+        thread::spawn(move || serve(io, served, refreshes, name));
+
+        Ok(Some(PluginDataSource {
+            description,
+            child,
+            state,
+            refresh,
+        }))
+    }
+
+    pub fn description(&self) -> &PluginDescription {
+        &self.description
+    }
+
+    fn state(&self) -> Result<MutexGuard<'_, PluginState>, PluginError> {
+        self.state
+            .lock()
+            .map_err(|_| PluginError::MutexLockPoisoned)
+    }
+}
+
+impl PluginIo {
+    fn request(&mut self, request: Request, label: &str) -> Result<Value, PluginError> {
+        self.next_id += 1;
+        let id = self.next_id;
+
+        let mut line = serde_json::to_string(&Envelope { id, request })?;
+        line.push('\n');
+        self.stdin
+            .write_all(line.as_bytes())
+            .and_then(|_| self.stdin.flush())
+            .map_err(|_| PluginError::Exited)?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let line = match self
+                .responses
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => return Err(PluginError::Timeout(self.timeout)),
+                Err(RecvTimeoutError::Disconnected) => return Err(PluginError::Exited),
+            };
+            let response: Response = match serde_json::from_str(&line) {
+                Ok(response) => response,
+                Err(e) => {
+                    warn!("Ignoring a malformed line from {}: {}", label, e);
+                    continue;
+                }
+            };
+            // A late response to a request which has timed out.
+            if response.id != id {
+                continue;
+            }
+
+            return match response.error {
+                Some(error) => Err(PluginError::Plugin(error)),
+                None => Ok(response.result),
+            };
+        }
+    }
+}
+
+/// On every refresh, sends `update` and then every query asked so far, until the data source is
+/// dropped.
+fn serve(mut io: PluginIo, state: Arc<Mutex<PluginState>>, refreshes: Receiver<()>, name: String) {
+    for () in refreshes {
+        let begin = Instant::now();
+        let update_error = io
+            .request(Request::Update, &name)
+            .err()
+            .map(|e| e.to_string());
+
+        let queries: Vec<_> = match state.lock() {
+            Ok(state) => state
+                .queries
+                .iter()
+                .map(|it| (it.query.clone(), it.format.clone()))
+                .collect(),
+            Err(_) => return,
+        };
+        let answers: Vec<_> = queries
+            .iter()
+            .map(|(query, format)| {
+                let result = io
+                    .request(Request::Query { query, format }, &name)
+                    .map_err(|e| e.to_string())?;
+                json::to_data(&result)
+                    .to_format(format)
+                    .map_err(|e| e.to_string())
+            })
+            .collect();
+
+        let mut state = match state.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+        state.update_error = update_error;
+        for ((query, format), answer) in queries.into_iter().zip(answers) {
+            if let Some(asked) = state
+                .queries
+                .iter_mut()
+                .find(|it| it.query == query && it.format == format)
+            {
+                asked.answer = Some(answer);
+            }
+        }
+        state.fetch_seconds = Some(begin.elapsed().as_secs_f64());
+    }
+}
+
+impl DataSource for PluginDataSource {
+    fn update(&self) -> eyre::Result<()> {
+        let _ = self.refresh.try_send(());
+
+        match self.state()?.update_error.take() {
+            Some(e) => Err(eyre::eyre!(e)),
+            None => Ok(()),
+        }
+    }
+
+    fn query(&mut self, query: &str, preferred_format: &DataFormat) -> eyre::Result<Data> {
+        let mut state = self.state()?;
+        let asked = state
+            .queries
+            .iter()
+            .find(|it| it.query == query && it.format == *preferred_format);
+
+        match asked {
+            Some(PluginQuery {
+                answer: Some(Ok(data)),
+                ..
+            }) => Ok(data.clone()),
+            Some(PluginQuery {
+                answer: Some(Err(e)),
+                ..
+            }) => Err(eyre::eyre!(e.clone())),
+            Some(_) => Ok(Data::Unknown),
+            None => {
+                state.queries.push(PluginQuery {
+                    query: query.to_string(),
+                    format: preferred_format.clone(),
+                    answer: None,
+                });
+                drop(state);
+                // Asks right away instead of on the next refresh.
+                let _ = self.refresh.try_send(());
+                Ok(Data::Unknown)
+            }
+        }
+    }
+
+    fn fetch_seconds(&self) -> Option<f64> {
+        self.state().ok()?.fetch_seconds
+    }
+}
+
+impl Drop for PluginDataSource {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(windows)]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("exe"))
+}
+
+#[cfg(not(windows))]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers `number`, `updates` and `slow`, sleeping as long as its argument on `update`.
+    const STUB: &str = r#"
+import json, sys, time
+
+updates = 0
+for line in sys.stdin:
+    request = json.loads(line)
+    response = {"id": request["id"]}
+    if request["method"] == "describe":
+        response["result"] = {"name": "stub", "queries": [{"query": "number"}]}
+    elif request["method"] == "update":
+        time.sleep(float(sys.argv[1]))
+        updates += 1
+    elif request["query"] == "number":
+        response["result"] = 42
+    elif request["query"] == "updates":
+        response["result"] = updates
+    elif request["query"] == "slow":
+        time.sleep(1.5)
+        response["result"] = "late"
+    else:
+        response["error"] = "There are no queries named " + request["query"]
+    print(json.dumps(response), flush=True)
+"#;
+
+    fn directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("measurrred-plugin-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    /// The stub in a directory of its own, sleeping `update_seconds` on every update.
+    fn stub(name: &str, update_seconds: f64, timeout: Duration) -> PluginDataSource {
+        let directory = directory(name);
+        let python = if cfg!(windows) { "python" } else { "python3" };
+        fs::write(directory.join("plugin.py"), STUB).unwrap();
+        fs::write(
+            directory.join("plugin.toml"),
+            format!(
+                r#"command = ["{}", "plugin.py", "{}"]"#,
+                python, update_seconds
+            ),
+        )
+        .unwrap();
+        PluginDataSource::load(&directory, timeout)
+            .unwrap()
+            .unwrap()
+    }
+
+    /// Waits for the plugin to answer `query`, which is asked on the first try.
+    fn answer(
+        source: &mut PluginDataSource,
+        query: &str,
+        format: &DataFormat,
+    ) -> eyre::Result<Data> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            match source.query(query, format) {
+                Ok(Data::Unknown) if Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(20))
+                }
+                answer => return answer,
+            }
+        }
+    }
+
+    #[test]
+    fn answers_in_the_format_asked() {
+        let mut source = stub("answers", 0.0, Duration::from_secs(5));
+        assert_eq!(source.description().name, "stub");
+
+        let number = answer(&mut source, "number", &DataFormat::String).unwrap();
+        assert!(matches!(number, Data::String(number) if number == "42"));
+        let number = answer(&mut source, "number", &DataFormat::F64).unwrap();
+        assert!(matches!(number, Data::F64(number) if number == 42.0));
+        assert!(answer(&mut source, "missing", &DataFormat::Int).is_err());
+        assert!(source.update().is_ok());
+    }
+
+    #[test]
+    fn discards_late_responses() {
+        let mut source = stub("late", 0.0, Duration::from_secs(1));
+
+        assert!(answer(&mut source, "slow", &DataFormat::String).is_err());
+        // Reading past the late response to `slow`, which has another id.
+        let number = answer(&mut source, "number", &DataFormat::Int).unwrap();
+        assert!(matches!(number, Data::I64(42)));
+    }
+
+    #[test]
+    fn skips_refreshes_while_busy() {
+        let mut source = stub("busy", 0.3, Duration::from_secs(5));
+
+        source.update().unwrap();
+        thread::sleep(Duration::from_millis(100));
+        for _ in 0..10 {
+            source.update().unwrap();
+        }
+        thread::sleep(Duration::from_secs(1));
+
+        // One refresh kept while busy, and another to ask `updates`.
+        let updates = answer(&mut source, "updates", &DataFormat::Int).unwrap();
+        assert!(matches!(updates, Data::I64(3)));
+    }
+
+    #[test]
+    fn loads_only_plugins() {
+        let timeout = Duration::from_secs(5);
+
+        let empty = directory("empty");
+        assert!(matches!(PluginDataSource::load(&empty, timeout), Ok(None)));
+
+        let file = empty.join("notes.txt");
+        fs::write(&file, "").unwrap();
+        assert!(matches!(PluginDataSource::load(&file, timeout), Ok(None)));
+
+        let no_command = directory("no-command");
+        fs::write(no_command.join("plugin.toml"), "command = []").unwrap();
+        assert!(matches!(
+            PluginDataSource::load(&no_command, timeout),
+            Err(PluginError::EmptyCommand)
+        ));
+    }
+}
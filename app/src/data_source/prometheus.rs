@@ -0,0 +1,499 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    sync::{Arc, RwLock, Weak},
+    time::{Duration, Instant},
+};
+
+use async_std::task;
+use declarrred::rt::{Data, DataFormat};
+use thiserror::Error;
+use tracing::warn;
+use url::Url;
+
+use crate::util::http;
+
+use super::{BoxedDataSource, DataSource};
+
+type Labels = BTreeMap<String, String>;
+type Matcher = (String, MatchOp, String);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Sample {
+    name: String,
+    labels: Labels,
+    value: f64,
+}
+
+#[derive(Debug, Error)]
+#[error("Malformed line {line}: {content}")]
+pub struct ExpositionParseError {
+    line: usize,
+    content: String,
+}
+
+/// Parses the Prometheus text format, which OpenMetrics is a superset of.
+fn parse_exposition(text: &str) -> Result<Vec<Sample>, ExpositionParseError> {
+    let mut samples = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = || ExpositionParseError {
+            line: index + 1,
+            content: line.to_string(),
+        };
+
+        let (name, rest) = split_name(line);
+        let (labels, rest) = match rest.strip_prefix('{') {
+            Some(rest) => parse_label_list(rest).ok_or_else(err)?,
+            None => (Vec::new(), rest),
+        };
+        if name.is_empty() || labels.iter().any(|(_, op, _)| *op != MatchOp::Equal) {
+            return Err(err());
+        }
+        // A timestamp and an exemplar may follow.
+        let value = rest
+            .split_whitespace()
+            .next()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(err)?;
+
+        samples.push(Sample {
+            name: name.to_string(),
+            labels: labels
+                .into_iter()
+                .map(|(label, _, value)| (label, value))
+                .collect(),
+            value,
+        });
+    }
+
+    Ok(samples)
+}
+
+fn split_name(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(s.len());
+    (&s[..end], s[end..].trim_start())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MatchOp {
+    Equal,
+    NotEqual,
+}
+
+/// Parses `label="value", ...}` right after the opening brace, returning what follows.
+fn parse_label_list(mut s: &str) -> Option<(Vec<Matcher>, &str)> {
+    let mut labels = Vec::new();
+    loop {
+        s = s.trim_start();
+        if let Some(rest) = s.strip_prefix('}') {
+            return Some((labels, rest.trim_start()));
+        }
+
+        let (label, rest) = split_name(s);
+        if label.is_empty() {
+            return None;
+        }
+        let (op, rest) = if let Some(rest) = rest.strip_prefix("!=") {
+            (MatchOp::NotEqual, rest)
+        } else {
+            (MatchOp::Equal, rest.strip_prefix('=')?)
+        };
+
+        let rest = rest.trim_start().strip_prefix('"')?;
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        s = loop {
+            match chars.next()? {
+                (i, '"') => break &rest[i + 1..],
+                (_, '\\') => match chars.next()? {
+                    (_, 'n') => value.push('\n'),
+                    (_, c) => value.push(c),
+                },
+                (_, c) => value.push(c),
+            }
+        };
+        labels.push((label.to_string(), op, value));
+
+        s = s.trim_start();
+        s = s.strip_prefix(',').unwrap_or(s);
+    }
+}
+
+/// `name{label="value", label!="value"}`, matching every series having the name and labels.
+#[derive(Clone, Debug, PartialEq)]
+struct Selector {
+    name: String,
+    matchers: Vec<Matcher>,
+}
+
+impl Selector {
+    fn matches(&self, sample: &Sample) -> bool {
+        sample.name == self.name
+            && self.matchers.iter().all(|(label, op, value)| {
+                let actual = sample.labels.get(label).map_or("", String::as_str);
+                (actual == value) == (*op == MatchOp::Equal)
+            })
+    }
+}
+
+/// A selector, summing every matching series, optionally wrapped in `rate()`.
+#[derive(Clone, Debug, PartialEq)]
+enum MetricQuery {
+    Value(Selector),
+    Rate(Selector),
+}
+
+#[derive(Debug, Error)]
+#[error("{0} is not a valid metric query")]
+pub struct MetricQueryParseError(String);
+
+impl FromStr for MetricQuery {
+    type Err = MetricQueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || MetricQueryParseError(s.to_string());
+        let trimmed = s.trim();
+        let (is_rate, selector) = match trimmed
+            .strip_prefix("rate(")
+            .and_then(|it| it.strip_suffix(')'))
+        {
+            Some(selector) => (true, selector.trim()),
+            None => (false, trimmed),
+        };
+
+        let (name, rest) = split_name(selector);
+        let (matchers, rest) = match rest.strip_prefix('{') {
+            Some(rest) => parse_label_list(rest).ok_or_else(err)?,
+            None => (Vec::new(), rest),
+        };
+        if name.is_empty() || !rest.is_empty() {
+            return Err(err());
+        }
+
+        let selector = Selector {
+            name: name.to_string(),
+            matchers,
+        };
+        Ok(if is_rate {
+            MetricQuery::Rate(selector)
+        } else {
+            MetricQuery::Value(selector)
+        })
+    }
+}
+
+struct Scrape {
+    samples: Vec<Sample>,
+    at: Instant,
+}
+
+/// The two latest scrapes, enough to tell rates.
+#[derive(Default)]
+struct ScrapeHistory {
+    previous: Option<Scrape>,
+    current: Option<Scrape>,
+    fetch_seconds: Option<f64>,
+}
+
+impl ScrapeHistory {
+    fn feed(&mut self, samples: Vec<Sample>, at: Instant) {
+        self.previous = self.current.replace(Scrape { samples, at });
+    }
+
+    fn evaluate(&self, query: &MetricQuery) -> Data {
+        let current = match &self.current {
+            Some(current) => current,
+            None => return Data::Unknown,
+        };
+
+        match query {
+            MetricQuery::Value(selector) => {
+                let mut values = current
+                    .samples
+                    .iter()
+                    .filter(|sample| selector.matches(sample))
+                    .map(|sample| sample.value)
+                    .peekable();
+                if values.peek().is_none() {
+                    return Data::Unknown;
+                }
+                Data::F64(values.sum())
+            }
+            MetricQuery::Rate(selector) => {
+                let previous = match &self.previous {
+                    Some(previous) => previous,
+                    None => return Data::Unknown,
+                };
+                let elapsed = current.at.duration_since(previous.at).as_secs_f64();
+                if elapsed <= 0.0 {
+                    return Data::Unknown;
+                }
+
+                let before: HashMap<_, _> = previous
+                    .samples
+                    .iter()
+                    .filter(|sample| selector.matches(sample))
+                    .map(|sample| (&sample.labels, sample.value))
+                    .collect();
+                let mut increases = current
+                    .samples
+                    .iter()
+                    .filter(|sample| selector.matches(sample))
+                    .filter_map(|sample| {
+                        let before = before.get(&sample.labels)?;
+                        // The counter has been reset in between.
+                        Some(if sample.value < *before {
+                            sample.value
+                        } else {
+                            sample.value - before
+                        })
+                    })
+                    .peekable();
+                if increases.peek().is_none() {
+                    return Data::Unknown;
+                }
+                Data::F64(increases.sum::<f64>() / elapsed)
+            }
+        }
+    }
+}
+
+/// Scrapes a Prometheus / OpenMetrics endpoint in the background.
+pub struct PrometheusDataSource {
+    history: Arc<RwLock<ScrapeHistory>>,
+    queries: HashMap<String, MetricQuery>,
+}
+
+impl PrometheusDataSource {
+    pub fn new(url: Url, headers: Vec<(String, String)>, interval: Duration) -> Self {
+        let history = Arc::new(RwLock::new(ScrapeHistory::default()));
+        task::spawn(scrape(Arc::downgrade(&history), url, headers, interval));
+
+        PrometheusDataSource {
+            history,
+            queries: HashMap::new(),
+        }
+    }
+
+    pub fn boxed(url: Url, headers: Vec<(String, String)>, interval: Duration) -> BoxedDataSource {
+        Box::new(PrometheusDataSource::new(url, headers, interval))
+    }
+}
+
+impl DataSource for PrometheusDataSource {
+    fn update(&self) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    fn query(&mut self, query: &str, preferred_format: &DataFormat) -> eyre::Result<Data> {
+        if !self.queries.contains_key(query) {
+            self.queries
+                .insert(query.to_string(), MetricQuery::from_str(query)?);
+        }
+        let data = self
+            .history
+            .read()
+            .map_err(|_| eyre::eyre!("Prometheus data source lock poisoned"))?
+            .evaluate(&self.queries[query]);
+
+        Ok(data.to_format(preferred_format)?)
+    }
+
+    fn fetch_seconds(&self) -> Option<f64> {
+        self.history.read().ok()?.fetch_seconds
+    }
+}
+
+async fn scrape(
+    history: Weak<RwLock<ScrapeHistory>>,
+    url: Url,
+    headers: Vec<(String, String)>,
+    interval: Duration,
+) {
+    loop {
+        let begin = Instant::now();
+        let body = if url.scheme() == "file" {
+            match url.to_file_path() {
+                Ok(path) => async_std::fs::read(path).await.map_err(|e| e.to_string()),
+                Err(_) => Err(format!("{} is not a valid file path", url)),
+            }
+        } else {
+            http::fetch(&url, &headers).await.map_err(|e| e.to_string())
+        };
+        let at = Instant::now();
+        let samples = body.and_then(|body| {
+            parse_exposition(&String::from_utf8_lossy(&body)).map_err(|e| e.to_string())
+        });
+
+        // The data source has been dropped.
+        let history = match history.upgrade() {
+            Some(history) => history,
+            None => return,
+        };
+        match history.write() {
+            Ok(mut history) => {
+                history.fetch_seconds = Some(at.duration_since(begin).as_secs_f64());
+                match samples {
+                    Ok(samples) => history.feed(samples, at),
+                    Err(e) => warn!("Failed to scrape {}: {}", url, e),
+                }
+            }
+            Err(_) => return,
+        }
+        drop(history);
+
+        task::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPOSITION: &str = r#"
+# HELP http_requests_total The total number of HTTP requests.
+# TYPE http_requests_total counter
+http_requests_total{job="api",code="200"} 1027 1395066363000
+http_requests_total{job="api",code="500"} 3
+http_requests_total{job="web", code="200"} 20
+# Escaping in label values
+msdos_file_access_time_seconds{path="C:\\DIR\\FILE.TXT",error="Cannot find file:\n\"FILE.TXT\""} 1.458255915e9
+temperature_celsius -3.5
+up NaN
+# EOF
+"#;
+
+    fn query(s: &str) -> MetricQuery {
+        MetricQuery::from_str(s).unwrap()
+    }
+
+    fn value(data: Data) -> f64 {
+        match data {
+            Data::F64(value) => value,
+            other => panic!("Expected a float but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_exposition() {
+        let samples = parse_exposition(EXPOSITION).unwrap();
+        assert_eq!(samples.len(), 6);
+        assert_eq!(samples[0].name, "http_requests_total");
+        assert_eq!(samples[0].labels["code"], "200");
+        assert_eq!(samples[0].value, 1027.0);
+        assert_eq!(samples[2].labels["job"], "web");
+        assert_eq!(samples[3].labels["path"], r"C:\DIR\FILE.TXT");
+        assert_eq!(
+            samples[3].labels["error"],
+            "Cannot find file:\n\"FILE.TXT\""
+        );
+        assert_eq!(samples[4].value, -3.5);
+        assert!(samples[5].value.is_nan());
+
+        assert!(parse_exposition("broken{job=\"api\" 1").is_err());
+        assert!(parse_exposition("no_value").is_err());
+    }
+
+    #[test]
+    fn parses_queries() {
+        assert_eq!(
+            query(r#"rate( http_requests_total{job="api", code!="500"} )"#),
+            MetricQuery::Rate(Selector {
+                name: "http_requests_total".to_string(),
+                matchers: vec![
+                    ("job".to_string(), MatchOp::Equal, "api".to_string()),
+                    ("code".to_string(), MatchOp::NotEqual, "500".to_string()),
+                ],
+            })
+        );
+        assert_eq!(
+            query("up"),
+            MetricQuery::Value(Selector {
+                name: "up".to_string(),
+                matchers: Vec::new(),
+            })
+        );
+        assert!(MetricQuery::from_str(r#"up{job="api""#).is_err());
+        assert!(MetricQuery::from_str("up trailing").is_err());
+        assert!(MetricQuery::from_str("{job=\"api\"}").is_err());
+    }
+
+    #[test]
+    fn sums_matching_series() {
+        let mut history = ScrapeHistory::default();
+        assert!(matches!(history.evaluate(&query("up")), Data::Unknown));
+
+        history.feed(parse_exposition(EXPOSITION).unwrap(), Instant::now());
+        let total = |s| value(history.evaluate(&query(s)));
+        assert_eq!(
+            total(r#"http_requests_total{job="api",code="200"}"#),
+            1027.0
+        );
+        assert_eq!(total(r#"http_requests_total{job="api"}"#), 1030.0);
+        assert_eq!(total(r#"http_requests_total{code!="500"}"#), 1047.0);
+        assert_eq!(total("http_requests_total"), 1050.0);
+        assert_eq!(total("temperature_celsius"), -3.5);
+        assert!(matches!(
+            history.evaluate(&query(r#"http_requests_total{job="db"}"#)),
+            Data::Unknown
+        ));
+    }
+
+    #[test]
+    fn computes_rate_between_scrapes() {
+        let begin = Instant::now();
+        let mut history = ScrapeHistory::default();
+        history.feed(
+            parse_exposition("requests{job=\"api\"} 100\nrequests{job=\"web\"} 50").unwrap(),
+            begin,
+        );
+        assert!(matches!(
+            history.evaluate(&query("rate(requests)")),
+            Data::Unknown
+        ));
+
+        history.feed(
+            parse_exposition("requests{job=\"api\"} 130\nrequests{job=\"web\"} 10").unwrap(),
+            begin + Duration::from_secs(10),
+        );
+        // `web` has been reset, so its increase is 10.
+        assert_eq!(value(history.evaluate(&query("rate(requests)"))), 4.0);
+        assert_eq!(
+            value(history.evaluate(&query(r#"rate(requests{job="api"})"#))),
+            3.0
+        );
+    }
+
+    #[async_std::test]
+    async fn scrapes_file() {
+        let path =
+            std::env::temp_dir().join(format!("measurrred-metrics-{}.txt", std::process::id()));
+        std::fs::write(&path, EXPOSITION).unwrap();
+
+        let mut source = PrometheusDataSource::new(
+            Url::from_file_path(&path).unwrap(),
+            Vec::new(),
+            Duration::from_millis(20),
+        );
+        for _ in 0..250 {
+            if source.history.read().unwrap().current.is_some() {
+                break;
+            }
+            task::sleep(Duration::from_millis(20)).await;
+        }
+
+        let data = source
+            .query(r#"http_requests_total{job="api"}"#, &DataFormat::Int)
+            .unwrap();
+        assert_eq!(data.as_i64().unwrap(), 1030);
+        assert!(source.query("rate(", &DataFormat::Float).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use declarrred::rt::Data;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::util::json;
+
+pub use self::server::serve;
+
+mod server;
+
+/// What measurrred knows about itself, as of the latest frame.
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Stats {
+    pub frames: u64,
+    pub frame_seconds: f64,
+    pub variables: BTreeMap<String, Value>,
+    pub widgets: BTreeMap<String, WidgetStats>,
+    pub data_sources: BTreeMap<String, DataSourceStats>,
+}
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WidgetStats {
+    pub render_seconds: f64,
+    pub errors: u64,
+}
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DataSourceStats {
+    pub update_seconds: f64,
+    /// Sum over every query of the latest frame.
+    pub query_seconds: f64,
+    /// Latest fetch of the sources fetching in the background, which queries only read from.
+    pub fetch_seconds: Option<f64>,
+    pub errors: u64,
+}
+
+impl Stats {
+    pub fn begin_frame(&mut self) {
+        for data_source in self.data_sources.values_mut() {
+            data_source.query_seconds = 0.0;
+        }
+    }
+
+    pub fn finish_frame(&mut self, elapsed: Duration, variables: &HashMap<String, Data>) {
+        self.frames += 1;
+        self.frame_seconds = elapsed.as_secs_f64();
+        self.variables = variables
+            .iter()
+            .map(|(name, data)| (name.clone(), json::from_data(data)))
+            .collect();
+    }
+
+    pub fn widget(&mut self, name: &str) -> &mut WidgetStats {
+        self.widgets.entry(name.to_string()).or_default()
+    }
+
+    pub fn data_source(&mut self, name: &str) -> &mut DataSourceStats {
+        self.data_sources.entry(name.to_string()).or_default()
+    }
+
+    /// Renders in the OpenMetrics text format.
+    pub fn to_openmetrics(&self) -> String {
+        let mut out = String::new();
+
+        family(&mut out, "measurrred_frames", "counter", "Frames rendered.");
+        sample(&mut out, "measurrred_frames_total", &[], self.frames as f64);
+        family(
+            &mut out,
+            "measurrred_frame_seconds",
+            "gauge",
+            "Time taken by the latest frame.",
+        );
+        sample(&mut out, "measurrred_frame_seconds", &[], self.frame_seconds);
+
+        family(
+            &mut out,
+            "measurrred_variable",
+            "gauge",
+            "Fetched variables holding a number or a boolean.",
+        );
+        for (name, value) in &self.variables {
+            let value = match value {
+                Value::Number(number) => number.as_f64(),
+                Value::Bool(boolean) => Some(*boolean as u8 as f64),
+                _ => None,
+            };
+            if let Some(value) = value {
+                sample(&mut out, "measurrred_variable", &[("name", name)], value);
+            }
+        }
+        family(
+            &mut out,
+            "measurrred_text_variable",
+            "info",
+            "Fetched variables holding a string.",
+        );
+        for (name, value) in &self.variables {
+            if let Value::String(value) = value {
+                sample(
+                    &mut out,
+                    "measurrred_text_variable_info",
+                    &[("name", name), ("value", value)],
+                    1.0,
+                );
+            }
+        }
+
+        family(
+            &mut out,
+            "measurrred_widget_render_seconds",
+            "gauge",
+            "Time taken to render the widget in the latest frame.",
+        );
+        for (name, widget) in &self.widgets {
+            sample(
+                &mut out,
+                "measurrred_widget_render_seconds",
+                &[("widget", name)],
+                widget.render_seconds,
+            );
+        }
+        family(
+            &mut out,
+            "measurrred_widget_errors",
+            "counter",
+            "Failed renders of the widget.",
+        );
+        for (name, widget) in &self.widgets {
+            sample(
+                &mut out,
+                "measurrred_widget_errors_total",
+                &[("widget", name)],
+                widget.errors as f64,
+            );
+        }
+
+        family(
+            &mut out,
+            "measurrred_data_source_update_seconds",
+            "gauge",
+            "Time taken to update the data source in the latest frame.",
+        );
+        for (name, data_source) in &self.data_sources {
+            sample(
+                &mut out,
+                "measurrred_data_source_update_seconds",
+                &[("source", name)],
+                data_source.update_seconds,
+            );
+        }
+        family(
+            &mut out,
+            "measurrred_data_source_query_seconds",
+            "gauge",
+            "Time taken by every query to the data source in the latest frame.",
+        );
+        for (name, data_source) in &self.data_sources {
+            sample(
+                &mut out,
+                "measurrred_data_source_query_seconds",
+                &[("source", name)],
+                data_source.query_seconds,
+            );
+        }
+        family(
+            &mut out,
+            "measurrred_data_source_fetch_seconds",
+            "gauge",
+            "Time taken by the latest background fetch of the data source.",
+        );
+        for (name, data_source) in &self.data_sources {
+            if let Some(fetch_seconds) = data_source.fetch_seconds {
+                sample(
+                    &mut out,
+                    "measurrred_data_source_fetch_seconds",
+                    &[("source", name)],
+                    fetch_seconds,
+                );
+            }
+        }
+        family(
+            &mut out,
+            "measurrred_data_source_errors",
+            "counter",
+            "Failed updates and queries of the data source.",
+        );
+        for (name, data_source) in &self.data_sources {
+            sample(
+                &mut out,
+                "measurrred_data_source_errors_total",
+                &[("source", name)],
+                data_source.errors as f64,
+            );
+        }
+
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    out.push_str(&format!("# TYPE {} {}\n# HELP {} {}\n", name, kind, name, help));
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels = labels
+            .iter()
+            .map(|(label, value)| {
+                let value = value
+                    .replace('\\', r"\\")
+                    .replace('"', r#"\""#)
+                    .replace('\n', r"\n");
+                format!(r#"{}="{}""#, label, value)
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&format!("{{{}}}", labels));
+    }
+    let value = if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    };
+    out.push_str(&format!(" {}\n", value));
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn renders_openmetrics() {
+        let mut stats = Stats::default();
+        stats.begin_frame();
+        stats.finish_frame(
+            Duration::from_millis(250),
+            &HashMap::from([
+                ("cpu".to_string(), Data::F64(12.5)),
+                ("charging".to_string(), Data::Bool(true)),
+                ("song".to_string(), Data::String("A \"B\"\nC".to_string())),
+            ]),
+        );
+        stats.variables.insert("disks".to_string(), json!(["C:"]));
+        stats.variables.insert("ram".to_string(), Value::Null);
+        stats.widget("ranolp/cpu").render_seconds = 0.5;
+        stats.widget("ranolp/cpu").errors = 2;
+        stats.data_source("system").update_seconds = 0.125;
+        stats.data_source("weather").fetch_seconds = Some(1.5);
+
+        assert_eq!(
+            stats.to_openmetrics(),
+            r#"# TYPE measurrred_frames counter
+# HELP measurrred_frames Frames rendered.
+measurrred_frames_total 1
+# TYPE measurrred_frame_seconds gauge
+# HELP measurrred_frame_seconds Time taken by the latest frame.
+measurrred_frame_seconds 0.25
+# TYPE measurrred_variable gauge
+# HELP measurrred_variable Fetched variables holding a number or a boolean.
+measurrred_variable{name="charging"} 1
+measurrred_variable{name="cpu"} 12.5
+# TYPE measurrred_text_variable info
+# HELP measurrred_text_variable Fetched variables holding a string.
+measurrred_text_variable_info{name="song",value="A \"B\"\nC"} 1
+# TYPE measurrred_widget_render_seconds gauge
+# HELP measurrred_widget_render_seconds Time taken to render the widget in the latest frame.
+measurrred_widget_render_seconds{widget="ranolp/cpu"} 0.5
+# TYPE measurrred_widget_errors counter
+# HELP measurrred_widget_errors Failed renders of the widget.
+measurrred_widget_errors_total{widget="ranolp/cpu"} 2
+# TYPE measurrred_data_source_update_seconds gauge
+# HELP measurrred_data_source_update_seconds Time taken to update the data source in the latest frame.
+measurrred_data_source_update_seconds{source="system"} 0.125
+measurrred_data_source_update_seconds{source="weather"} 0
+# TYPE measurrred_data_source_query_seconds gauge
+# HELP measurrred_data_source_query_seconds Time taken by every query to the data source in the latest frame.
+measurrred_data_source_query_seconds{source="system"} 0
+measurrred_data_source_query_seconds{source="weather"} 0
+# TYPE measurrred_data_source_fetch_seconds gauge
+# HELP measurrred_data_source_fetch_seconds Time taken by the latest background fetch of the data source.
+measurrred_data_source_fetch_seconds{source="weather"} 1.5
+# TYPE measurrred_data_source_errors counter
+# HELP measurrred_data_source_errors Failed updates and queries of the data source.
+measurrred_data_source_errors_total{source="system"} 0
+measurrred_data_source_errors_total{source="weather"} 0
+# EOF
+"#
+        );
+    }
+
+    #[test]
+    fn renders_special_values() {
+        let mut out = String::new();
+        sample(&mut out, "a", &[], f64::NAN);
+        sample(&mut out, "b", &[("path", r"C:\")], f64::INFINITY);
+        sample(&mut out, "c", &[], f64::NEG_INFINITY);
+        assert_eq!(out, "a NaN\nb{path=\"C:\\\\\"} +Inf\nc -Inf\n");
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+};
+
+use async_std::{
+    io::{prelude::BufReadExt, BufReader, WriteExt},
+    net::{TcpListener, TcpStream},
+    task,
+};
+use futures::StreamExt;
+use tracing::{info, warn};
+
+use super::Stats;
+
+/// Serves `stats` on localhost, as OpenMetrics on `/metrics` and as JSON on `/metrics.json`.
+pub async fn serve(port: u16, stats: Arc<Mutex<Stats>>) -> std::io::Result<()> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let listener = TcpListener::bind(address).await?;
+    info!("Serving metrics on http://{}/metrics", address);
+
+    let mut incoming = listener.incoming();
+    while let Some(stream) = incoming.next().await {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept a metrics request: {}", e);
+                continue;
+            }
+        };
+        let stats = stats.clone();
+        task::spawn(async move {
+            if let Err(e) = respond(stream, &stats).await {
+                warn!("Failed to respond to a metrics request: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+async fn respond(stream: TcpStream, stats: &Mutex<Stats>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    // Headers are of no interest, but have to be read.
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut request = request_line.split_whitespace();
+    let method = request.next().unwrap_or_default();
+    let path = request
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default();
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics") => (
+            "200 OK",
+            "application/openmetrics-text; version=1.0.0; charset=utf-8",
+            stats
+                .lock()
+                .map(|stats| stats.to_openmetrics())
+                .unwrap_or_default(),
+        ),
+        ("GET", "/metrics.json") => (
+            "200 OK",
+            "application/json",
+            stats
+                .lock()
+                .ok()
+                .and_then(|stats| serde_json::to_string(&*stats).ok())
+                .unwrap_or_default(),
+        ),
+        ("GET", _) => ("404 Not Found", "text/plain", "Not Found".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method Not Allowed".to_string(),
+        ),
+    };
+
+    let mut stream = &stream;
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )
+        .await?;
+    stream.flush().await
+}
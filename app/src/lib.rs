@@ -1,6 +1,7 @@
 pub mod component;
 pub mod config;
 pub mod data_source;
+pub mod exporter;
 pub mod platform;
 pub mod system;
 pub mod util;
@@ -1,19 +1,26 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use std::collections::HashMap;
+use std::env::consts::DLL_EXTENSION;
+use std::sync::{Arc, Mutex};
 
 use std::time::{Duration, Instant};
 use std::{fs, thread};
 
 use app::{
-    component::SetupContext,
+    component::{register_plugin_component, SetupContext},
     config::MeasurrredConfig,
-    data_source::{BoxedDataSource, KnowhwDataSource},
+    data_source::{
+        BoxedDataSource, DylibDataSource, ExecDataSource, HttpDataSource, KnowhwDataSource,
+        PluginDataSource, PrometheusDataSource,
+    },
+    exporter::{self, Stats},
     platform::taskbar::{TaskbarHandle, TaskbarOverlay},
-    system::HorizontalPosition,
+    system::Theme,
     widget::load_widget,
 };
-use knowhw::windows::{BatteryReport, GlobalMemoryStatus, Pdh};
+use declarrred::rt::Data;
+use knowhw::windows::{BatteryReport, GlobalMemoryStatus, Network, Pdh, Process};
 use tiny_skia::{Paint, Pixmap, Rect, Transform};
 use tracing::{error, info, warn};
 use tracing_unwrap::{ResultExt, OptionExt};
@@ -33,6 +40,12 @@ async fn main() -> eyre::Result<()> {
 
     info!("Config loaded.");
 
+    if measurrred_config.theme.file.exists() {
+        Theme::load(&measurrred_config.theme.file)?.install();
+        info!("Theme loaded.");
+    }
+    Theme::set_variant(measurrred_config.theme.variant.variant());
+
     let mut data_source: HashMap<String, BoxedDataSource> = HashMap::from_iter([
         (
             "windows/pdh".to_string(),
@@ -46,7 +59,126 @@ async fn main() -> eyre::Result<()> {
             "windows/battery-report".to_string(),
             KnowhwDataSource::boxed(BatteryReport),
         ),
+        (
+            "windows/process".to_string(),
+            KnowhwDataSource::boxed(Process::new().unwrap_or_log()),
+        ),
+        (
+            "windows/network".to_string(),
+            KnowhwDataSource::boxed(Network::new(
+                measurrred_config.network.to_interface_filter(),
+            )),
+        ),
     ]);
+    for (name, http) in &measurrred_config.http {
+        data_source.insert(
+            format!("http/{}", name),
+            HttpDataSource::boxed(
+                http.url.clone(),
+                http.headers.clone().into_iter().collect(),
+                Duration::from_millis(http.interval),
+                Duration::from_millis(http.max_backoff),
+            ),
+        );
+    }
+    for (name, exec) in &measurrred_config.exec {
+        data_source.insert(
+            format!("exec/{}", name),
+            ExecDataSource::boxed(
+                exec.command.clone(),
+                exec.output,
+                Duration::from_millis(exec.interval),
+                Duration::from_millis(exec.timeout),
+            ),
+        );
+    }
+    for (name, prometheus) in &measurrred_config.prometheus {
+        data_source.insert(
+            format!("prometheus/{}", name),
+            PrometheusDataSource::boxed(
+                prometheus.url.clone(),
+                prometheus.headers.clone().into_iter().collect(),
+                Duration::from_millis(prometheus.interval),
+            ),
+        );
+    }
+
+    info!("Starting plugins");
+    for entry in fs::read_dir("plugins")
+        .and_then(|it| it.collect::<Result<Vec<_>, _>>())
+        .unwrap_or_default()
+    {
+        let path = entry.path();
+
+        if path
+            .extension()
+            .is_some_and(|extension| extension == DLL_EXTENSION)
+        {
+            match plugrrred::host::load(&path) {
+                Ok(plugin) => {
+                    info!(
+                        "Loaded native plugin {} {} from {}",
+                        plugin.name,
+                        plugin.version,
+                        path.to_string_lossy()
+                    );
+                    for (name, source) in plugin.data_sources {
+                        let name = format!("plugin/{}", name);
+                        if data_source.contains_key(&name) {
+                            error!(
+                                "Skipping {} of {} as another plugin has taken the name",
+                                name,
+                                path.to_string_lossy()
+                            );
+                            continue;
+                        }
+                        data_source.insert(name, DylibDataSource::boxed(source));
+                    }
+                    for (name, factory) in plugin.components {
+                        register_plugin_component(name, factory);
+                    }
+                }
+                Err(e) => error!(
+                    "Skipping plugin {} due to an error: {}",
+                    path.to_string_lossy(),
+                    e
+                ),
+            }
+            continue;
+        }
+
+        let plugin = match PluginDataSource::load(
+            &path,
+            Duration::from_millis(measurrred_config.plugin.timeout),
+        ) {
+            Ok(Some(plugin)) => plugin,
+            Ok(None) => continue,
+            Err(e) => {
+                error!(
+                    "Skipping plugin {} due to an error: {}",
+                    path.to_string_lossy(),
+                    e
+                );
+                continue;
+            }
+        };
+
+        let name = format!("plugin/{}", plugin.description().name);
+        if data_source.contains_key(&name) {
+            error!(
+                "Skipping plugin {} as another plugin has taken the name {}",
+                path.to_string_lossy(),
+                name
+            );
+            continue;
+        }
+        info!(
+            "Loaded plugin {} from {}",
+            plugin.description().name,
+            path.to_string_lossy()
+        );
+        data_source.insert(name, Box::new(plugin));
+    }
 
     info!("Initializing widgets");
     let mut widgets = Vec::new();
@@ -112,23 +244,62 @@ async fn main() -> eyre::Result<()> {
         (begin.elapsed().as_millis() as f64) / 1000.0
     );
 
+    let exported_stats = if measurrred_config.exporter.enabled {
+        let stats = Arc::new(Mutex::new(Stats::default()));
+        let port = measurrred_config.exporter.port;
+        let served = stats.clone();
+        async_std::task::spawn(async move {
+            if let Err(e) = exporter::serve(port, served).await {
+                error!("Failed to serve metrics on port {}: {}", port, e);
+            }
+        });
+        Some(stats)
+    } else {
+        None
+    };
+
     let mut overlay_w = overlay.clone();
     let handle = thread::spawn(move || -> eyre::Result<()> {
+        let mut stats = Stats::default();
         loop {
             let begin = Instant::now();
+            stats.begin_frame();
+
+            let variant = measurrred_config.theme.variant.variant();
+            if variant != Theme::variant() {
+                info!("Switching to the {:?} theme", variant);
+                Theme::set_variant(variant);
+                // The background is the color key of the overlay.
+                overlay_w.accept_config(&measurrred_config)?;
+            }
 
-            for data_source in data_source.values_mut() {
-                data_source.update()?;
+            // Plugins and commands may fail at any time, which should not stop the others.
+            for (name, data_source) in data_source.iter_mut() {
+                let update_begin = Instant::now();
+                let result = data_source.update();
+                let source_stats = stats.data_source(name);
+                source_stats.update_seconds = update_begin.elapsed().as_secs_f64();
+                source_stats.fetch_seconds = data_source.fetch_seconds();
+                if let Err(e) = result {
+                    source_stats.errors += 1;
+                    warn!("Failed to update {}: {}", name, e);
+                }
             }
 
             let mut variables = HashMap::new();
             for query in &data_queries {
-                let data = data_source
+                let query_begin = Instant::now();
+                let result = data_source
                     .get_mut(&query.source)
                     .ok_or(eyre::eyre!("Unknown data source: {}", &query.source))
-                    .and_then(|source| source.query(&query.query, &query.format))
-                    .map_err(|e| eyre::eyre!("error while querying {}: {}", query.query, e))
-                    .unwrap();
+                    .and_then(|source| source.query(&query.query, &query.format));
+                let source_stats = stats.data_source(&query.source);
+                source_stats.query_seconds += query_begin.elapsed().as_secs_f64();
+                let data = result.unwrap_or_else(|e| {
+                    source_stats.errors += 1;
+                    warn!("Error while querying {}: {}", query.query, e);
+                    Data::Unknown
+                });
                 variables.insert(query.name.clone(), data);
             }
 
@@ -151,20 +322,32 @@ async fn main() -> eyre::Result<()> {
             );
             let zoom = overlay_w.zoom()?;
             for widget in widgets.iter_mut() {
-                widget
-                    .render(
-                        &measurrred_config,
-                        &usvg_options,
-                        &mut pixmap,
-                        overlay_w.target.rect()?,
-                        zoom,
-                        &variables,
-                    )
-                    .unwrap();
+                let render_begin = Instant::now();
+                let result = widget.render(
+                    &measurrred_config,
+                    &usvg_options,
+                    &mut pixmap,
+                    overlay_w.target.rect()?,
+                    zoom,
+                    &variables,
+                );
+                let widget_stats = stats.widget(&widget.name);
+                widget_stats.render_seconds = render_begin.elapsed().as_secs_f64();
+                if let Err(e) = result {
+                    widget_stats.errors += 1;
+                    warn!("Failed to render {}: {}", widget.name, e);
+                }
             }
             overlay_w.accept_pixmap(pixmap)?;
             overlay_w.redraw()?;
 
+            stats.finish_frame(begin.elapsed(), &variables);
+            if let Some(exported) = &exported_stats {
+                if let Ok(mut exported) = exported.lock() {
+                    *exported = stats.clone();
+                }
+            }
+
             let delta = begin.elapsed().as_millis() as u64;
 
             if delta >= measurrred_config.general.refresh_interval {
@@ -2,4 +2,5 @@ pub mod contextmenu;
 pub mod dpi;
 pub mod monitor;
 pub mod taskbar;
+pub mod theme;
 pub mod tray;
@@ -1,4 +1,10 @@
-use std::{collections::HashMap, ptr::null_mut, sync::RwLock};
+use std::{
+    collections::HashMap,
+    mem::size_of,
+    ptr::{null, null_mut},
+    slice,
+    sync::RwLock,
+};
 
 use once_cell::sync::{Lazy, OnceCell};
 use thiserror::Error;
@@ -8,19 +14,22 @@ use tracing_unwrap::{OptionExt, ResultExt};
 use windows::{
     core::PCWSTR,
     Win32::{
-        Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Foundation::{HANDLE, HWND, LPARAM, LRESULT, POINT, RECT, SIZE, WPARAM},
         Graphics::Gdi::{
-            BeginPaint, BitBlt, CreateBitmap, CreateCompatibleDC, CreateSolidBrush, DeleteDC,
-            DeleteObject, EndPaint, FillRect, RedrawWindow, SelectObject, HRGN, PAINTSTRUCT,
-            RDW_INVALIDATE, RDW_UPDATENOW, SRCPAINT,
+            BeginPaint, BitBlt, CreateBitmap, CreateCompatibleDC, CreateDIBSection,
+            CreateSolidBrush, DeleteDC, DeleteObject, EndPaint, FillRect, GetDC, RedrawWindow,
+            ReleaseDC, SelectObject, AC_SRC_ALPHA, AC_SRC_OVER, BITMAPINFO, BITMAPINFOHEADER,
+            BI_RGB, BLENDFUNCTION, DIB_RGB_COLORS, HRGN, PAINTSTRUCT, RDW_INVALIDATE,
+            RDW_UPDATENOW, SRCPAINT,
         },
         System::LibraryLoader::GetModuleHandleW,
         UI::WindowsAndMessaging::{
             CreateWindowExW, DefWindowProcW, DispatchMessageW, GetClientRect, GetMessageW,
-            MoveWindow, PostQuitMessage, RegisterClassW, SetLayeredWindowAttributes, ShowWindow,
-            TranslateMessage, CS_HREDRAW, CS_VREDRAW, HMENU, LWA_COLORKEY, MSG, SW_SHOW,
-            WM_DESTROY, WM_DPICHANGED, WM_PAINT, WNDCLASSW, WS_EX_LAYERED, WS_EX_TOPMOST, WS_POPUP,
-            WS_VISIBLE,
+            GetWindowLongPtrW, MoveWindow, PostQuitMessage, RegisterClassW,
+            SetLayeredWindowAttributes, SetWindowLongPtrW, ShowWindow, TranslateMessage,
+            UpdateLayeredWindow, CS_HREDRAW, CS_VREDRAW, GWL_EXSTYLE, HMENU, LWA_COLORKEY, MSG,
+            SW_SHOW, ULW_ALPHA, WM_DESTROY, WM_DPICHANGED, WM_PAINT, WNDCLASSW, WS_EX_LAYERED,
+            WS_EX_TOPMOST, WS_POPUP, WS_VISIBLE,
         },
     },
 };
@@ -52,6 +61,8 @@ pub struct ActualTaskbarOverlay {
     target: TaskbarHandle,
     pixmap: Option<Pixmap>,
     background_color: u32,
+    /// Whether the background is translucent, which a color key cannot express.
+    per_pixel_alpha: bool,
 }
 
 #[derive(Error, Debug)]
@@ -131,6 +142,7 @@ impl TaskbarOverlay {
             hwnd: hwnd.clone(),
             target: target.clone(),
             background_color: 0,
+            per_pixel_alpha: false,
             pixmap: None,
         };
 
@@ -154,12 +166,23 @@ impl TaskbarOverlay {
             .map_err(|_| TaskbarOverlayError::MutexLockPoisoned)?;
         let mut actual_self = map.get_mut(&self.hwnd.0).unwrap_or_log();
 
-        actual_self.background_color = config.general.background_color.to_windows_color();
+        let background_color = &config.general.background_color;
+        actual_self.background_color = background_color.to_windows_color();
+        actual_self.per_pixel_alpha = !background_color.is_opaque();
 
-        unsafe {
-            SetLayeredWindowAttributes(self.hwnd, actual_self.background_color, 0, LWA_COLORKEY)
+        if actual_self.per_pixel_alpha {
+            // UpdateLayeredWindow fails once a color key is set, until the window is layered anew.
+            unsafe {
+                let style = GetWindowLongPtrW(self.hwnd, GWL_EXSTYLE);
+                SetWindowLongPtrW(self.hwnd, GWL_EXSTYLE, style & !(WS_EX_LAYERED.0 as isize));
+                SetWindowLongPtrW(self.hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED.0 as isize);
+            }
+        } else {
+            unsafe {
+                SetLayeredWindowAttributes(self.hwnd, actual_self.background_color, 0, LWA_COLORKEY)
+            }
+            .ok()?;
         }
-        .ok()?;
 
         Ok(())
     }
@@ -181,6 +204,17 @@ impl TaskbarOverlay {
     }
 
     pub fn redraw(&self) -> eyre::Result<()> {
+        {
+            let map = OVERLAY_INSTANCES
+                .read()
+                .map_err(|_| TaskbarOverlayError::MutexLockPoisoned)?;
+            let actual_self = map.get(&self.hwnd.0).unwrap_or_log();
+            if actual_self.per_pixel_alpha {
+                actual_self.update_layered()?;
+                return Ok(());
+            }
+        }
+
         let fail = unsafe {
             RedrawWindow(
                 self.hwnd,
@@ -232,6 +266,77 @@ impl TaskbarOverlay {
 }
 
 impl ActualTaskbarOverlay {
+    /// Hands the pixmap over with its alpha channel, in place of `WM_PAINT`.
+    fn update_layered(&self) -> windows::core::Result<()> {
+        let pixmap = match &self.pixmap {
+            Some(pixmap) => pixmap,
+            None => return Ok(()),
+        };
+        let width = pixmap.width() as i32;
+        let height = pixmap.height() as i32;
+
+        unsafe {
+            let screen = GetDC(HWND(0));
+            let dc = CreateCompatibleDC(screen);
+            let info = BITMAPINFO {
+                bmiHeader: BITMAPINFOHEADER {
+                    biSize: size_of::<BITMAPINFOHEADER>() as u32,
+                    biWidth: width,
+                    // Top-down, like the pixmap.
+                    biHeight: -height,
+                    biPlanes: 1,
+                    biBitCount: 32,
+                    biCompression: BI_RGB as u32,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut bits = null_mut();
+            let result = CreateDIBSection(dc, &info, DIB_RGB_COLORS, &mut bits, HANDLE(0), 0)
+                .and_then(|bitmap| {
+                    // Both are premultiplied, only the order of channels differs.
+                    let target =
+                        slice::from_raw_parts_mut(bits as *mut u32, (width * height) as usize);
+                    for (target, color) in target.iter_mut().zip(pixmap.pixels()) {
+                        *target = (color.blue() as u32)
+                            | ((color.green() as u32) << 8)
+                            | ((color.red() as u32) << 16)
+                            | ((color.alpha() as u32) << 24);
+                    }
+
+                    let previous = SelectObject(dc, bitmap);
+                    let size = SIZE {
+                        cx: width,
+                        cy: height,
+                    };
+                    let blend = BLENDFUNCTION {
+                        BlendOp: AC_SRC_OVER as u8,
+                        BlendFlags: 0,
+                        SourceConstantAlpha: 0xFF,
+                        AlphaFormat: AC_SRC_ALPHA as u8,
+                    };
+                    let updated = UpdateLayeredWindow(
+                        self.hwnd,
+                        screen,
+                        null(),
+                        &size,
+                        dc,
+                        &POINT::default(),
+                        0,
+                        &blend,
+                        ULW_ALPHA,
+                    );
+                    SelectObject(dc, previous);
+                    DeleteObject(bitmap);
+                    updated.ok()
+                });
+            DeleteDC(dc);
+            ReleaseDC(HWND(0), screen);
+
+            result
+        }
+    }
+
     fn update_layout(&self) -> windows::core::Result<()> {
         let target_rect = self.target.rect()?;
         unsafe {
@@ -0,0 +1,25 @@
+use std::{ffi::c_void, mem::size_of, ptr::null_mut};
+
+use windows::Win32::{
+    Foundation::ERROR_SUCCESS,
+    System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD},
+};
+
+/// Whether apps should be light, as chosen in the personalization settings.
+pub fn apps_use_light_theme() -> Option<bool> {
+    let mut value = 0u32;
+    let mut size = size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+            "AppsUseLightTheme",
+            RRF_RT_REG_DWORD,
+            null_mut(),
+            &mut value as *mut u32 as *mut c_void,
+            &mut size,
+        )
+    };
+
+    (result == ERROR_SUCCESS).then_some(value != 0)
+}
@@ -1,18 +1,44 @@
-use std::{fmt, str::FromStr};
+use std::{collections::HashMap, fmt, str::FromStr};
 
+use declarrred::rt::Data;
 use serde::{Deserialize, Serialize};
 use svgtypes::Color as SvgColor;
+use thiserror::Error;
 use tiny_skia::Color as TinySkiaColor;
-use usvg::Color as UsvgColor;
+use tracing::warn;
+use usvg::{Color as UsvgColor, Opacity};
 
+use super::{ColorScale, ColorScaleParseError, Theme};
+
+#[derive(Clone)]
 pub struct Color {
-    handle: UsvgColor,
+    handle: ColorHandle,
     origin: String,
 }
 
+#[derive(Clone)]
+enum ColorHandle {
+    Literal(SvgColor),
+    /// `theme:<token>`, resolved whenever used as the theme may change.
+    Theme(String),
+    /// Depends on a variable, see [`Color::at`].
+    Scale(ColorScale),
+}
+
+#[derive(Error, Debug)]
+pub enum ColorParseError {
+    #[error("{0}")]
+    Svg(#[from] svgtypes::Error),
+    #[error("{0}")]
+    Scale(#[from] ColorScaleParseError),
+}
+
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.origin)
+        match self.handle {
+            ColorHandle::Literal(_) => write!(f, "{}", self.origin),
+            ColorHandle::Theme(_) | ColorHandle::Scale(_) => write!(f, "{}", self.to_hex()),
+        }
     }
 }
 
@@ -21,18 +47,31 @@ impl Serialize for Color {
     where
         S: serde::Serializer,
     {
-        serializer.collect_str(&self.to_string())
+        serializer.collect_str(&self.origin)
     }
 }
 
 impl FromStr for Color {
-    type Err = <SvgColor as FromStr>::Err;
+    type Err = ColorParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("scale(") {
+            return Ok(Color {
+                handle: ColorHandle::Scale(ColorScale::from_str(s)?),
+                origin: s.to_string(),
+            });
+        }
+        if let Some(token) = s.strip_prefix("theme:") {
+            return Ok(Color {
+                handle: ColorHandle::Theme(token.to_string()),
+                origin: s.to_string(),
+            });
+        }
+
         let color = SvgColor::from_str(s)?;
 
         Ok(Color {
-            handle: UsvgColor::new_rgb(color.red, color.green, color.blue),
+            handle: ColorHandle::Literal(color),
             origin: s.to_string(),
         })
     }
@@ -45,20 +84,55 @@ impl<'de> Deserialize<'de> for Color {
     {
         let s = String::deserialize(deserializer)?;
         Color::from_str(&s)
-            .map_err(|_| serde::de::Error::custom(format!("Failed to parse color {}", s)))
+            .map_err(|e| serde::de::Error::custom(format!("Failed to parse color {}: {}", s, e)))
     }
 }
 
 impl Color {
+    /// Settles a color scale with `variables`, which otherwise takes the color of its first stop.
+    pub fn at(&self, variables: &HashMap<String, Data>) -> Color {
+        match &self.handle {
+            ColorHandle::Scale(scale) => Color {
+                handle: ColorHandle::Literal(scale.evaluate(Some(variables))),
+                origin: self.origin.clone(),
+            },
+            _ => self.clone(),
+        }
+    }
+
+    pub(super) fn resolve(&self) -> SvgColor {
+        match &self.handle {
+            ColorHandle::Literal(color) => *color,
+            ColorHandle::Scale(scale) => scale.evaluate(None),
+            ColorHandle::Theme(token) => Theme::resolve(token).unwrap_or_else(|| {
+                warn!("There are no theme color named {}", token);
+                SvgColor::black()
+            }),
+        }
+    }
+
     pub fn to_usvg_color(&self) -> UsvgColor {
-        self.handle.clone()
+        let color = self.resolve();
+        UsvgColor::new_rgb(color.red, color.green, color.blue)
+    }
+    /// The alpha channel, which usvg keeps apart from colors.
+    pub fn to_usvg_opacity(&self) -> Opacity {
+        Opacity::new(self.resolve().alpha as f64 / 255.0)
     }
     pub fn to_tiny_skia_color(&self) -> TinySkiaColor {
-        TinySkiaColor::from_rgba8(self.handle.red, self.handle.green, self.handle.blue, 0xFF)
+        let color = self.resolve();
+        TinySkiaColor::from_rgba8(color.red, color.green, color.blue, color.alpha)
     }
     pub fn to_windows_color(&self) -> u32 {
-        (self.handle.red as u32)
-            | ((self.handle.green as u32) << 8)
-            | ((self.handle.blue as u32) << 16)
+        let color = self.resolve();
+        (color.red as u32) | ((color.green as u32) << 8) | ((color.blue as u32) << 16)
+    }
+    /// `#rrggbb`, without the alpha channel.
+    pub fn to_hex(&self) -> String {
+        let color = self.resolve();
+        format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+    }
+    pub fn is_opaque(&self) -> bool {
+        self.resolve().alpha == 0xFF
     }
 }
@@ -0,0 +1,275 @@
+use std::{collections::HashMap, str::FromStr};
+
+use declarrred::rt::Data;
+use svgtypes::Color as SvgColor;
+use thiserror::Error;
+
+use super::{paint::function_arguments, Color};
+
+/// `scale(<variable>, <value>:<color>, ... [, step | linear] [, srgb | oklab])`
+#[derive(Clone)]
+pub struct ColorScale {
+    variable: String,
+    /// Sorted by value.
+    stops: Vec<(f64, Color)>,
+    mode: ScaleMode,
+    space: ColorSpace,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ScaleMode {
+    /// The color of the greatest stop not above the value.
+    Step,
+    /// Blends the two stops around the value.
+    Linear,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColorSpace {
+    Srgb,
+    Oklab,
+}
+
+#[derive(Error, Debug)]
+pub enum ColorScaleParseError {
+    #[error("A color scale needs a variable and at least one stop")]
+    TooFewArguments,
+    #[error("Failed to parse color scale stop {0}, expected <value>:<color>")]
+    Stop(String),
+}
+
+impl FromStr for ColorScale {
+    type Err = ColorScaleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let arguments =
+            function_arguments(s, "scale").ok_or(ColorScaleParseError::TooFewArguments)?;
+        let (variable, arguments) = arguments
+            .split_first()
+            .ok_or(ColorScaleParseError::TooFewArguments)?;
+
+        let mut scale = ColorScale {
+            variable: variable.clone(),
+            stops: Vec::new(),
+            mode: ScaleMode::Linear,
+            space: ColorSpace::Oklab,
+        };
+        for argument in arguments {
+            match argument.as_str() {
+                "step" => scale.mode = ScaleMode::Step,
+                "linear" => scale.mode = ScaleMode::Linear,
+                "srgb" => scale.space = ColorSpace::Srgb,
+                "oklab" => scale.space = ColorSpace::Oklab,
+                _ => {
+                    let stop = || ColorScaleParseError::Stop(argument.clone());
+                    let (value, color) = argument.split_once(':').ok_or_else(stop)?;
+                    scale.stops.push((
+                        value.trim().parse().map_err(|_| stop())?,
+                        Color::from_str(color.trim()).map_err(|_| stop())?,
+                    ));
+                }
+            }
+        }
+        if scale.stops.is_empty() {
+            return Err(ColorScaleParseError::TooFewArguments);
+        }
+        scale.stops.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        Ok(scale)
+    }
+}
+
+impl ColorScale {
+    /// The color for `variables`, or of the first stop when the variable is not known.
+    pub fn evaluate(&self, variables: Option<&HashMap<String, Data>>) -> SvgColor {
+        let value = variables
+            .and_then(|variables| variables.get(&self.variable))
+            .and_then(|data| data.as_float().ok())
+            .filter(|value| !value.is_nan());
+        let value = match value {
+            Some(value) => value,
+            None => return self.stops[0].1.resolve(),
+        };
+
+        let next = self.stops.partition_point(|(stop, _)| *stop <= value);
+        match (next, self.mode) {
+            (0, _) => self.stops[0].1.resolve(),
+            (next, _) if next == self.stops.len() => self.stops[next - 1].1.resolve(),
+            (next, ScaleMode::Step) => self.stops[next - 1].1.resolve(),
+            (next, ScaleMode::Linear) => {
+                let (from, from_color) = &self.stops[next - 1];
+                let (to, to_color) = &self.stops[next];
+                let t = (value - from) / (to - from);
+                mix(from_color.resolve(), to_color.resolve(), t, self.space)
+            }
+        }
+    }
+}
+
+fn mix(from: SvgColor, to: SvgColor, t: f64, space: ColorSpace) -> SvgColor {
+    let lerp = |a: f64, b: f64| a + (b - a) * t;
+    let alpha = lerp(from.alpha as f64, to.alpha as f64).round() as u8;
+
+    match space {
+        ColorSpace::Srgb => SvgColor::new_rgba(
+            lerp(from.red as f64, to.red as f64).round() as u8,
+            lerp(from.green as f64, to.green as f64).round() as u8,
+            lerp(from.blue as f64, to.blue as f64).round() as u8,
+            alpha,
+        ),
+        ColorSpace::Oklab => {
+            let (from, to) = (to_oklab(from), to_oklab(to));
+            let [red, green, blue] = from_oklab([
+                lerp(from[0], to[0]),
+                lerp(from[1], to[1]),
+                lerp(from[2], to[2]),
+            ]);
+            SvgColor::new_rgba(red, green, blue, alpha)
+        }
+    }
+}
+
+// https://bottosson.github.io/posts/oklab/
+fn to_oklab(color: SvgColor) -> [f64; 3] {
+    let linear = |channel: u8| {
+        let c = channel as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(color.red), linear(color.green), linear(color.blue));
+
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+fn from_oklab([lightness, a, b]: [f64; 3]) -> [u8; 3] {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+
+    let gamma = |c: f64| {
+        let c = if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        (c.clamp(0.0, 1.0) * 255.0).round() as u8
+    };
+
+    [
+        gamma(4.0767416621 * l - 3.3077085913 * m + 0.2309667516 * s),
+        gamma(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+        gamma(-0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color(scale: &ColorScale, value: Option<f64>) -> (u8, u8, u8, u8) {
+        let variables = value
+            .map(|value| HashMap::from([(scale.variable.clone(), Data::F64(value))]))
+            .unwrap_or_default();
+        let color = scale.evaluate(Some(&variables));
+        (color.red, color.green, color.blue, color.alpha)
+    }
+
+    #[test]
+    fn parses_stops_and_options() {
+        let scale = ColorScale::from_str("scale(cpu%, 90:#e03131, 0:#2f9e44, step, srgb)").unwrap();
+        assert_eq!(scale.variable, "cpu%");
+        assert_eq!(
+            scale
+                .stops
+                .iter()
+                .map(|(value, _)| *value)
+                .collect::<Vec<_>>(),
+            vec![0.0, 90.0]
+        );
+        assert_eq!(scale.stops[0].1.to_hex(), "#2f9e44");
+        assert!(scale.mode == ScaleMode::Step);
+        assert!(scale.space == ColorSpace::Srgb);
+
+        let scale = ColorScale::from_str("scale(ram, 0:rgba(0, 0, 0, 0.5))").unwrap();
+        assert!(scale.mode == ScaleMode::Linear);
+        assert!(scale.space == ColorSpace::Oklab);
+    }
+
+    #[test]
+    fn rejects_malformed_scales() {
+        assert!(matches!(
+            ColorScale::from_str("scale(cpu%)"),
+            Err(ColorScaleParseError::TooFewArguments)
+        ));
+        assert!(matches!(
+            ColorScale::from_str("scale(cpu%, step)"),
+            Err(ColorScaleParseError::TooFewArguments)
+        ));
+        assert!(matches!(
+            ColorScale::from_str("scale(cpu%, 10-red)"),
+            Err(ColorScaleParseError::Stop(_))
+        ));
+        assert!(matches!(
+            ColorScale::from_str("scale(cpu%, ten:red)"),
+            Err(ColorScaleParseError::Stop(_))
+        ));
+        assert!(matches!(
+            ColorScale::from_str("scale(cpu%, 10:nocolor)"),
+            Err(ColorScaleParseError::Stop(_))
+        ));
+    }
+
+    #[test]
+    fn blends_between_stops() {
+        let scale = ColorScale::from_str("scale(v, 0:#00000000, 100:#ffffffff, srgb)").unwrap();
+        assert_eq!(color(&scale, Some(50.0)), (128, 128, 128, 128));
+        assert_eq!(color(&scale, Some(25.0)), (64, 64, 64, 64));
+        assert_eq!(color(&scale, Some(-10.0)), (0, 0, 0, 0));
+        assert_eq!(color(&scale, Some(200.0)), (255, 255, 255, 255));
+        assert_eq!(color(&scale, None), (0, 0, 0, 0));
+        assert_eq!(color(&scale, Some(f64::NAN)), (0, 0, 0, 0));
+
+        // Mid grey in OKLab is lighter than the sRGB average.
+        let scale = ColorScale::from_str("scale(v, 0:black, 100:white)").unwrap();
+        let (red, green, blue, alpha) = color(&scale, Some(50.0));
+        assert_eq!((red, alpha), (99, 255));
+        assert_eq!((red, red), (green, blue));
+    }
+
+    #[test]
+    fn steps_at_stops() {
+        let scale =
+            ColorScale::from_str("scale(v, 0:#000000, 60:#ff0000, 90:#00ff00, step)").unwrap();
+        assert_eq!(color(&scale, Some(59.9)), (0, 0, 0, 255));
+        assert_eq!(color(&scale, Some(60.0)), (255, 0, 0, 255));
+        assert_eq!(color(&scale, Some(89.0)), (255, 0, 0, 255));
+        assert_eq!(color(&scale, Some(95.0)), (0, 255, 0, 255));
+    }
+
+    #[test]
+    fn round_trips_through_oklab() {
+        for red in (0..=255).step_by(15) {
+            for green in (0..=255).step_by(15) {
+                for blue in (0..=255).step_by(15) {
+                    let color = SvgColor::new_rgb(red, green, blue);
+                    assert_eq!(from_oklab(to_oklab(color)), [red, green, blue]);
+                }
+            }
+        }
+
+        let [lightness, a, b] = to_oklab(SvgColor::white());
+        assert!((lightness - 1.0).abs() < 1e-4);
+        assert!(a.abs() < 1e-4 && b.abs() < 1e-4);
+    }
+}
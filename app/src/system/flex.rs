@@ -0,0 +1,718 @@
+//! The measure and arrange pass of `<hbox>` and `<vbox>`: children come in with the size they
+//! were drawn at, and go out with where they are put in the box, without drawing anything.
+
+use std::ops::Range;
+
+use serde::Deserialize;
+
+/// Where an item goes in its box, with `x` and `y` from the top left of the box.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Bounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Row,
+    Column,
+}
+
+/// How items share the room left along the main axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Justify {
+    #[default]
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+impl Justify {
+    /// Space before the first of `count` items and between each of them, given `room` left.
+    fn spacing(&self, room: f64, count: usize) -> (f64, f64) {
+        let count = count as f64;
+        match self {
+            _ if count == 0.0 => (0.0, 0.0),
+            Justify::Start => (0.0, 0.0),
+            Justify::End => (room, 0.0),
+            Justify::Center => (room / 2.0, 0.0),
+            Justify::SpaceBetween if count == 1.0 => (0.0, 0.0),
+            Justify::SpaceBetween => (0.0, room / (count - 1.0)),
+            Justify::SpaceAround => (room / count / 2.0, room / count),
+            Justify::SpaceEvenly => (room / (count + 1.0), room / (count + 1.0)),
+        }
+    }
+}
+
+/// Where an item goes across the main axis, in its line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Align {
+    #[default]
+    Start,
+    End,
+    Center,
+    /// Takes the whole line, as far as the size limits of the item let it.
+    Stretch,
+}
+
+impl Align {
+    /// How far to move what is aligned, given `room` left around it.
+    pub fn offset(&self, room: f64) -> f64 {
+        match self {
+            Align::Start | Align::Stretch => 0.0,
+            Align::End => room,
+            Align::Center => room / 2.0,
+        }
+    }
+}
+
+/// A fixed size, or the least and the most a size can be.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SizeLimit {
+    pub fixed: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl SizeLimit {
+    /// `size` made to fit, the least winning over the most like in CSS.
+    pub fn clamp(&self, size: f64) -> f64 {
+        let size = self.fixed.unwrap_or(size);
+        let size = self.max.map_or(size, |max| size.min(max));
+        self.min.map_or(size, |min| size.max(min))
+    }
+
+    /// The room there is inside, if it is bounded.
+    pub fn room(&self) -> Option<f64> {
+        self.fixed.or(self.max).map(|room| self.clamp(room))
+    }
+
+    /// The limit of a box its container gives `size`: fixed to it, as far as the limit lets it.
+    pub fn given(&self, size: f64) -> SizeLimit {
+        SizeLimit {
+            fixed: Some(self.clamp(size)),
+            ..*self
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlexItem {
+    /// Size the item takes unless it grows, shrinks or stretches.
+    pub width: f64,
+    pub height: f64,
+    /// Share of the room left the item takes.
+    pub grow: f64,
+    /// Share of the room missing the item gives up, weighted by its size.
+    pub shrink: f64,
+    pub width_limit: SizeLimit,
+    pub height_limit: SizeLimit,
+    /// Overrides the `align` of the box.
+    pub align: Option<Align>,
+    /// Space before the item along the main axis, on top of the gap.
+    pub margin: f64,
+    /// Where the item starts from the start of its line, instead of after the previous one.
+    pub position: Option<f64>,
+    /// Starts the item with the previous one, over it, instead of after it.
+    pub overlap: bool,
+}
+
+impl FlexItem {
+    pub fn new(width: f64, height: f64) -> Self {
+        FlexItem {
+            width,
+            height,
+            grow: 0.0,
+            shrink: 1.0,
+            width_limit: SizeLimit::default(),
+            height_limit: SizeLimit::default(),
+            align: None,
+            margin: 0.0,
+            position: None,
+            overlap: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Flex {
+    pub direction: Direction,
+    pub justify: Justify,
+    pub align: Align,
+    /// Space between items, and between lines.
+    pub gap: f64,
+    /// Breaks items into more lines instead of shrinking them, when there is a most size.
+    pub wrap: bool,
+    /// Size of the box inside, without its padding and border.
+    pub width: SizeLimit,
+    pub height: SizeLimit,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlexLayout {
+    pub width: f64,
+    pub height: f64,
+    /// Bounds of each item, in the order they came in.
+    pub items: Vec<Bounds>,
+}
+
+impl Flex {
+    pub fn new(direction: Direction) -> Self {
+        Flex {
+            direction,
+            justify: Justify::default(),
+            align: Align::default(),
+            gap: 0.0,
+            wrap: false,
+            width: SizeLimit::default(),
+            height: SizeLimit::default(),
+        }
+    }
+
+    pub fn layout(&self, items: &[FlexItem]) -> FlexLayout {
+        let row = self.direction == Direction::Row;
+        let (main_limit, cross_limit) = if row {
+            (self.width, self.height)
+        } else {
+            (self.height, self.width)
+        };
+        let (main_limits, cross_limits): (Vec<_>, Vec<_>) = items
+            .iter()
+            .map(|item| {
+                if row {
+                    (item.width_limit, item.height_limit)
+                } else {
+                    (item.height_limit, item.width_limit)
+                }
+            })
+            .unzip();
+        let bases = items
+            .iter()
+            .zip(&main_limits)
+            .map(|(item, limit)| limit.clamp(if row { item.width } else { item.height }))
+            .collect::<Vec<_>>();
+        let crosses = items
+            .iter()
+            .zip(&cross_limits)
+            .map(|(item, limit)| limit.clamp(if row { item.height } else { item.width }))
+            .collect::<Vec<_>>();
+
+        let room = main_limit.room();
+        let lines = self.lines(items, &bases, room);
+
+        let mut sizes = bases.clone();
+        if let Some(room) = room {
+            for line in &lines {
+                let (_, extent) = self.place(&items[line.clone()], &bases[line.clone()], 0.0, 0.0);
+                let free = room - extent;
+                let factors = items[line.clone()]
+                    .iter()
+                    .zip(&bases[line.clone()])
+                    .map(|(item, base)| {
+                        if free > 0.0 {
+                            item.grow
+                        } else {
+                            item.shrink * base
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                sizes[line.clone()].copy_from_slice(&resolve(
+                    &bases[line.clone()],
+                    &factors,
+                    &main_limits[line.clone()],
+                    free,
+                ));
+            }
+        }
+
+        let extents = lines
+            .iter()
+            .map(|line| {
+                self.place(&items[line.clone()], &sizes[line.clone()], 0.0, 0.0)
+                    .1
+            })
+            .collect::<Vec<_>>();
+        let main_size = main_limit.clamp(extents.iter().copied().fold(0.0, f64::max));
+
+        let mut line_crosses = lines
+            .iter()
+            .map(|line| crosses[line.clone()].iter().copied().fold(0.0, f64::max))
+            .collect::<Vec<_>>();
+        let gaps = self.gap * lines.len().saturating_sub(1) as f64;
+        let cross_size = cross_limit.clamp(line_crosses.iter().sum::<f64>() + gaps);
+        if let [line_cross] = &mut line_crosses[..] {
+            *line_cross = cross_size;
+        }
+
+        let mut bounds = vec![Bounds::default(); items.len()];
+        let mut line_start = 0.0;
+        for ((line, extent), line_cross) in lines.iter().zip(extents).zip(line_crosses) {
+            let items = &items[line.clone()];
+            let count = items
+                .iter()
+                .enumerate()
+                .filter(|(index, item)| *index == 0 || !item.overlap)
+                .count();
+            let (lead, between) = self.justify.spacing((main_size - extent).max(0.0), count);
+            let (starts, _) = self.place(items, &sizes[line.clone()], lead, between);
+
+            for (index, (item, start)) in line.clone().zip(items.iter().zip(starts)) {
+                let align = item.align.unwrap_or(self.align);
+                let cross = if align == Align::Stretch {
+                    cross_limits[index].clamp(line_cross)
+                } else {
+                    crosses[index]
+                };
+                let cross_start = line_start + align.offset(line_cross - cross);
+                bounds[index] = if row {
+                    Bounds {
+                        x: start,
+                        y: cross_start,
+                        width: sizes[index],
+                        height: cross,
+                    }
+                } else {
+                    Bounds {
+                        x: cross_start,
+                        y: start,
+                        width: cross,
+                        height: sizes[index],
+                    }
+                };
+            }
+            line_start += line_cross + self.gap;
+        }
+
+        let (width, height) = if row {
+            (main_size, cross_size)
+        } else {
+            (cross_size, main_size)
+        };
+        FlexLayout {
+            width,
+            height,
+            items: bounds,
+        }
+    }
+
+    /// Splits items into lines no longer than `room`, or keeps them in one without wrapping.
+    fn lines(&self, items: &[FlexItem], sizes: &[f64], room: Option<f64>) -> Vec<Range<usize>> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        if let (true, Some(room)) = (self.wrap, room) {
+            for (index, item) in items.iter().enumerate() {
+                // Overlapping and positioned items stay with what they are put against.
+                if index == start || item.overlap || item.position.is_some() {
+                    continue;
+                }
+                let (_, extent) =
+                    self.place(&items[start..=index], &sizes[start..=index], 0.0, 0.0);
+                if extent - room > 1e-6 {
+                    lines.push(start..index);
+                    start = index;
+                }
+            }
+        }
+        lines.push(start..items.len());
+        lines
+    }
+
+    /// Starts of the items of a line along the main axis, and how far the line reaches.
+    ///
+    /// `lead` goes before the first item and `between` between each of them, as spread by `justify`.
+    fn place(&self, items: &[FlexItem], sizes: &[f64], lead: f64, between: f64) -> (Vec<f64>, f64) {
+        let mut starts = Vec::with_capacity(items.len());
+        let mut placed = false;
+        let mut cursor = 0.0;
+        let mut previous = 0.0;
+        let mut extent = 0.0f64;
+        for (item, &size) in items.iter().zip(sizes) {
+            let start = if item.overlap && placed {
+                let start = previous + item.margin;
+                cursor = f64::max(cursor, start + size);
+                start
+            } else {
+                let start = match item.position {
+                    Some(position) => position,
+                    None if placed => cursor + self.gap + between,
+                    None => cursor + lead,
+                } + item.margin;
+                previous = start;
+                placed = true;
+                cursor = start + size;
+                start
+            };
+            extent = extent.max(cursor);
+            starts.push(start);
+        }
+        (starts, extent)
+    }
+}
+
+/// Sizes of items grown or shrunk by `free` space shared by `factors`, freezing the ones hitting
+/// their limits and sharing again what they couldn't take among the rest.
+fn resolve(bases: &[f64], factors: &[f64], limits: &[SizeLimit], free: f64) -> Vec<f64> {
+    let mut sizes = bases.to_vec();
+    let mut frozen = factors
+        .iter()
+        .map(|&factor| factor <= 0.0)
+        .collect::<Vec<_>>();
+    loop {
+        let open = (0..sizes.len())
+            .filter(|&index| !frozen[index])
+            .collect::<Vec<_>>();
+        let total = open.iter().map(|&index| factors[index]).sum::<f64>();
+        if total <= 0.0 {
+            break;
+        }
+        let taken = (0..sizes.len())
+            .filter(|&index| frozen[index])
+            .map(|index| sizes[index] - bases[index])
+            .sum::<f64>();
+        let left = free - taken;
+
+        let mut clamped = false;
+        for index in open {
+            let target = bases[index] + left * factors[index] / total;
+            let size = limits[index].clamp(target.max(0.0));
+            sizes[index] = size;
+            if size != target {
+                frozen[index] = true;
+                clamped = true;
+            }
+        }
+        if !clamped {
+            break;
+        }
+    }
+    sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(x: f64, y: f64, width: f64, height: f64) -> Bounds {
+        Bounds {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn row(width: Option<f64>) -> Flex {
+        Flex {
+            width: SizeLimit {
+                fixed: width,
+                ..Default::default()
+            },
+            ..Flex::new(Direction::Row)
+        }
+    }
+
+    #[test]
+    fn places_items_one_after_another() {
+        let flex = Flex {
+            gap: 4.0,
+            ..row(None)
+        };
+        let layout = flex.layout(&[FlexItem::new(10.0, 5.0), FlexItem::new(20.0, 8.0)]);
+        assert_eq!(layout.width, 34.0);
+        assert_eq!(layout.height, 8.0);
+        assert_eq!(
+            layout.items,
+            vec![bounds(0.0, 0.0, 10.0, 5.0), bounds(14.0, 0.0, 20.0, 8.0)]
+        );
+    }
+
+    #[test]
+    fn stacks_items_in_a_column() {
+        let layout = Flex::new(Direction::Column)
+            .layout(&[FlexItem::new(10.0, 5.0), FlexItem::new(20.0, 8.0)]);
+        assert_eq!((layout.width, layout.height), (20.0, 13.0));
+        assert_eq!(
+            layout.items,
+            vec![bounds(0.0, 0.0, 10.0, 5.0), bounds(0.0, 5.0, 20.0, 8.0)]
+        );
+    }
+
+    #[test]
+    fn grows_by_factor() {
+        let layout = row(Some(100.0)).layout(&[
+            FlexItem {
+                grow: 1.0,
+                ..FlexItem::new(10.0, 0.0)
+            },
+            FlexItem {
+                grow: 3.0,
+                ..FlexItem::new(10.0, 0.0)
+            },
+        ]);
+        assert_eq!(layout.width, 100.0);
+        assert_eq!(
+            layout.items,
+            vec![bounds(0.0, 0.0, 30.0, 0.0), bounds(30.0, 0.0, 70.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn gives_what_a_capped_item_cannot_take_to_the_rest() {
+        let layout = row(Some(100.0)).layout(&[
+            FlexItem {
+                grow: 1.0,
+                width_limit: SizeLimit {
+                    max: Some(20.0),
+                    ..Default::default()
+                },
+                ..FlexItem::new(10.0, 0.0)
+            },
+            FlexItem {
+                grow: 1.0,
+                ..FlexItem::new(10.0, 0.0)
+            },
+        ]);
+        assert_eq!(
+            layout.items,
+            vec![bounds(0.0, 0.0, 20.0, 0.0), bounds(20.0, 0.0, 80.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn shrinks_by_factor_and_size() {
+        let layout = row(Some(50.0)).layout(&[
+            FlexItem::new(20.0, 0.0),
+            FlexItem::new(40.0, 0.0),
+            FlexItem {
+                shrink: 0.0,
+                ..FlexItem::new(20.0, 0.0)
+            },
+        ]);
+        // 30 too wide, taken from the first two by 20:40.
+        assert_eq!(
+            layout.items,
+            vec![
+                bounds(0.0, 0.0, 10.0, 0.0),
+                bounds(10.0, 0.0, 20.0, 0.0),
+                bounds(30.0, 0.0, 20.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn shrinks_no_further_than_the_least() {
+        let layout = row(Some(50.0)).layout(&[
+            FlexItem {
+                width_limit: SizeLimit {
+                    min: Some(30.0),
+                    ..Default::default()
+                },
+                ..FlexItem::new(40.0, 0.0)
+            },
+            FlexItem::new(40.0, 0.0),
+        ]);
+        assert_eq!(
+            layout.items,
+            vec![bounds(0.0, 0.0, 30.0, 0.0), bounds(30.0, 0.0, 20.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn keeps_fixed_sizes() {
+        let layout = row(Some(100.0)).layout(&[
+            FlexItem {
+                grow: 1.0,
+                width_limit: SizeLimit {
+                    fixed: Some(25.0),
+                    ..Default::default()
+                },
+                height_limit: SizeLimit {
+                    fixed: Some(12.0),
+                    ..Default::default()
+                },
+                ..FlexItem::new(10.0, 4.0)
+            },
+            FlexItem {
+                grow: 1.0,
+                ..FlexItem::new(10.0, 4.0)
+            },
+        ]);
+        assert_eq!(layout.height, 12.0);
+        assert_eq!(
+            layout.items,
+            vec![bounds(0.0, 0.0, 25.0, 12.0), bounds(25.0, 0.0, 75.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn justifies_the_room_left() {
+        let items = [FlexItem::new(10.0, 0.0), FlexItem::new(10.0, 0.0)];
+        let starts = |justify| {
+            Flex {
+                justify,
+                ..row(Some(110.0))
+            }
+            .layout(&items)
+            .items
+            .iter()
+            .map(|bounds| bounds.x)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(starts(Justify::Start), vec![0.0, 10.0]);
+        assert_eq!(starts(Justify::End), vec![90.0, 100.0]);
+        assert_eq!(starts(Justify::Center), vec![45.0, 55.0]);
+        assert_eq!(starts(Justify::SpaceBetween), vec![0.0, 100.0]);
+        assert_eq!(starts(Justify::SpaceAround), vec![22.5, 77.5]);
+        assert_eq!(starts(Justify::SpaceEvenly), vec![30.0, 70.0]);
+    }
+
+    #[test]
+    fn aligns_across_the_line() {
+        let flex = Flex {
+            align: Align::Center,
+            ..row(None)
+        };
+        let layout = flex.layout(&[
+            FlexItem::new(10.0, 20.0),
+            FlexItem::new(10.0, 10.0),
+            FlexItem {
+                align: Some(Align::End),
+                ..FlexItem::new(10.0, 4.0)
+            },
+            FlexItem {
+                align: Some(Align::Stretch),
+                height_limit: SizeLimit {
+                    max: Some(15.0),
+                    ..Default::default()
+                },
+                ..FlexItem::new(10.0, 4.0)
+            },
+        ]);
+        assert_eq!(
+            layout.items,
+            vec![
+                bounds(0.0, 0.0, 10.0, 20.0),
+                bounds(10.0, 5.0, 10.0, 10.0),
+                bounds(20.0, 16.0, 10.0, 4.0),
+                bounds(30.0, 0.0, 10.0, 15.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn stretches_to_a_fixed_cross_size() {
+        let flex = Flex {
+            align: Align::Stretch,
+            height: SizeLimit {
+                fixed: Some(30.0),
+                ..Default::default()
+            },
+            ..row(None)
+        };
+        let layout = flex.layout(&[FlexItem::new(10.0, 4.0)]);
+        assert_eq!(layout.height, 30.0);
+        assert_eq!(layout.items, vec![bounds(0.0, 0.0, 10.0, 30.0)]);
+    }
+
+    #[test]
+    fn wraps_into_lines() {
+        let flex = Flex {
+            wrap: true,
+            gap: 2.0,
+            width: SizeLimit {
+                max: Some(50.0),
+                ..Default::default()
+            },
+            ..Flex::new(Direction::Row)
+        };
+        let layout = flex.layout(&[
+            FlexItem::new(20.0, 10.0),
+            FlexItem::new(20.0, 6.0),
+            FlexItem::new(20.0, 8.0),
+        ]);
+        assert_eq!((layout.width, layout.height), (42.0, 20.0));
+        assert_eq!(
+            layout.items,
+            vec![
+                bounds(0.0, 0.0, 20.0, 10.0),
+                bounds(22.0, 0.0, 20.0, 6.0),
+                bounds(0.0, 12.0, 20.0, 8.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn lays_out_again_in_the_size_given() {
+        let items = [
+            FlexItem {
+                grow: 1.0,
+                ..FlexItem::new(10.0, 4.0)
+            },
+            FlexItem::new(10.0, 4.0),
+        ];
+        let inner = row(None);
+        let natural = inner.layout(&items);
+        assert_eq!(natural.width, 20.0);
+
+        let outer = row(Some(50.0)).layout(&[FlexItem {
+            grow: 1.0,
+            ..FlexItem::new(natural.width, natural.height)
+        }]);
+        let given = Flex {
+            width: inner.width.given(outer.items[0].width),
+            ..inner
+        }
+        .layout(&items);
+        assert_eq!(given.width, 50.0);
+        assert_eq!(
+            given.items,
+            vec![bounds(0.0, 0.0, 40.0, 4.0), bounds(40.0, 0.0, 10.0, 4.0)]
+        );
+
+        let capped = SizeLimit {
+            max: Some(30.0),
+            ..Default::default()
+        };
+        assert_eq!(capped.given(50.0).fixed, Some(30.0));
+        let fixed = SizeLimit {
+            fixed: Some(25.0),
+            ..Default::default()
+        };
+        assert_eq!(fixed.given(50.0).fixed, Some(25.0));
+    }
+
+    #[test]
+    fn follows_margins_positions_and_overlaps() {
+        let layout = row(None).layout(&[
+            FlexItem {
+                margin: 5.0,
+                ..FlexItem::new(10.0, 0.0)
+            },
+            FlexItem {
+                overlap: true,
+                ..FlexItem::new(20.0, 0.0)
+            },
+            FlexItem {
+                position: Some(40.0),
+                margin: 2.0,
+                ..FlexItem::new(10.0, 0.0)
+            },
+        ]);
+        assert_eq!(layout.width, 52.0);
+        assert_eq!(
+            layout.items,
+            vec![
+                bounds(5.0, 0.0, 10.0, 0.0),
+                bounds(5.0, 0.0, 20.0, 0.0),
+                bounds(42.0, 0.0, 10.0, 0.0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,582 @@
+//! The measure and arrange pass of `<grid>` and `<stack>`: cells come in with the size they
+//! were drawn at, and go out with where they are put in the tracks, without drawing anything.
+
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{Align, Bounds, Length, LengthParseError, SizeLimit};
+
+/// Size of a column or a row, as written in `columns` and `rows`.
+pub enum TrackSize {
+    /// As large as the largest cell in it.
+    Auto,
+    Length(Length),
+    /// A share of the room left, like `1fr`.
+    Fraction(f64),
+}
+
+/// Sizes of the tracks separated by spaces, like `auto 1fr 40px`, or a count of `auto` tracks.
+pub struct TrackList(pub Vec<TrackSize>);
+
+#[derive(Debug, Error)]
+pub enum TrackListParseError {
+    #[error("{0}")]
+    Length(#[from] LengthParseError),
+    #[error("Cannot parse {src} as a share of the room left")]
+    Fraction { src: String },
+}
+
+impl FromStr for TrackList {
+    type Err = TrackListParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(count) = s.trim().parse::<usize>() {
+            return Ok(TrackList((0..count).map(|_| TrackSize::Auto).collect()));
+        }
+        s.split_whitespace()
+            .map(|track| {
+                if track == "auto" {
+                    Ok(TrackSize::Auto)
+                } else if let Some(fraction) = track.strip_suffix("fr") {
+                    fraction.parse().map(TrackSize::Fraction).map_err(|_| {
+                        TrackListParseError::Fraction {
+                            src: track.to_string(),
+                        }
+                    })
+                } else {
+                    Ok(TrackSize::Length(Length::from_str(track)?))
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(TrackList)
+    }
+}
+
+impl fmt::Display for TrackList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tracks = self
+            .0
+            .iter()
+            .map(|track| match track {
+                TrackSize::Auto => "auto".to_string(),
+                TrackSize::Length(length) => length.to_string(),
+                TrackSize::Fraction(fraction) => format!("{}fr", fraction),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", tracks.join(" "))
+    }
+}
+
+impl Serialize for TrackList {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for TrackList {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        TrackList::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl TrackList {
+    pub fn translate_to_px(&self, viewbox_width: f64, viewbox_height: f64) -> Vec<Track> {
+        self.0
+            .iter()
+            .map(|track| match track {
+                TrackSize::Auto => Track::Auto,
+                TrackSize::Length(length) => {
+                    Track::Fixed(length.translate_to_px(viewbox_width, viewbox_height))
+                }
+                TrackSize::Fraction(fraction) => Track::Fraction(*fraction),
+            })
+            .collect()
+    }
+}
+
+/// A column or a row, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Track {
+    Auto,
+    Fixed(f64),
+    Fraction(f64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridCell {
+    /// Size the cell is drawn at.
+    pub width: f64,
+    pub height: f64,
+    /// The first column and row of the cell from 0, or the next free ones.
+    pub column: Option<usize>,
+    pub row: Option<usize>,
+    pub column_span: usize,
+    pub row_span: usize,
+    /// Override the `justify` and `align` of the grid.
+    pub justify: Option<Align>,
+    pub align: Option<Align>,
+}
+
+impl GridCell {
+    pub fn new(width: f64, height: f64) -> Self {
+        GridCell {
+            width,
+            height,
+            column: None,
+            row: None,
+            column_span: 1,
+            row_span: 1,
+            justify: None,
+            align: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridTracks {
+    /// Rows and columns more than these are added as `auto`.
+    pub columns: Vec<Track>,
+    pub rows: Vec<Track>,
+    pub column_gap: f64,
+    pub row_gap: f64,
+    /// Where cells are put in their area from left to right, and from top to bottom.
+    pub justify: Align,
+    pub align: Align,
+    /// Size of the grid inside, without its padding and border.
+    pub width: SizeLimit,
+    pub height: SizeLimit,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridLayout {
+    pub width: f64,
+    pub height: f64,
+    /// Bounds of each cell, in the order they came in.
+    pub cells: Vec<Bounds>,
+}
+
+impl GridTracks {
+    pub fn new(columns: Vec<Track>, rows: Vec<Track>) -> Self {
+        GridTracks {
+            columns,
+            rows,
+            column_gap: 0.0,
+            row_gap: 0.0,
+            justify: Align::default(),
+            align: Align::default(),
+            width: SizeLimit::default(),
+            height: SizeLimit::default(),
+        }
+    }
+
+    pub fn layout(&self, cells: &[GridCell]) -> GridLayout {
+        let areas = self.place(cells);
+
+        let column_count = areas
+            .iter()
+            .map(|&(column, _, column_span, _)| column + column_span)
+            .fold(self.columns.len(), usize::max);
+        let row_count = areas
+            .iter()
+            .map(|&(_, row, _, row_span)| row + row_span)
+            .fold(self.rows.len(), usize::max);
+
+        let columns = size_tracks(
+            &with_implicit(&self.columns, column_count),
+            &areas
+                .iter()
+                .zip(cells)
+                .map(|(&(column, _, span, _), cell)| (column, span, cell.width))
+                .collect::<Vec<_>>(),
+            self.column_gap,
+            self.width.room(),
+        );
+        let rows = size_tracks(
+            &with_implicit(&self.rows, row_count),
+            &areas
+                .iter()
+                .zip(cells)
+                .map(|(&(_, row, _, span), cell)| (row, span, cell.height))
+                .collect::<Vec<_>>(),
+            self.row_gap,
+            self.height.room(),
+        );
+
+        let column_starts = starts(&columns, self.column_gap);
+        let row_starts = starts(&rows, self.row_gap);
+        let span = |sizes: &[f64], gap: f64, start: usize, span: usize| {
+            sizes[start..start + span].iter().sum::<f64>() + gap * (span - 1) as f64
+        };
+
+        let cells = areas
+            .iter()
+            .zip(cells)
+            .map(|(&(column, row, column_span, row_span), cell)| {
+                let area_width = span(&columns, self.column_gap, column, column_span);
+                let area_height = span(&rows, self.row_gap, row, row_span);
+                let justify = cell.justify.unwrap_or(self.justify);
+                let align = cell.align.unwrap_or(self.align);
+                // Cells larger than their area are cut to it.
+                let width = if justify == Align::Stretch {
+                    area_width
+                } else {
+                    cell.width.min(area_width)
+                };
+                let height = if align == Align::Stretch {
+                    area_height
+                } else {
+                    cell.height.min(area_height)
+                };
+                Bounds {
+                    x: column_starts[column] + justify.offset(area_width - width),
+                    y: row_starts[row] + align.offset(area_height - height),
+                    width,
+                    height,
+                }
+            })
+            .collect();
+
+        GridLayout {
+            width: self.width.clamp(total(&columns, self.column_gap)),
+            height: self.height.clamp(total(&rows, self.row_gap)),
+            cells,
+        }
+    }
+
+    /// The column, row, column span and row span of each cell, putting the ones without a
+    /// column or a row in the next free area, from left to right and then from top to bottom.
+    fn place(&self, cells: &[GridCell]) -> Vec<(usize, usize, usize, usize)> {
+        let flow = self.columns.len().max(1);
+        let mut taken = HashSet::new();
+        let free = |taken: &HashSet<(usize, usize)>, column, row, column_span, row_span| {
+            (column..column + column_span)
+                .all(|column| (row..row + row_span).all(|row| !taken.contains(&(column, row))))
+        };
+
+        let mut cursor = (0, 0);
+        cells
+            .iter()
+            .map(|cell| {
+                let row_span = cell.row_span.max(1);
+                let (column, row, column_span) = match (cell.column, cell.row) {
+                    (Some(column), Some(row)) => (column, row, cell.column_span.max(1)),
+                    (Some(column), None) => {
+                        let span = cell.column_span.max(1);
+                        let row = (0..)
+                            .find(|&row| free(&taken, column, row, span, row_span))
+                            .unwrap_or_default();
+                        (column, row, span)
+                    }
+                    (None, Some(row)) => {
+                        let span = cell.column_span.max(1);
+                        let column = (0..)
+                            .find(|&column| free(&taken, column, row, span, row_span))
+                            .unwrap_or_default();
+                        (column, row, span)
+                    }
+                    (None, None) => {
+                        let span = cell.column_span.clamp(1, flow);
+                        let (mut column, mut row) = cursor;
+                        loop {
+                            if column + span > flow {
+                                column = 0;
+                                row += 1;
+                            } else if free(&taken, column, row, span, row_span) {
+                                break;
+                            } else {
+                                column += 1;
+                            }
+                        }
+                        cursor = (column + span, row);
+                        (column, row, span)
+                    }
+                };
+
+                for column in column..column + column_span {
+                    for row in row..row + row_span {
+                        taken.insert((column, row));
+                    }
+                }
+                (column, row, column_span, row_span)
+            })
+            .collect()
+    }
+}
+
+fn with_implicit(tracks: &[Track], count: usize) -> Vec<Track> {
+    let mut tracks = tracks.to_vec();
+    tracks.resize(count, Track::Auto);
+    tracks
+}
+
+fn total(sizes: &[f64], gap: f64) -> f64 {
+    sizes.iter().sum::<f64>() + gap * sizes.len().saturating_sub(1) as f64
+}
+
+fn starts(sizes: &[f64], gap: f64) -> Vec<f64> {
+    sizes
+        .iter()
+        .scan(0.0, |start, size| {
+            let current = *start;
+            *start += size + gap;
+            Some(current)
+        })
+        .collect()
+}
+
+/// Sizes of `tracks` holding `items` given as their first track, span and size.
+///
+/// `auto` tracks fit their items, spread evenly for items spanning more than one. Fractions share
+/// what `room` is left, and are at least as large as their items need, in proportion to each other.
+fn size_tracks(
+    tracks: &[Track],
+    items: &[(usize, usize, f64)],
+    gap: f64,
+    room: Option<f64>,
+) -> Vec<f64> {
+    let mut sizes = tracks
+        .iter()
+        .map(|track| match track {
+            Track::Fixed(size) => *size,
+            Track::Auto | Track::Fraction(_) => 0.0,
+        })
+        .collect::<Vec<_>>();
+    let growable = |index: usize| !matches!(tracks[index], Track::Fixed(_));
+
+    for &(start, _, size) in items.iter().filter(|(_, span, _)| *span == 1) {
+        if growable(start) {
+            sizes[start] = sizes[start].max(size);
+        }
+    }
+
+    let mut spanning = items
+        .iter()
+        .filter(|(_, span, _)| *span > 1)
+        .collect::<Vec<_>>();
+    spanning.sort_by_key(|(_, span, _)| *span);
+    for &&(start, span, size) in &spanning {
+        let range = start..start + span;
+        let needed = size - sizes[range.clone()].iter().sum::<f64>() - gap * (span - 1) as f64;
+        let auto = range
+            .clone()
+            .filter(|&index| tracks[index] == Track::Auto)
+            .collect::<Vec<_>>();
+        let targets = if auto.is_empty() {
+            range.filter(|&index| growable(index)).collect()
+        } else {
+            auto
+        };
+        if needed > 0.0 && !targets.is_empty() {
+            let share = needed / targets.len() as f64;
+            for index in targets {
+                sizes[index] += share;
+            }
+        }
+    }
+
+    let fractions = tracks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, track)| match track {
+            Track::Fraction(fraction) if *fraction > 0.0 => Some((index, *fraction)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let total = fractions.iter().map(|(_, fraction)| fraction).sum::<f64>();
+    if total > 0.0 {
+        let mut unit = fractions
+            .iter()
+            .map(|&(index, fraction)| sizes[index] / fraction)
+            .fold(0.0, f64::max);
+        if let Some(room) = room {
+            let others = (0..sizes.len())
+                .filter(|index| {
+                    fractions
+                        .iter()
+                        .all(|(fraction_index, _)| fraction_index != index)
+                })
+                .map(|index| sizes[index])
+                .sum::<f64>();
+            let gaps = gap * sizes.len().saturating_sub(1) as f64;
+            unit = unit.max((room - others - gaps) / total);
+        }
+        for (index, fraction) in fractions {
+            sizes[index] = unit * fraction;
+        }
+    }
+
+    sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(x: f64, y: f64, width: f64, height: f64) -> Bounds {
+        Bounds {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn flows_cells_from_left_to_right() {
+        let grid = GridTracks {
+            column_gap: 2.0,
+            row_gap: 4.0,
+            ..GridTracks::new(vec![Track::Auto; 2], Vec::new())
+        };
+        let layout = grid.layout(&[
+            GridCell::new(10.0, 5.0),
+            GridCell::new(20.0, 6.0),
+            GridCell::new(15.0, 8.0),
+            GridCell::new(5.0, 2.0),
+        ]);
+        assert_eq!((layout.width, layout.height), (37.0, 18.0));
+        assert_eq!(
+            layout.cells,
+            vec![
+                bounds(0.0, 0.0, 10.0, 5.0),
+                bounds(17.0, 0.0, 20.0, 6.0),
+                bounds(0.0, 10.0, 15.0, 8.0),
+                bounds(17.0, 10.0, 5.0, 2.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_and_skips_taken_cells() {
+        let grid = GridTracks::new(vec![Track::Fixed(10.0); 3], vec![Track::Fixed(10.0); 2]);
+        let layout = grid.layout(&[
+            GridCell {
+                column: Some(2),
+                row: Some(0),
+                row_span: 2,
+                ..GridCell::new(5.0, 5.0)
+            },
+            GridCell {
+                column_span: 2,
+                align: Some(Align::Stretch),
+                justify: Some(Align::Stretch),
+                ..GridCell::new(5.0, 5.0)
+            },
+            GridCell::new(5.0, 5.0),
+        ]);
+        // The last cell finds the third column taken, and goes on to the next row.
+        assert_eq!(
+            layout.cells,
+            vec![
+                bounds(20.0, 0.0, 5.0, 5.0),
+                bounds(0.0, 0.0, 20.0, 10.0),
+                bounds(0.0, 10.0, 5.0, 5.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn adds_rows_as_needed() {
+        let grid = GridTracks::new(vec![Track::Auto], vec![Track::Fixed(10.0)]);
+        let layout = grid.layout(&[GridCell::new(5.0, 5.0), GridCell::new(5.0, 7.0)]);
+        assert_eq!((layout.width, layout.height), (5.0, 17.0));
+        assert_eq!(layout.cells[1], bounds(0.0, 10.0, 5.0, 7.0));
+    }
+
+    #[test]
+    fn widens_auto_tracks_for_spanning_cells() {
+        let grid = GridTracks::new(vec![Track::Auto, Track::Auto], Vec::new());
+        let layout = grid.layout(&[
+            GridCell::new(10.0, 5.0),
+            GridCell::new(10.0, 5.0),
+            GridCell {
+                column_span: 2,
+                ..GridCell::new(30.0, 5.0)
+            },
+        ]);
+        assert_eq!(layout.width, 30.0);
+        assert_eq!(layout.cells[1], bounds(15.0, 0.0, 10.0, 5.0));
+    }
+
+    #[test]
+    fn shares_the_room_left_by_fraction() {
+        let grid = GridTracks {
+            width: SizeLimit {
+                fixed: Some(100.0),
+                ..Default::default()
+            },
+            ..GridTracks::new(
+                vec![
+                    Track::Fixed(40.0),
+                    Track::Fraction(1.0),
+                    Track::Fraction(2.0),
+                ],
+                Vec::new(),
+            )
+        };
+        let layout = grid.layout(&[
+            GridCell::new(5.0, 5.0),
+            GridCell::new(5.0, 5.0),
+            GridCell::new(5.0, 5.0),
+        ]);
+        assert_eq!(layout.width, 100.0);
+        assert_eq!(
+            layout.cells.iter().map(|cell| cell.x).collect::<Vec<_>>(),
+            vec![0.0, 40.0, 60.0]
+        );
+    }
+
+    #[test]
+    fn evens_fractions_out_without_room() {
+        let grid = GridTracks::new(vec![Track::Fraction(1.0); 2], Vec::new());
+        let layout = grid.layout(&[GridCell::new(10.0, 5.0), GridCell::new(30.0, 5.0)]);
+        assert_eq!(layout.width, 60.0);
+        assert_eq!(layout.cells[1].x, 30.0);
+    }
+
+    #[test]
+    fn aligns_cells_in_their_area() {
+        let grid = GridTracks {
+            justify: Align::Center,
+            align: Align::End,
+            ..GridTracks::new(vec![Track::Fixed(20.0)], vec![Track::Fixed(20.0)])
+        };
+        let layout = grid.layout(&[
+            GridCell::new(10.0, 10.0),
+            GridCell {
+                column: Some(0),
+                row: Some(0),
+                justify: Some(Align::Start),
+                ..GridCell::new(30.0, 4.0)
+            },
+        ]);
+        assert_eq!(
+            layout.cells,
+            vec![bounds(5.0, 10.0, 10.0, 10.0), bounds(0.0, 16.0, 20.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn parses_track_lists() {
+        let tracks = "auto 1fr 40px".parse::<TrackList>().unwrap();
+        assert_eq!(
+            tracks.translate_to_px(100.0, 100.0),
+            vec![Track::Auto, Track::Fraction(1.0), Track::Fixed(40.0)]
+        );
+        let tracks = "2".parse::<TrackList>().unwrap();
+        assert_eq!(
+            tracks.translate_to_px(100.0, 100.0),
+            vec![Track::Auto, Track::Auto]
+        );
+        assert!("1xr".parse::<TrackList>().is_err());
+    }
+}
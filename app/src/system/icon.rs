@@ -0,0 +1,72 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use thiserror::Error;
+
+static ICON_SETS: Lazy<RwLock<HashMap<String, IconSet>>> = Lazy::new(|| {
+    let mut sets = HashMap::new();
+    if let Ok(mut nerd) = IconSet::parse(include_bytes!("../../assets/icons/nerd.json")) {
+        nerd.font_family = Some("Symbols Nerd Font".to_string());
+        sets.insert("nerd".to_string(), nerd);
+    }
+    RwLock::new(sets)
+});
+
+#[derive(Error, Debug)]
+pub enum IconSetParseError {
+    #[error("Failed to deserialize the glyph map: {0}")]
+    JsonDeserialize(#[from] serde_json::Error),
+    #[error("Failed to parse the code point {1} of {0}")]
+    CodePoint(String, String),
+}
+
+/// Glyph names of an icon font, like the `glyphnames.json` of Nerd Fonts.
+#[derive(Clone, Default)]
+pub struct IconSet {
+    pub font_family: Option<String>,
+    glyphs: HashMap<String, char>,
+}
+
+impl IconSet {
+    /// Reads `{ "<name>": { "code": "<hex>" }, ... }`, skipping entries without a code.
+    pub fn parse(data: &[u8]) -> Result<IconSet, IconSetParseError> {
+        let entries: HashMap<String, Value> = serde_json::from_slice(data)?;
+
+        let mut glyphs = HashMap::with_capacity(entries.len());
+        for (name, entry) in entries {
+            let code = match entry.get("code").and_then(Value::as_str) {
+                Some(code) => code,
+                None => continue,
+            };
+            let glyph = u32::from_str_radix(code, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| IconSetParseError::CodePoint(name.clone(), code.to_string()))?;
+            glyphs.insert(name, glyph);
+        }
+
+        Ok(IconSet {
+            font_family: None,
+            glyphs,
+        })
+    }
+
+    /// Adds the glyphs to the set named `name`, replacing the font family if given.
+    pub fn install(self, name: &str) {
+        if let Ok(mut sets) = ICON_SETS.write() {
+            let set = sets.entry(name.to_string()).or_default();
+            set.glyphs.extend(self.glyphs);
+            if self.font_family.is_some() {
+                set.font_family = self.font_family;
+            }
+        }
+    }
+
+    /// The glyph named `name` in the set `set`, and the font family of the set.
+    pub fn resolve(set: &str, name: &str) -> Option<(char, Option<String>)> {
+        let sets = ICON_SETS.read().ok()?;
+        let set = sets.get(set)?;
+        Some((*set.glyphs.get(name)?, set.font_family.clone()))
+    }
+}
@@ -0,0 +1,85 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{Length, LengthParseError};
+
+/// Lengths for each side, written as one to four lengths like `padding` in CSS:
+/// all sides, vertical and horizontal, top, horizontal and bottom, or top, right, bottom and left.
+pub struct Insets {
+    pub top: Length,
+    pub right: Length,
+    pub bottom: Length,
+    pub left: Length,
+}
+
+#[derive(Debug, Error)]
+pub enum InsetsParseError {
+    #[error("{0}")]
+    Length(#[from] LengthParseError),
+    #[error("{src} should have one to four lengths")]
+    Count { src: String },
+}
+
+impl FromStr for Insets {
+    type Err = InsetsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lengths = s
+            .split_whitespace()
+            .map(Length::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (top, right, bottom, left) = match &lengths[..] {
+            [all] => (all, all, all, all),
+            [vertical, horizontal] => (vertical, horizontal, vertical, horizontal),
+            [top, horizontal, bottom] => (top, horizontal, bottom, horizontal),
+            [top, right, bottom, left] => (top, right, bottom, left),
+            _ => return Err(InsetsParseError::Count { src: s.to_string() }),
+        };
+        Ok(Insets {
+            top: top.clone(),
+            right: right.clone(),
+            bottom: bottom.clone(),
+            left: left.clone(),
+        })
+    }
+}
+
+impl fmt::Display for Insets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.top, self.right, self.bottom, self.left
+        )
+    }
+}
+
+impl Serialize for Insets {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Insets {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Insets::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Insets {
+    /// The top, right, bottom and left side in pixels.
+    pub fn translate_to_px(&self, viewbox_width: f64, viewbox_height: f64) -> [f64; 4] {
+        [&self.top, &self.right, &self.bottom, &self.left]
+            .map(|length| length.translate_to_px(viewbox_width, viewbox_height))
+    }
+}
@@ -3,6 +3,7 @@ use std::{fmt, str::FromStr};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+#[derive(Clone)]
 pub enum Length {
     Pixel(i64),
     ViewboxHeight(f64),
@@ -1,11 +1,27 @@
 pub use alignment::*;
 pub use color::*;
+pub use color_scale::*;
+pub use flex::*;
+pub use grid::*;
+pub use icon::*;
+pub use insets::*;
 pub use length::*;
+pub use paint::*;
 pub use position::*;
 pub use rect::*;
+pub use text_layout::*;
+pub use theme::*;
 
 mod alignment;
 mod color;
+mod color_scale;
+mod flex;
+mod grid;
+mod icon;
+mod insets;
 mod length;
+mod paint;
 mod position;
 mod rect;
+mod text_layout;
+mod theme;
@@ -0,0 +1,403 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use declarrred::rt::Data;
+use serde::Deserialize;
+use thiserror::Error;
+use usvg::{
+    BaseGradient, LinearGradient, Node, NodeKind, Opacity, PositiveNumber, RadialGradient,
+    SpreadMethod, Stop, StopOffset, Transform, Units,
+};
+
+use super::Color;
+
+/// A color, or a gradient over the bounding box of what it paints.
+pub enum Paint {
+    Color(Color),
+    /// `linear-gradient([<angle>deg | to <side>,] <stop>, ...)`, where 0deg points to the top.
+    LinearGradient {
+        angle: f64,
+        stops: Vec<GradientStop>,
+    },
+    /// `radial-gradient(<stop>, ...)`, from the center to the farthest side.
+    RadialGradient {
+        stops: Vec<GradientStop>,
+    },
+}
+
+/// `<color> [<offset>%]`
+pub struct GradientStop {
+    pub color: Color,
+    pub offset: Option<f64>,
+}
+
+#[derive(Error, Debug)]
+pub enum PaintParseError {
+    #[error("Failed to parse color {0}")]
+    Color(String),
+    #[error("Failed to parse gradient direction {0}")]
+    Direction(String),
+    #[error("Failed to parse gradient stop offset {0}")]
+    Offset(String),
+    #[error("A gradient needs at least two stops")]
+    TooFewStops,
+}
+
+impl FromStr for Paint {
+    type Err = PaintParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(arguments) = function_arguments(s, "linear-gradient") {
+            let (angle, stops) = match arguments.split_first() {
+                Some((first, rest)) if first.ends_with("deg") || first.starts_with("to ") => {
+                    (parse_direction(first)?, rest)
+                }
+                _ => (180.0, &arguments[..]),
+            };
+            Ok(Paint::LinearGradient {
+                angle,
+                stops: parse_stops(stops)?,
+            })
+        } else if let Some(arguments) = function_arguments(s, "radial-gradient") {
+            Ok(Paint::RadialGradient {
+                stops: parse_stops(&arguments)?,
+            })
+        } else {
+            Ok(Paint::Color(parse_color(s)?))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Paint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Paint::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Paint {
+    /// Converts into what usvg paints with.
+    ///
+    /// Gradients are appended to `parent`, to be moved into the defs before rendering.
+    /// Color scales are settled with `variables`.
+    pub fn to_usvg_paint(
+        &self,
+        parent: &mut Node,
+        variables: &HashMap<String, Data>,
+    ) -> (usvg::Paint, Opacity) {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let (kind, stops) = match self {
+            Paint::Color(color) => {
+                let color = color.at(variables);
+                return (
+                    usvg::Paint::Color(color.to_usvg_color()),
+                    color.to_usvg_opacity(),
+                );
+            }
+            Paint::LinearGradient { stops, .. } => ("linear", stops),
+            Paint::RadialGradient { stops } => ("radial", stops),
+        };
+        let id = format!(
+            "measurrred-{}-gradient-{}",
+            kind,
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let base = BaseGradient {
+            units: Units::ObjectBoundingBox,
+            transform: Transform::default(),
+            spread_method: SpreadMethod::Pad,
+            stops: resolve_offsets(stops)
+                .into_iter()
+                .map(|(offset, color)| {
+                    let color = color.at(variables);
+                    Stop {
+                        offset: StopOffset::new(offset),
+                        color: color.to_usvg_color(),
+                        opacity: color.to_usvg_opacity(),
+                    }
+                })
+                .collect(),
+        };
+
+        let gradient = match self {
+            Paint::LinearGradient { angle, .. } => {
+                let (dx, dy) = (angle.to_radians().sin(), -angle.to_radians().cos());
+                NodeKind::LinearGradient(LinearGradient {
+                    id: id.clone(),
+                    x1: 0.5 - dx / 2.0,
+                    y1: 0.5 - dy / 2.0,
+                    x2: 0.5 + dx / 2.0,
+                    y2: 0.5 + dy / 2.0,
+                    base,
+                })
+            }
+            _ => NodeKind::RadialGradient(RadialGradient {
+                id: id.clone(),
+                cx: 0.5,
+                cy: 0.5,
+                r: PositiveNumber::new(0.5),
+                fx: 0.5,
+                fy: 0.5,
+                base,
+            }),
+        };
+        parent.append(Node::new(gradient));
+
+        (usvg::Paint::Link(id), Opacity::default())
+    }
+}
+
+/// Moves the gradients of [`Paint::to_usvg_paint`] within `root` into the defs of `tree`, along
+/// with the patterns, clip paths, masks and filters plugins draw with.
+pub fn hoist_paint_servers(root: &Node, tree: &mut usvg::Tree) {
+    let servers: Vec<_> = root
+        .descendants()
+        .filter(|node| {
+            matches!(
+                *node.borrow(),
+                NodeKind::LinearGradient(_)
+                    | NodeKind::RadialGradient(_)
+                    | NodeKind::Pattern(_)
+                    | NodeKind::ClipPath(_)
+                    | NodeKind::Mask(_)
+                    | NodeKind::Filter(_)
+            )
+        })
+        .collect();
+    for mut server in servers {
+        server.detach();
+        tree.defs().append(server);
+    }
+}
+
+/// Stops with missing offsets are spread evenly between their neighbours, like in CSS.
+fn resolve_offsets(stops: &[GradientStop]) -> Vec<(f64, &Color)> {
+    let last = stops.len().saturating_sub(1);
+    let mut offsets: Vec<_> = stops
+        .iter()
+        .enumerate()
+        .map(|(i, stop)| match (stop.offset, i) {
+            (Some(offset), _) => Some(offset),
+            (None, 0) => Some(0.0),
+            (None, i) if i == last => Some(1.0),
+            _ => None,
+        })
+        .collect();
+
+    let mut i = 0;
+    while i < offsets.len() {
+        if offsets[i].is_some() {
+            i += 1;
+            continue;
+        }
+        let begin = i - 1;
+        let end = (i..offsets.len())
+            .find(|&j| offsets[j].is_some())
+            .unwrap_or(last);
+        let (from, to) = (offsets[begin].unwrap_or(0.0), offsets[end].unwrap_or(1.0));
+        for (j, offset) in offsets[i..end].iter_mut().enumerate() {
+            let step = (i + j - begin) as f64 / (end - begin) as f64;
+            *offset = Some(from + (to - from) * step);
+        }
+        i = end;
+    }
+
+    offsets
+        .into_iter()
+        .zip(stops)
+        .map(|(offset, stop)| (offset.unwrap_or(0.0), &stop.color))
+        .collect()
+}
+
+/// Splits `name(a, b(c, d), e)` into `["a", "b(c, d)", "e"]`.
+pub(super) fn function_arguments(s: &str, name: &str) -> Option<Vec<String>> {
+    let inner = s.strip_prefix(name)?.trim_start();
+    let inner = inner.strip_prefix('(')?.strip_suffix(')')?;
+
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in inner.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                arguments.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    arguments.push(current.trim().to_string());
+
+    Some(arguments)
+}
+
+fn parse_direction(s: &str) -> Result<f64, PaintParseError> {
+    if let Some(degrees) = s.strip_suffix("deg") {
+        return degrees
+            .trim()
+            .parse()
+            .map_err(|_| PaintParseError::Direction(s.to_string()));
+    }
+
+    let (mut dx, mut dy) = (0.0, 0.0);
+    for side in s.trim_start_matches("to ").split_whitespace() {
+        match side {
+            "top" => dy -= 1.0,
+            "bottom" => dy += 1.0,
+            "left" => dx -= 1.0,
+            "right" => dx += 1.0,
+            _ => return Err(PaintParseError::Direction(s.to_string())),
+        }
+    }
+    if dx == 0.0 && dy == 0.0 {
+        return Err(PaintParseError::Direction(s.to_string()));
+    }
+
+    Ok(f64::atan2(dx, -dy).to_degrees())
+}
+
+fn parse_stops(arguments: &[String]) -> Result<Vec<GradientStop>, PaintParseError> {
+    if arguments.len() < 2 {
+        return Err(PaintParseError::TooFewStops);
+    }
+
+    arguments
+        .iter()
+        .map(|argument| match argument.rsplit_once(' ') {
+            Some((color, offset)) if offset.ends_with('%') => Ok(GradientStop {
+                color: parse_color(color)?,
+                offset: Some(
+                    offset
+                        .trim_end_matches('%')
+                        .parse::<f64>()
+                        .map_err(|_| PaintParseError::Offset(offset.to_string()))?
+                        / 100.0,
+                ),
+            }),
+            _ => Ok(GradientStop {
+                color: parse_color(argument)?,
+                offset: None,
+            }),
+        })
+        .collect()
+}
+
+fn parse_color(s: &str) -> Result<Color, PaintParseError> {
+    Color::from_str(s.trim()).map_err(|_| PaintParseError::Color(s.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(stops: &[&str]) -> Vec<f64> {
+        let stops = stops
+            .iter()
+            .map(|stop| stop.to_string())
+            .collect::<Vec<_>>();
+        resolve_offsets(&parse_stops(&stops).unwrap())
+            .into_iter()
+            .map(|(offset, _)| (offset * 1000.0).round() / 1000.0)
+            .collect()
+    }
+
+    #[test]
+    fn parses_colors_and_gradients() {
+        match Paint::from_str("#ff000080").unwrap() {
+            Paint::Color(color) => {
+                assert_eq!(color.to_hex(), "#ff0000");
+                assert_eq!(color.resolve().alpha, 0x80);
+            }
+            _ => panic!("Expected a color"),
+        }
+
+        match Paint::from_str("linear-gradient(to right, red, #0000ff 40%, lime)").unwrap() {
+            Paint::LinearGradient { angle, stops } => {
+                assert_eq!(angle, 90.0);
+                assert_eq!(
+                    stops.iter().map(|stop| stop.offset).collect::<Vec<_>>(),
+                    vec![None, Some(0.4), None]
+                );
+                assert_eq!(stops[1].color.to_hex(), "#0000ff");
+            }
+            _ => panic!("Expected a linear gradient"),
+        }
+
+        match Paint::from_str("linear-gradient(red, rgba(0, 0, 255, 0.5))").unwrap() {
+            Paint::LinearGradient { angle, stops } => {
+                assert_eq!(angle, 180.0);
+                assert_eq!(stops.len(), 2);
+            }
+            _ => panic!("Expected a linear gradient"),
+        }
+
+        assert!(matches!(
+            Paint::from_str("radial-gradient(white, transparent)").unwrap(),
+            Paint::RadialGradient { stops } if stops.len() == 2
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_paints() {
+        assert!(matches!(
+            Paint::from_str("linear-gradient(red)"),
+            Err(PaintParseError::TooFewStops)
+        ));
+        assert!(matches!(
+            Paint::from_str("linear-gradient(to middle, red, blue)"),
+            Err(PaintParseError::Direction(_))
+        ));
+        assert!(matches!(
+            Paint::from_str("linear-gradient(red, blue 4x%)"),
+            Err(PaintParseError::Offset(_))
+        ));
+        assert!(matches!(
+            Paint::from_str("radial-gradient(red, nocolor)"),
+            Err(PaintParseError::Color(_))
+        ));
+    }
+
+    #[test]
+    fn parses_directions() {
+        assert_eq!(parse_direction("to top").unwrap(), 0.0);
+        assert_eq!(parse_direction("to right").unwrap(), 90.0);
+        assert_eq!(parse_direction("to bottom").unwrap(), 180.0);
+        assert_eq!(parse_direction("to left").unwrap(), -90.0);
+        assert_eq!(parse_direction("to top right").unwrap(), 45.0);
+        assert_eq!(parse_direction("to bottom left").unwrap(), -135.0);
+        assert_eq!(parse_direction("30deg").unwrap(), 30.0);
+        assert!(parse_direction("to top bottom").is_err());
+        assert!(parse_direction("to").is_err());
+        assert!(parse_direction("halfdeg").is_err());
+    }
+
+    #[test]
+    fn spreads_stops_without_offsets() {
+        assert_eq!(offsets(&["red", "lime"]), vec![0.0, 1.0]);
+        assert_eq!(
+            offsets(&["red", "lime", "blue", "white"]),
+            vec![0.0, 0.333, 0.667, 1.0]
+        );
+        assert_eq!(
+            offsets(&["red 10%", "lime", "blue", "white 70%"]),
+            vec![0.1, 0.3, 0.5, 0.7]
+        );
+        assert_eq!(offsets(&["red", "lime 80%", "blue"]), vec![0.0, 0.8, 1.0]);
+        assert_eq!(
+            offsets(&["red", "lime", "blue 50%", "white", "black"]),
+            vec![0.0, 0.25, 0.5, 0.75, 1.0]
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use once_cell::sync::Lazy;
+use rustybuzz::{Feature, Tag, UnicodeBuffer};
+use thiserror::Error;
+use ttf_parser::{GlyphId, OutlineBuilder};
+use usvg::{
+    fontdb::{Database, Family, Query, Weight, ID},
+    PathData,
+};
+
+const CACHE_CAPACITY: usize = 256;
+
+static LAYOUTS: Lazy<Mutex<LayoutCache>> = Lazy::new(Default::default);
+static FALLBACKS: Lazy<Mutex<HashMap<char, Option<ID>>>> = Lazy::new(Default::default);
+static FACES: Lazy<Mutex<HashMap<(String, u16), ID>>> = Lazy::new(Default::default);
+
+#[derive(Error, Debug)]
+pub enum TextLayoutError {
+    #[error("Failed to find font {0}{1}")]
+    FontNotFound(String, String),
+    #[error("Failed to read the font face of {0}")]
+    FaceParse(String),
+}
+
+/// Glyph outlines of a shaped string, starting at 0 with its top at 0.
+pub struct TextLayout {
+    pub outline: PathData,
+    /// Sum of the advances, leading and trailing spaces included.
+    pub width: f64,
+    pub height: f64,
+    /// Distance from the top to the baseline.
+    pub ascender: f64,
+    /// Where each cluster begins, as its byte offset in the string and its pen position.
+    pub carets: Vec<(usize, f64)>,
+}
+
+impl TextLayout {
+    /// Shapes `text`, or takes the layout shaped last time with the same font and size.
+    ///
+    /// `tabular_figures` asks for digits of the same width, where the font has them.
+    pub fn shape(
+        fontdb: &Database,
+        family: &str,
+        weight: u16,
+        size: f64,
+        tabular_figures: bool,
+        text: &str,
+    ) -> Result<Arc<TextLayout>, TextLayoutError> {
+        let font = face(fontdb, family, weight)?;
+
+        let key = LayoutKey {
+            font,
+            size: size.to_bits(),
+            tabular_figures,
+            text: text.to_string(),
+        };
+        if let Some(layout) = LAYOUTS.lock().ok().and_then(|mut cache| cache.get(&key)) {
+            return Ok(layout);
+        }
+
+        let layout = Arc::new(
+            build(fontdb, font, size, tabular_figures, text)
+                .ok_or_else(|| TextLayoutError::FaceParse(family.to_string()))?,
+        );
+        if let Ok(mut cache) = LAYOUTS.lock() {
+            cache.insert(key, layout.clone());
+        }
+        Ok(layout)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct LayoutKey {
+    font: ID,
+    size: u64,
+    tabular_figures: bool,
+    text: String,
+}
+
+/// Keeps the layouts used since the last turnover and drops the rest, so that
+/// strings changing every frame don't pile up.
+#[derive(Default)]
+struct LayoutCache {
+    current: HashMap<LayoutKey, Arc<TextLayout>>,
+    previous: HashMap<LayoutKey, Arc<TextLayout>>,
+}
+
+impl LayoutCache {
+    fn get(&mut self, key: &LayoutKey) -> Option<Arc<TextLayout>> {
+        if let Some(layout) = self.current.get(key) {
+            return Some(layout.clone());
+        }
+        let layout = self.previous.remove(key)?;
+        self.insert(key.clone(), layout.clone());
+        Some(layout)
+    }
+
+    fn insert(&mut self, key: LayoutKey, layout: Arc<TextLayout>) {
+        if self.current.len() >= CACHE_CAPACITY {
+            self.previous = std::mem::take(&mut self.current);
+        }
+        self.current.insert(key, layout);
+    }
+}
+
+/// The face of `family` at `weight`, looked up in `fontdb` only the first time as that goes
+/// through every face.
+fn face(fontdb: &Database, family: &str, weight: u16) -> Result<ID, TextLayoutError> {
+    let key = (family.to_string(), weight);
+    if let Some(font) = FACES.lock().ok().and_then(|cache| cache.get(&key).copied()) {
+        return Ok(font);
+    }
+    let font = fontdb
+        .query(&Query {
+            families: &[Family::Name(family)],
+            weight: Weight(weight),
+            ..Default::default()
+        })
+        .ok_or_else(|| {
+            TextLayoutError::FontNotFound(family.to_string(), suggest(fontdb, family))
+        })?;
+    if let Ok(mut cache) = FACES.lock() {
+        cache.insert(key, font);
+    }
+    Ok(font)
+}
+
+fn suggest(fontdb: &Database, family: &str) -> String {
+    let mut families = fontdb
+        .faces()
+        .iter()
+        .map(|face| face.family.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    families.sort_by_key(|candidate| strsim::damerau_levenshtein(family, candidate));
+    if families.is_empty() {
+        ".".to_string()
+    } else {
+        format!(
+            ", you may wanted to use one of these fonts: {}",
+            families.into_iter().take(5).collect::<Vec<_>>().join(", ")
+        )
+    }
+}
+
+fn build(
+    fontdb: &Database,
+    primary: ID,
+    size: f64,
+    tabular_figures: bool,
+    text: &str,
+) -> Option<TextLayout> {
+    let features = if tabular_figures {
+        vec![Feature::new(Tag::from_bytes(b"tnum"), 1, ..)]
+    } else {
+        Vec::new()
+    };
+
+    let (ascender, height) = fontdb.with_face_data(primary, |data, index| {
+        let face = ttf_parser::Face::from_slice(data, index).ok()?;
+        let scale = size / face.units_per_em() as f64;
+        Some((scale * face.ascender() as f64, scale * face.height() as f64))
+    })??;
+
+    let mut layout = TextLayout {
+        outline: PathData::new(),
+        width: 0.0,
+        height,
+        ascender,
+        carets: Vec::new(),
+    };
+    for (font, start, run) in runs(fontdb, primary, text)? {
+        fontdb.with_face_data(font, |data, index| {
+            let face = rustybuzz::Face::from_slice(data, index)?;
+            let scale = size / face.units_per_em() as f64;
+
+            let mut buffer = UnicodeBuffer::new();
+            buffer.push_str(run);
+            let glyphs = rustybuzz::shape(&face, &features, buffer);
+            for (info, position) in glyphs.glyph_infos().iter().zip(glyphs.glyph_positions()) {
+                let offset = start + info.cluster as usize;
+                if layout.carets.last().map(|&(last, _)| last) != Some(offset) {
+                    layout.carets.push((offset, layout.width));
+                }
+                face.outline_glyph(
+                    GlyphId(info.glyph_id as u16),
+                    &mut Outline {
+                        data: &mut layout.outline,
+                        x: layout.width + position.x_offset as f64 * scale,
+                        y: ascender - position.y_offset as f64 * scale,
+                        scale,
+                    },
+                );
+                layout.width += position.x_advance as f64 * scale;
+            }
+            Some(())
+        })??;
+    }
+
+    Some(layout)
+}
+
+/// Splits `text` into runs of the same face with their byte offsets, falling back to the first face
+/// having a glyph for the characters `primary` lacks.
+fn runs<'a>(fontdb: &Database, primary: ID, text: &'a str) -> Option<Vec<(ID, usize, &'a str)>> {
+    let fonts = fontdb.with_face_data(primary, |data, index| {
+        let face = ttf_parser::Face::from_slice(data, index).ok()?;
+        Some(
+            text.chars()
+                .map(|c| match face.glyph_index(c) {
+                    Some(_) => primary,
+                    None => fallback(fontdb, c).unwrap_or(primary),
+                })
+                .collect::<Vec<_>>(),
+        )
+    })??;
+
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut current = None;
+    for ((offset, _), font) in text.char_indices().zip(fonts) {
+        if current != Some(font) {
+            if let Some(previous) = current {
+                runs.push((previous, start, &text[start..offset]));
+            }
+            start = offset;
+            current = Some(font);
+        }
+    }
+    if let Some(font) = current {
+        runs.push((font, start, &text[start..]));
+    }
+    Some(runs)
+}
+
+fn fallback(fontdb: &Database, c: char) -> Option<ID> {
+    if let Some(font) = FALLBACKS
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&c).copied())
+    {
+        return font;
+    }
+    let font = fontdb.faces().iter().map(|face| face.id).find(|&id| {
+        fontdb
+            .with_face_data(id, |data, index| {
+                ttf_parser::Face::from_slice(data, index)
+                    .ok()
+                    .and_then(|face| face.glyph_index(c))
+                    .is_some()
+            })
+            .unwrap_or(false)
+    });
+    if let Ok(mut cache) = FALLBACKS.lock() {
+        cache.insert(c, font);
+    }
+    font
+}
+
+/// Writes glyph outlines, flipping them to y-down around the baseline at `y`.
+struct Outline<'a> {
+    data: &'a mut PathData,
+    x: f64,
+    y: f64,
+    scale: f64,
+}
+
+impl Outline<'_> {
+    fn point(&self, x: f32, y: f32) -> (f64, f64) {
+        (
+            self.x + x as f64 * self.scale,
+            self.y - y as f64 * self.scale,
+        )
+    }
+}
+
+impl OutlineBuilder for Outline<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.point(x, y);
+        self.data.push_move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.point(x, y);
+        self.data.push_line_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x1, y1) = self.point(x1, y1);
+        let (x, y) = self.point(x, y);
+        self.data.push_quad_to(x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x1, y1) = self.point(x1, y1);
+        let (x2, y2) = self.point(x2, y2);
+        let (x, y) = self.point(x, y);
+        self.data.push_curve_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.data.push_close_path();
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::RwLock,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use svgtypes::Color as SvgColor;
+use thiserror::Error;
+
+static THEME: Lazy<RwLock<ActiveTheme>> = Lazy::new(Default::default);
+
+#[derive(Default)]
+struct ActiveTheme {
+    theme: Theme,
+    variant: ThemeVariant,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThemeVariant {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// Which variant to use, `auto` following the system where it is known.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeMode {
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+impl ThemeMode {
+    pub fn variant(&self) -> ThemeVariant {
+        match self {
+            ThemeMode::Auto => system_variant().unwrap_or_default(),
+            ThemeMode::Light => ThemeVariant::Light,
+            ThemeMode::Dark => ThemeVariant::Dark,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn system_variant() -> Option<ThemeVariant> {
+    crate::platform::theme::apps_use_light_theme().map(|light| {
+        if light {
+            ThemeVariant::Light
+        } else {
+            ThemeVariant::Dark
+        }
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+fn system_variant() -> Option<ThemeVariant> {
+    None
+}
+
+#[derive(Error, Debug)]
+pub enum ThemeLoadError {
+    #[error("I/O failed from {0}")]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("Failed to deserialize {0}: {1}")]
+    TomlDeserialize(PathBuf, #[source] toml::de::Error),
+    #[error("Failed to parse color {1} of {0}")]
+    Color(String, String),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ThemeFile {
+    #[serde(default)]
+    palette: HashMap<String, String>,
+    #[serde(default)]
+    light: HashMap<String, String>,
+    #[serde(default)]
+    dark: HashMap<String, String>,
+}
+
+/// Named colors, each resolved for both variants.
+#[derive(Default)]
+pub struct Theme {
+    light: HashMap<String, SvgColor>,
+    dark: HashMap<String, SvgColor>,
+}
+
+impl Theme {
+    pub fn load(path: &Path) -> Result<Theme, ThemeLoadError> {
+        let file: ThemeFile = toml::from_slice(
+            &fs::read(path).map_err(|e| ThemeLoadError::Io(path.to_path_buf(), e))?,
+        )
+        .map_err(|e| ThemeLoadError::TomlDeserialize(path.to_path_buf(), e))?;
+
+        let palette = file
+            .palette
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), parse(name, value)?)))
+            .collect::<Result<HashMap<_, _>, ThemeLoadError>>()?;
+        // Tokens of a variant are either palette names or colors, and fall back to the palette.
+        let variant = |tokens: &HashMap<String, String>| {
+            let mut colors = palette.clone();
+            for (name, value) in tokens {
+                let color = match palette.get(value) {
+                    Some(color) => *color,
+                    None => parse(name, value)?,
+                };
+                colors.insert(name.clone(), color);
+            }
+            Ok::<_, ThemeLoadError>(colors)
+        };
+
+        Ok(Theme {
+            light: variant(&file.light)?,
+            dark: variant(&file.dark)?,
+        })
+    }
+
+    /// Makes the theme the one `theme:` colors refer to.
+    pub fn install(self) {
+        if let Ok(mut active) = THEME.write() {
+            active.theme = self;
+        }
+    }
+
+    pub fn set_variant(variant: ThemeVariant) {
+        if let Ok(mut active) = THEME.write() {
+            active.variant = variant;
+        }
+    }
+
+    pub fn variant() -> ThemeVariant {
+        THEME
+            .read()
+            .map(|active| active.variant)
+            .unwrap_or_default()
+    }
+
+    /// Looks `token` up in the installed theme, for the current variant.
+    pub fn resolve(token: &str) -> Option<SvgColor> {
+        let active = THEME.read().ok()?;
+        let colors = match active.variant {
+            ThemeVariant::Light => &active.theme.light,
+            ThemeVariant::Dark => &active.theme.dark,
+        };
+        colors.get(token).copied()
+    }
+}
+
+fn parse(name: &str, value: &str) -> Result<SvgColor, ThemeLoadError> {
+    SvgColor::from_str(value)
+        .map_err(|_| ThemeLoadError::Color(name.to_string(), value.to_string()))
+}
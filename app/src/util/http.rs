@@ -18,3 +18,19 @@ static HOOK: Lazy<AssetPreHook<surf::Error>> = Lazy::new(|| {
 pub async fn get(url: impl AsRef<str>) -> Result<Vec<u8>, AssetPreHookError<surf::Error>> {
     HOOK.run(Url::parse(url.as_ref()).map_err(surf::Error::from)?).await
 }
+
+/// Unlike [`get`], always hits the network and fails on a non-success status.
+pub async fn fetch(url: &Url, headers: &[(String, String)]) -> Result<Vec<u8>, surf::Error> {
+    let mut request = CLIENT.get(url);
+    for (name, value) in headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let mut response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(surf::Error::from_str(
+            response.status(),
+            format!("{} responded with {}", url, response.status()),
+        ));
+    }
+    response.body_bytes().await
+}
@@ -0,0 +1,136 @@
+use std::str::FromStr;
+
+use declarrred::rt::Data;
+use serde_json::Value;
+use thiserror::Error;
+
+pub fn to_data(value: &Value) -> Data {
+    match value {
+        Value::Null => Data::Unknown,
+        Value::Bool(v) => Data::Bool(*v),
+        Value::Number(v) => {
+            if let Some(v) = v.as_u64() {
+                Data::U64(v)
+            } else if let Some(v) = v.as_i64() {
+                Data::I64(v)
+            } else {
+                Data::F64(v.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Value::String(v) => Data::String(v.clone()),
+        Value::Array(v) => Data::List(v.iter().map(to_data).collect()),
+        Value::Object(v) => Data::Map(
+            v.iter()
+                .map(|(key, value)| (key.clone(), to_data(value)))
+                .collect(),
+        ),
+    }
+}
+
+pub fn from_data(data: &Data) -> Value {
+    match data {
+        Data::String(v) => Value::String(v.clone()),
+        Data::I32(v) => (*v).into(),
+        Data::U32(v) => (*v).into(),
+        Data::I64(v) => (*v).into(),
+        Data::U64(v) => (*v).into(),
+        Data::F64(v) => (*v).into(),
+        Data::Bool(v) => Value::Bool(*v),
+        Data::List(v) => Value::Array(v.iter().map(from_data).collect()),
+        Data::Map(v) => Value::Object(
+            v.iter()
+                .map(|(key, value)| (key.clone(), from_data(value)))
+                .collect(),
+        ),
+        Data::Unknown => Value::Null,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Locates a value inside a JSON document.
+///
+/// Either a JSON pointer (`/current/temp_c`) or a JSONPath of dots and brackets only
+/// (`$.current.temp_c`, `$.jobs[0]['name']`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Error)]
+#[error("{0} is neither a JSON pointer nor a supported JSONPath")]
+pub struct JsonPathParseError(String);
+
+impl FromStr for JsonPath {
+    type Err = JsonPathParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || JsonPathParseError(s.to_string());
+        if s.is_empty() {
+            return Ok(JsonPath {
+                segments: Vec::new(),
+            });
+        }
+        if let Some(pointer) = s.strip_prefix('/') {
+            return Ok(JsonPath {
+                segments: pointer
+                    .split('/')
+                    .map(|token| token.replace("~1", "/").replace("~0", "~"))
+                    .map(|token| match token.parse() {
+                        Ok(index) => Segment::Index(index),
+                        Err(_) => Segment::Key(token),
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut rest = s.strip_prefix('$').ok_or_else(err)?;
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(err());
+                }
+                segments.push(Segment::Key(after[..end].to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(err)?;
+                let inner = after[..end].trim();
+                segments.push(
+                    match inner
+                        .strip_prefix('\'')
+                        .and_then(|it| it.strip_suffix('\''))
+                        .or_else(|| inner.strip_prefix('"').and_then(|it| it.strip_suffix('"')))
+                    {
+                        Some(key) => Segment::Key(key.to_string()),
+                        None => Segment::Index(inner.parse().map_err(|_| err())?),
+                    },
+                );
+                rest = &after[end + 1..];
+            } else {
+                return Err(err());
+            }
+        }
+
+        Ok(JsonPath { segments })
+    }
+}
+
+impl JsonPath {
+    pub fn find<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match (segment, value) {
+                (Segment::Key(key), Value::Object(object)) => object.get(key),
+                (Segment::Index(index), Value::Array(array)) => array.get(*index),
+                // JSON pointers can't tell an index from a numeric key.
+                (Segment::Index(index), Value::Object(object)) => object.get(&index.to_string()),
+                _ => None,
+            })
+    }
+}
@@ -0,0 +1,46 @@
+use async_stream::try_stream;
+use url::Url;
+
+use crate::{
+    component::{Job, JobStage, SetupContext},
+    util::http,
+};
+
+/// Reads `url` from online or from a file, and hands the bytes to `finalizer`.
+pub fn load_job(
+    url: Url,
+    finalizer: impl FnOnce(Vec<u8>, &mut SetupContext) -> eyre::Result<()> + Send + 'static,
+) -> Job {
+    Box::pin(try_stream! {
+        let data = match url.scheme() {
+            "http" | "https" => {
+                yield JobStage::Progress {
+                    label: format!("Reading {} from online...", url),
+                    value: 0.0
+                };
+                http::get(&url).await.map_err(|_| eyre::eyre!("Failed to request {}", url))?
+            }
+            "file" => {
+                yield JobStage::Progress {
+                    label: format!("Reading {} from file...", url),
+                    value: 0.0
+                };
+                std::fs::read(
+                    url
+                        .to_file_path()
+                        .map_err(|_| eyre::eyre!("Failed to convert {} into path.", url))?,
+                )?
+            }
+            scheme => {
+                yield JobStage::Fail {
+                    label: format!("Unsupported url scheme: {}", scheme)
+                };
+                return
+            },
+        };
+        yield JobStage::Completed {
+            label: format!("Loaded {}!", url),
+            finalizer: Box::new(move |context| finalizer(data, context))
+        }
+    })
+}
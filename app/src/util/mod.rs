@@ -1,2 +1,4 @@
 pub mod http;
+pub mod json;
+pub mod load;
 pub mod serde;
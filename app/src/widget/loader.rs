@@ -1,6 +1,6 @@
 use std::{
     fs::File,
-    io::{BufReader, Read},
+    io::Read,
     path::{Path, PathBuf},
 };
 
@@ -8,7 +8,7 @@ use thiserror::Error;
 
 use crate::component::Component;
 
-use super::{Widget, WidgetConfig};
+use super::{template, Widget, WidgetConfig};
 
 #[derive(Error, Debug)]
 pub enum WidgetLoadError {
@@ -18,6 +18,12 @@ pub enum WidgetLoadError {
     TomlDeserialize(PathBuf, #[source] toml::de::Error),
     #[error("Failed to deserialize {0}: {1}")]
     XmlDeserialize(PathBuf, #[source] quick_xml::DeError),
+    #[error("Failed to parse {0}: {1}")]
+    XmlParse(PathBuf, #[source] quick_xml::Error),
+    #[error("{0}:{1}: {2}")]
+    Template(PathBuf, usize, String),
+    #[error("{0}:{1}: Failed to include {2}: {3}")]
+    Include(PathBuf, usize, PathBuf, #[source] Box<WidgetLoadError>),
 }
 
 pub fn load_widget<'a>(directory: impl AsRef<Path>) -> Result<Option<Widget>, WidgetLoadError> {
@@ -25,12 +31,27 @@ pub fn load_widget<'a>(directory: impl AsRef<Path>) -> Result<Option<Widget>, Wi
     let widget_config = load_widget_config(directory.join("taskbar.config.toml"))?;
     let component = load_widget_components(directory.join("taskbar.component.xml"))?;
     if widget_config.general.enabled {
-        Ok(Some(Widget::new(widget_config, component)))
+        Ok(Some(Widget::new(
+            widget_name(directory),
+            widget_config,
+            component,
+        )))
     } else {
         Ok(None)
     }
 }
 
+fn widget_name(directory: &Path) -> String {
+    let mut components: Vec<_> = directory
+        .components()
+        .rev()
+        .take(2)
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    components.reverse();
+    components.join("/")
+}
+
 pub fn load_widget_config(path: PathBuf) -> Result<WidgetConfig, WidgetLoadError> {
     Ok(toml::from_slice(
         &File::open(path.clone())
@@ -41,8 +62,6 @@ pub fn load_widget_config(path: PathBuf) -> Result<WidgetConfig, WidgetLoadError
 }
 
 pub fn load_widget_components(path: PathBuf) -> Result<Component, WidgetLoadError> {
-    Ok(quick_xml::de::from_reader(BufReader::new(
-        File::open(path.clone()).map_err(|e| WidgetLoadError::Io(path.clone(), e))?,
-    ))
-    .map_err(|e| WidgetLoadError::XmlDeserialize(path.clone(), e))?)
+    quick_xml::de::from_str(&template::expand(&path)?)
+        .map_err(|e| WidgetLoadError::XmlDeserialize(path.clone(), e))
 }
@@ -10,7 +10,7 @@ use crate::{
         Component, ComponentAction, Job, JobStage, RenderContext, SetupContext, UpdateContext,
     },
     config::MeasurrredConfig,
-    system::{HorizontalPosition, Rect, VerticalPosition},
+    system::{hoist_paint_servers, HorizontalPosition, Rect, VerticalPosition},
 };
 
 pub use self::config::WidgetConfig;
@@ -18,16 +18,20 @@ pub use self::loader::*;
 
 mod config;
 mod loader;
+mod template;
 
 pub struct Widget {
+    /// `<author>/<widget>`, after the directory it was loaded from.
+    pub name: String,
     pub x: HorizontalPosition,
     pub y: VerticalPosition,
     pub component: Component,
 }
 
 impl Widget {
-    pub fn new(config: WidgetConfig, component: Component) -> Self {
+    pub fn new(name: String, config: WidgetConfig, component: Component) -> Self {
         Widget {
+            name,
             x: config.position.x,
             y: config.position.y,
             component,
@@ -94,7 +98,7 @@ impl Widget {
         );
         let root = self.component.render(&render_context)?;
 
-        let tree = usvg::Tree::create(usvg::Svg {
+        let mut tree = usvg::Tree::create(usvg::Svg {
             size: usvg::Size::new(viewbox_width, viewbox_height).unwrap(),
             view_box: usvg::ViewBox {
                 rect: usvg::Rect::new(0.0, 0.0, viewbox_width, viewbox_height).unwrap(),
@@ -106,6 +110,8 @@ impl Widget {
             },
         });
 
+        hoist_paint_servers(&root, &mut tree);
+
         let bbox = root.calculate_bbox().unwrap();
         let actual_width = bbox.width();
         let actual_height = bbox.height();
@@ -0,0 +1,504 @@
+//! `<include>`, `<define>` and `<use>`, expanded before components are deserialized.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use url::Url;
+
+use super::WidgetLoadError;
+
+#[derive(Clone)]
+enum XmlNode {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Clone)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+    line: usize,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Clone)]
+struct Template {
+    /// The file the template is defined in, which its includes are relative to.
+    file: PathBuf,
+    params: Vec<String>,
+    body: Vec<XmlNode>,
+}
+
+/// Reads the components at `path`, with every include and template expanded.
+pub fn expand(path: &Path) -> Result<String, WidgetLoadError> {
+    let mut expander = Expander::default();
+    let nodes = expander.expand_file(path)?;
+
+    let mut out = String::new();
+    write(&nodes, &mut out);
+    Ok(out)
+}
+
+#[derive(Default)]
+struct Expander {
+    /// Canonical paths of the files being expanded, outermost first.
+    includes: Vec<PathBuf>,
+    /// Names of the templates being expanded, outermost first.
+    uses: Vec<String>,
+    templates: HashMap<String, Template>,
+}
+
+impl Expander {
+    fn expand_file(&mut self, path: &Path) -> Result<Vec<XmlNode>, WidgetLoadError> {
+        let canonical =
+            fs::canonicalize(path).map_err(|e| WidgetLoadError::Io(path.to_path_buf(), e))?;
+        self.includes.push(canonical);
+        let nodes = parse(path)?;
+        let nodes = self.expand_nodes(path, nodes)?;
+        self.includes.pop();
+
+        Ok(nodes)
+    }
+
+    fn expand_nodes(
+        &mut self,
+        file: &Path,
+        nodes: Vec<XmlNode>,
+    ) -> Result<Vec<XmlNode>, WidgetLoadError> {
+        let mut out = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let mut element = match node {
+                XmlNode::Element(element) => element,
+                text => {
+                    out.push(text);
+                    continue;
+                }
+            };
+            let line = element.line;
+            let error =
+                |message: String| WidgetLoadError::Template(file.to_path_buf(), line, message);
+
+            match element.name.as_str() {
+                "include" => {
+                    let src = element
+                        .attribute("src")
+                        .ok_or_else(|| error("<include> requires src".to_string()))?;
+                    let target = file.parent().unwrap_or_else(|| Path::new("")).join(src);
+                    if let Ok(canonical) = fs::canonicalize(&target) {
+                        if let Some(begin) = self.includes.iter().position(|it| it == &canonical) {
+                            let cycle = self.includes[begin..]
+                                .iter()
+                                .chain([&canonical])
+                                .map(|it| it.to_string_lossy())
+                                .collect::<Vec<_>>()
+                                .join(" -> ");
+                            return Err(error(format!("Include cycle: {}", cycle)));
+                        }
+                    }
+
+                    let nodes = self.expand_file(&target).map_err(|e| {
+                        WidgetLoadError::Include(
+                            file.to_path_buf(),
+                            element.line,
+                            src.into(),
+                            Box::new(e),
+                        )
+                    })?;
+                    out.extend(nodes);
+                }
+                "define" => {
+                    let name = element
+                        .attribute("name")
+                        .ok_or_else(|| error("<define> requires name".to_string()))?
+                        .to_string();
+                    let params = element
+                        .attribute("params")
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .map(str::to_string)
+                        .collect();
+                    self.templates.insert(
+                        name,
+                        Template {
+                            file: file.to_path_buf(),
+                            params,
+                            body: element.children,
+                        },
+                    );
+                }
+                "use" => {
+                    let name = element
+                        .attribute("name")
+                        .ok_or_else(|| error("<use> requires name".to_string()))?;
+                    let template =
+                        self.templates.get(name).cloned().ok_or_else(|| {
+                            error(format!("There are no template named {}", name))
+                        })?;
+                    if self.uses.iter().any(|it| it == name) {
+                        return Err(error(format!(
+                            "Template cycle: {} -> {}",
+                            self.uses.join(" -> "),
+                            name
+                        )));
+                    }
+                    for (key, _) in &element.attributes {
+                        if key != "name" && !template.params.contains(key) {
+                            return Err(error(format!(
+                                "Template {} has no parameter named {}",
+                                name, key
+                            )));
+                        }
+                    }
+                    let mut arguments = HashMap::new();
+                    for param in &template.params {
+                        let value = element.attribute(param).ok_or_else(|| {
+                            error(format!("Template {} requires {}", name, param))
+                        })?;
+                        arguments.insert(param.as_str(), value);
+                    }
+
+                    let body = substitute(template.body.clone(), &arguments);
+                    self.uses.push(name.to_string());
+                    let nodes = self.expand_nodes(&template.file, body)?;
+                    self.uses.pop();
+                    out.extend(nodes);
+                }
+                _ => {
+                    if element.name == "image" {
+                        resolve_src(file, &mut element).map_err(error)?;
+                    }
+                    element.children = self.expand_nodes(file, element.children)?;
+                    out.push(XmlNode::Element(element));
+                }
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+fn parse(path: &Path) -> Result<Vec<XmlNode>, WidgetLoadError> {
+    let content =
+        fs::read_to_string(path).map_err(|e| WidgetLoadError::Io(path.to_path_buf(), e))?;
+    let xml_error = |e| WidgetLoadError::XmlParse(path.to_path_buf(), e);
+
+    let mut reader = Reader::from_str(&content);
+    reader.check_end_names(true);
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+    let mut buf = Vec::new();
+    let (mut line, mut counted) = (1, 0);
+    loop {
+        line += content[counted..reader.buffer_position()]
+            .matches('\n')
+            .count();
+        counted = reader.buffer_position();
+        let node = match reader.read_event(&mut buf).map_err(xml_error)? {
+            Event::Start(start) => {
+                stack.push(element(&reader, &start, line).map_err(xml_error)?);
+                continue;
+            }
+            Event::Empty(start) => {
+                XmlNode::Element(element(&reader, &start, line).map_err(xml_error)?)
+            }
+            Event::End(end) => XmlNode::Element(stack.pop().ok_or_else(|| {
+                xml_error(quick_xml::Error::UnexpectedToken(
+                    String::from_utf8_lossy(end.name()).into_owned(),
+                ))
+            })?),
+            Event::Text(text) => {
+                XmlNode::Text(text.unescape_and_decode(&reader).map_err(xml_error)?)
+            }
+            Event::CData(text) => XmlNode::Text(String::from_utf8_lossy(&text).into_owned()),
+            Event::Eof => break,
+            _ => continue,
+        };
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+    if let Some(unclosed) = stack.pop() {
+        return Err(xml_error(quick_xml::Error::UnexpectedEof(unclosed.name)));
+    }
+
+    Ok(roots)
+}
+
+fn element(
+    reader: &Reader<&[u8]>,
+    start: &BytesStart,
+    line: usize,
+) -> Result<Element, quick_xml::Error> {
+    let attributes = start
+        .attributes()
+        .map(|attribute| {
+            let attribute = attribute?;
+            Ok((
+                String::from_utf8_lossy(attribute.key).into_owned(),
+                attribute.unescape_and_decode_value(reader)?,
+            ))
+        })
+        .collect::<Result<_, quick_xml::Error>>()?;
+
+    Ok(Element {
+        name: String::from_utf8_lossy(start.name()).into_owned(),
+        attributes,
+        children: Vec::new(),
+        line,
+    })
+}
+
+/// Turns a path in `src` into a `file:` URL, relative to the file it is written in like includes.
+fn resolve_src(file: &Path, element: &mut Element) -> Result<(), String> {
+    for (key, value) in element.attributes.iter_mut() {
+        // A single letter is the drive of a Windows path rather than a scheme.
+        if key != "src" || matches!(Url::parse(value), Ok(url) if url.scheme().len() > 1) {
+            continue;
+        }
+        let path = file.parent().unwrap_or_else(|| Path::new("")).join(&*value);
+        let path =
+            fs::canonicalize(&path).map_err(|e| format!("Failed to find {}: {}", value, e))?;
+        *value = Url::from_file_path(&path)
+            .map_err(|_| format!("Failed to convert {} into URL", path.to_string_lossy()))?
+            .to_string();
+    }
+    Ok(())
+}
+
+/// Replaces every `${param}` in attribute values and texts.
+fn substitute(nodes: Vec<XmlNode>, arguments: &HashMap<&str, &str>) -> Vec<XmlNode> {
+    let replace = |value: &str| {
+        arguments
+            .iter()
+            .fold(value.to_string(), |value, (param, argument)| {
+                value.replace(&format!("${{{}}}", param), argument)
+            })
+    };
+
+    nodes
+        .into_iter()
+        .map(|node| match node {
+            XmlNode::Element(mut element) => {
+                for (_, value) in element.attributes.iter_mut() {
+                    *value = replace(value);
+                }
+                element.children = substitute(element.children, arguments);
+                XmlNode::Element(element)
+            }
+            XmlNode::Text(text) => XmlNode::Text(replace(&text)),
+        })
+        .collect()
+}
+
+fn write(nodes: &[XmlNode], out: &mut String) {
+    for node in nodes {
+        match node {
+            XmlNode::Element(element) => {
+                out.push('<');
+                out.push_str(&element.name);
+                for (key, value) in &element.attributes {
+                    out.push_str(&format!(r#" {}="{}""#, key, escape(value)));
+                }
+                if element.children.is_empty() {
+                    out.push_str(" />");
+                } else {
+                    out.push('>');
+                    match element.name.as_str() {
+                        "text" | "span" => write_inline(&element.children, out),
+                        _ => write(&element.children, out),
+                    }
+                    out.push_str(&format!("</{}>", element.name));
+                }
+            }
+            XmlNode::Text(text) => out.push_str(&escape(text)),
+        }
+    }
+}
+
+/// Writes the content of `<text>` and `<span>`, keeping whitespace at the ends
+/// of text as a character reference so that it still separates the fragments
+/// after the deserializer trims each text. `<text>` collapses it later.
+fn write_inline(nodes: &[XmlNode], out: &mut String) {
+    for node in nodes {
+        match node {
+            XmlNode::Text(text) => {
+                let trimmed = text.trim();
+                if text.starts_with(char::is_whitespace) {
+                    out.push_str("&#32;");
+                }
+                if !trimmed.is_empty() {
+                    out.push_str(&escape(trimmed));
+                    if text.ends_with(char::is_whitespace) {
+                        out.push_str("&#32;");
+                    }
+                }
+            }
+            element => write(std::slice::from_ref(element), out),
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    /// Writes `files` into a directory of their own, and returns the path of the first.
+    fn files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory = env::temp_dir().join(format!("measurrred-{}-{}", test, process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        for (name, content) in files {
+            let path = directory.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        directory.join(files[0].0)
+    }
+
+    /// Texts as the deserializer sees them, trimmed before they are unescaped.
+    fn texts(xml: &str) -> Vec<String> {
+        let mut reader = Reader::from_str(xml);
+        reader.trim_text(true);
+        let mut buf = Vec::new();
+        let mut texts = Vec::new();
+        loop {
+            match reader.read_event(&mut buf).unwrap() {
+                Event::Text(text) => texts.push(text.unescape_and_decode(&reader).unwrap()),
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        texts
+    }
+
+    #[test]
+    fn includes_files_relative_to_the_including_one() {
+        let path = files(
+            "include",
+            &[
+                ("main.xml", r#"<hbox><include src="shared/a.xml" /></hbox>"#),
+                ("shared/a.xml", r#"<text>A</text><include src="b.xml" />"#),
+                ("shared/b.xml", r#"<margin size="4px" />"#),
+            ],
+        );
+        assert_eq!(
+            expand(&path).unwrap(),
+            r#"<hbox><text>A</text><margin size="4px" /></hbox>"#
+        );
+    }
+
+    #[test]
+    fn expands_templates_with_their_arguments() {
+        let path = files(
+            "use",
+            &[(
+                "main.xml",
+                r#"<vbox><define name="tile" params="label var"><text color="${var}">${label}</text></define><use name="tile" label="CPU" var="cpu" /><use name="tile" label="RAM" var="ram" /></vbox>"#,
+            )],
+        );
+        assert_eq!(
+            expand(&path).unwrap(),
+            r#"<vbox><text color="cpu">CPU</text><text color="ram">RAM</text></vbox>"#
+        );
+    }
+
+    #[test]
+    fn rejects_include_cycles() {
+        let path = files(
+            "include-cycle",
+            &[
+                ("a.xml", r#"<include src="b.xml" />"#),
+                ("b.xml", r#"<include src="a.xml" />"#),
+            ],
+        );
+        let error = expand(&path).unwrap_err().to_string();
+        assert!(error.contains("Include cycle"), "{}", error);
+        assert!(error.contains("a.xml -> "), "{}", error);
+    }
+
+    #[test]
+    fn rejects_template_cycles() {
+        let path = files(
+            "use-cycle",
+            &[(
+                "main.xml",
+                r#"<group><define name="a"><use name="b" /></define><define name="b"><use name="a" /></define><use name="a" /></group>"#,
+            )],
+        );
+        let error = expand(&path).unwrap_err().to_string();
+        assert!(error.ends_with("Template cycle: a -> b -> a"), "{}", error);
+    }
+
+    #[test]
+    fn points_errors_at_their_line() {
+        let path = files(
+            "line",
+            &[
+                (
+                    "main.xml",
+                    "<hbox>\n    <text>A</text>\n\n    <use name=\"tile\" />\n</hbox>",
+                ),
+                (
+                    "other.xml",
+                    "<group>\n    <include src=\"main.xml\" />\n</group>",
+                ),
+            ],
+        );
+        match expand(&path) {
+            Err(WidgetLoadError::Template(file, line, message)) => {
+                assert_eq!(file, path);
+                assert_eq!(line, 4);
+                assert_eq!(message, "There are no template named tile");
+            }
+            _ => panic!("Expected a template error"),
+        }
+
+        match expand(&path.with_file_name("other.xml")) {
+            Err(WidgetLoadError::Include(_, 2, src, inner)) => {
+                assert_eq!(src, PathBuf::from("main.xml"));
+                assert!(matches!(*inner, WidgetLoadError::Template(_, 4, _)));
+            }
+            _ => panic!("Expected an include error"),
+        }
+    }
+
+    #[test]
+    fn keeps_whitespace_around_text_fragments() {
+        let path = files(
+            "whitespace",
+            &[(
+                "main.xml",
+                r#"<group><define name="unit" params="unit"><text><variable name="clock" />&#x0020;${unit} / <variable name="max" /> </text></define><use name="unit" unit="GHz" /></group>"#,
+            )],
+        );
+        let expanded = expand(&path).unwrap();
+        assert_eq!(texts(&expanded), vec![" GHz / ", " "]);
+    }
+}
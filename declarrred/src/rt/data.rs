@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::BTreeMap};
 
 use thiserror::Error;
 
@@ -13,6 +13,8 @@ pub enum Data {
     U64(u64),
     F64(f64),
     Bool(bool),
+    List(Vec<Data>),
+    Map(BTreeMap<String, Data>),
     Unknown,
 }
 
@@ -27,15 +29,27 @@ pub struct DataConversionError {
 }
 
 impl Data {
-    pub fn as_string(&self) -> Result<Cow<str>, DataConversionError> {
+    pub fn as_string(&self) -> Result<Cow<'_, str>, DataConversionError> {
         Ok(match self {
-            Data::String(v) => Cow::Borrowed(&v),
+            Data::String(v) => Cow::Borrowed(v),
             Data::I32(v) => Cow::Owned(v.to_string()),
             Data::U32(v) => Cow::Owned(v.to_string()),
             Data::I64(v) => Cow::Owned(v.to_string()),
             Data::U64(v) => Cow::Owned(v.to_string()),
             Data::F64(v) => Cow::Owned(v.to_string()),
             Data::Bool(v) => Cow::Owned(v.to_string()),
+            Data::List(v) => Cow::Owned(
+                v.iter()
+                    .map(|item| item.as_string().map(Cow::into_owned))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(", "),
+            ),
+            Data::Map(v) => Cow::Owned(
+                v.iter()
+                    .map(|(key, value)| Ok(format!("{}={}", key, value.as_string()?)))
+                    .collect::<Result<Vec<_>, DataConversionError>>()?
+                    .join(", "),
+            ),
             Data::Unknown => Cow::Borrowed(""),
         })
     }
@@ -52,6 +66,7 @@ impl Data {
             Data::U64(v) => Ok(*v as i64),
             Data::F64(v) => Ok(*v as i64),
             Data::Bool(v) => Ok(*v as i64),
+            Data::List(_) | Data::Map(_) => Err(self.conversion_error(DataFormat::Int)),
             Data::Unknown => Ok(0),
         }
     }
@@ -68,6 +83,7 @@ impl Data {
             Data::U64(v) => Ok(*v),
             Data::F64(v) => Ok(*v as u64),
             Data::Bool(v) => Ok(*v as u64),
+            Data::List(_) | Data::Map(_) => Err(self.conversion_error(DataFormat::UInt)),
             Data::Unknown => Ok(0),
         }
     }
@@ -84,6 +100,7 @@ impl Data {
             Data::U64(v) => Ok(*v as f64),
             Data::F64(v) => Ok(*v),
             Data::Bool(v) => Ok(*v as i64 as f64),
+            Data::List(_) | Data::Map(_) => Err(self.conversion_error(DataFormat::Float)),
             Data::Unknown => Ok(0.0),
         }
     }
@@ -100,7 +117,53 @@ impl Data {
             Data::U64(v) => Ok(*v != 0),
             Data::F64(v) => Ok(v.abs() > f64::EPSILON),
             Data::Bool(v) => Ok(*v),
+            Data::List(v) => Ok(!v.is_empty()),
+            Data::Map(v) => Ok(!v.is_empty()),
             Data::Unknown => Ok(false),
         }
     }
+
+    pub fn as_list(&self) -> Result<&[Data], DataConversionError> {
+        match self {
+            Data::List(v) => Ok(v),
+            Data::Unknown => Ok(&[]),
+            _ => Err(self.conversion_error(DataFormat::List)),
+        }
+    }
+
+    pub fn as_map(&self) -> Result<&BTreeMap<String, Data>, DataConversionError> {
+        match self {
+            Data::Map(v) => Ok(v),
+            _ => Err(self.conversion_error(DataFormat::Map)),
+        }
+    }
+
+    /// Converts the data into the shape requested by `format`, leaving `Unknown` as is.
+    pub fn to_format(&self, format: &DataFormat) -> Result<Data, DataConversionError> {
+        if let Data::Unknown = self {
+            return Ok(Data::Unknown);
+        }
+        Ok(match format {
+            DataFormat::String => Data::String(self.as_string()?.into_owned()),
+            DataFormat::I32 => Data::I32(self.as_i64()? as i32),
+            DataFormat::U32 => Data::U32(self.as_u64()? as u32),
+            DataFormat::I64 | DataFormat::Int => Data::I64(self.as_i64()?),
+            DataFormat::U64 | DataFormat::UInt => Data::U64(self.as_u64()?),
+            DataFormat::F64 | DataFormat::Float => Data::F64(self.as_float()?),
+            DataFormat::Bool => Data::Bool(self.as_bool()?),
+            DataFormat::List => Data::List(self.as_list()?.to_vec()),
+            DataFormat::Map => Data::Map(self.as_map()?.clone()),
+        })
+    }
+
+    fn conversion_error(&self, to_format: DataFormat) -> DataConversionError {
+        DataConversionError {
+            from_format: match self {
+                Data::List(_) => Some(DataFormat::List),
+                Data::Map(_) => Some(DataFormat::Map),
+                _ => None,
+            },
+            to_format,
+        }
+    }
 }
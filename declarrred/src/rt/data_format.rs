@@ -1,6 +1,6 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum DataFormat {
     String,
@@ -13,4 +13,6 @@ pub enum DataFormat {
     F64,
     Float,
     Bool,
+    List,
+    Map,
 }
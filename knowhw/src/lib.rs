@@ -2,6 +2,11 @@ use std::error::Error;
 
 use declarrred::rt::{Data, DataFormat};
 
+pub mod network;
+pub mod process;
+
+#[cfg(target_os = "linux")]
+pub mod linux;
 #[cfg(target_os = "windows")]
 pub mod windows;
 
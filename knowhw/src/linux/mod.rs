@@ -0,0 +1,7 @@
+#![cfg(target_os = "linux")]
+
+pub use network::{Network, NetworkError};
+pub use process::{Process, ProcessError};
+
+mod network;
+mod process;
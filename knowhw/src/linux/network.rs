@@ -0,0 +1,134 @@
+use std::{fs, path::Path, str::FromStr, sync::Mutex};
+
+use declarrred::rt::{Data, DataConversionError, DataFormat};
+use thiserror::Error;
+
+use crate::{
+    network::{InterfaceCounters, InterfaceFilter, NetworkQuery, NetworkQueryError, NetworkTracker},
+    Knowhw,
+};
+
+const ARPHRD_LOOPBACK: u32 = 772;
+const IFF_UP: u32 = 0x1;
+
+pub struct Network {
+    tracker: Mutex<NetworkTracker>,
+}
+
+#[derive(Debug, Error)]
+pub enum NetworkError {
+    #[error("Invalid query: {0}")]
+    InvalidQuery(#[from] NetworkQueryError),
+
+    #[error("{0}")]
+    Conversion(#[from] DataConversionError),
+
+    #[error("Failed to read /proc/net/dev: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Malformed /proc/net/dev line: {0}")]
+    MalformedDev(String),
+
+    #[error("Mutex lock poisoned")]
+    MutexLockPoisoned,
+}
+
+impl Network {
+    pub fn new(filter: InterfaceFilter) -> Self {
+        Network {
+            tracker: Mutex::new(NetworkTracker::new(filter)),
+        }
+    }
+}
+
+impl Knowhw for Network {
+    type Error = NetworkError;
+
+    fn update(&self) -> Result<(), Self::Error> {
+        let interfaces = read_interfaces()?;
+        self.tracker
+            .lock()
+            .map_err(|_| NetworkError::MutexLockPoisoned)?
+            .feed(interfaces);
+
+        Ok(())
+    }
+
+    fn query(&mut self, query: &str, preferred_format: &DataFormat) -> Result<Data, Self::Error> {
+        let query = NetworkQuery::from_str(query)?;
+        let tracker = self
+            .tracker
+            .get_mut()
+            .map_err(|_| NetworkError::MutexLockPoisoned)?;
+
+        Ok(tracker.query(&query)?.to_format(preferred_format)?)
+    }
+}
+
+fn read_interfaces() -> Result<Vec<InterfaceCounters>, NetworkError> {
+    // The first two lines are headers.
+    fs::read_to_string("/proc/net/dev")?
+        .lines()
+        .skip(2)
+        .map(|line| {
+            let malformed = || NetworkError::MalformedDev(line.to_string());
+            let (name, counters) = line.split_once(':').ok_or_else(malformed)?;
+            let name = name.trim();
+            let counters: Vec<u64> = counters
+                .split_whitespace()
+                .map(|counter| counter.parse().map_err(|_| malformed()))
+                .collect::<Result<_, _>>()?;
+            let (rx_bytes, tx_bytes) = match counters[..] {
+                [rx_bytes, _, _, _, _, _, _, _, tx_bytes, ..] => (rx_bytes, tx_bytes),
+                _ => return Err(malformed()),
+            };
+
+            let class = Path::new("/sys/class/net").join(name);
+            let read_sys = |attribute: &str| {
+                fs::read_to_string(class.join(attribute))
+                    .map(|s| s.trim().to_string())
+                    .unwrap_or_default()
+            };
+            let flags = u32::from_str_radix(read_sys("flags").trim_start_matches("0x"), 16)
+                .unwrap_or(0);
+            // Loopback and some tunnels never report an operstate other than `unknown`.
+            let is_up = match read_sys("operstate").as_str() {
+                "up" => true,
+                "unknown" => flags & IFF_UP != 0,
+                _ => false,
+            };
+
+            Ok(InterfaceCounters {
+                name: name.to_string(),
+                is_up,
+                is_loopback: read_sys("type").parse() == Ok(ARPHRD_LOOPBACK),
+                // Only interfaces backed by a device have the `device` link.
+                is_virtual: !class.join("device").exists(),
+                rx_bytes,
+                tx_bytes,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_in_the_format_asked() {
+        let mut network = Network::new(InterfaceFilter::default());
+        assert!(matches!(
+            network.query("rx-total", &DataFormat::String),
+            Ok(Data::String(total)) if total == "0"
+        ));
+        assert!(matches!(
+            network.query("rx-rate", &DataFormat::Float),
+            Ok(Data::F64(_))
+        ));
+        assert!(matches!(
+            network.query("interfaces", &DataFormat::U32),
+            Err(NetworkError::Conversion(_))
+        ));
+    }
+}
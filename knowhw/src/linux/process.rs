@@ -0,0 +1,228 @@
+use std::{collections::HashMap, fs, str::FromStr, sync::Mutex, time::Instant};
+
+use declarrred::rt::{Data, DataConversionError, DataFormat};
+use thiserror::Error;
+
+use crate::{
+    process::{ProcessQuery, ProcessQueryError, ProcessSample},
+    Knowhw,
+};
+
+pub struct Process {
+    state: Mutex<ProcessState>,
+}
+
+#[derive(Default)]
+struct ProcessState {
+    last: Option<Snapshot>,
+    samples: Vec<ProcessSample>,
+}
+
+struct Snapshot {
+    taken_at: Instant,
+    total_jiffies: u64,
+    processes: HashMap<u32, RawProcess>,
+}
+
+struct RawProcess {
+    name: String,
+    jiffies: u64,
+    memory: u64,
+    io_read: u64,
+    io_write: u64,
+}
+
+#[derive(Debug, Error)]
+pub enum ProcessError {
+    #[error("Failed to parse query: {0}")]
+    InvalidQuery(#[from] ProcessQueryError),
+
+    #[error("Unsupported Format: {0:?}")]
+    UnsupportedFormat(DataFormat),
+
+    #[error("{0}")]
+    Conversion(#[from] DataConversionError),
+
+    #[error("Failed to read /proc: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Malformed /proc/stat")]
+    MalformedStat,
+
+    #[error("Mutex lock poisoned")]
+    MutexLockPoisoned,
+}
+
+impl Process {
+    pub fn new() -> Self {
+        Process {
+            state: Mutex::new(ProcessState::default()),
+        }
+    }
+}
+
+impl Default for Process {
+    fn default() -> Self {
+        Process::new()
+    }
+}
+
+impl Knowhw for Process {
+    type Error = ProcessError;
+
+    fn update(&self) -> Result<(), Self::Error> {
+        let snapshot = Snapshot::take()?;
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| ProcessError::MutexLockPoisoned)?;
+
+        if let Some(last) = &state.last {
+            let elapsed = snapshot.taken_at.duration_since(last.taken_at).as_secs_f64();
+            let total = snapshot.total_jiffies.saturating_sub(last.total_jiffies) as f64;
+            state.samples = snapshot
+                .processes
+                .iter()
+                .map(|(&pid, now)| {
+                    let (cpu, io_read, io_write) = match last.processes.get(&pid) {
+                        Some(before) if elapsed > 0.0 && total > 0.0 => (
+                            (now.jiffies.saturating_sub(before.jiffies) as f64 / total * 100.0)
+                                .min(100.0),
+                            now.io_read.saturating_sub(before.io_read) as f64 / elapsed,
+                            now.io_write.saturating_sub(before.io_write) as f64 / elapsed,
+                        ),
+                        _ => (0.0, 0.0, 0.0),
+                    };
+                    ProcessSample {
+                        pid,
+                        name: now.name.clone(),
+                        cpu,
+                        memory: now.memory,
+                        io_read,
+                        io_write,
+                    }
+                })
+                .collect();
+        }
+        state.last = Some(snapshot);
+
+        Ok(())
+    }
+
+    fn query(&mut self, query: &str, preferred_format: &DataFormat) -> Result<Data, Self::Error> {
+        let query = ProcessQuery::from_str(query)?;
+        match (&query, preferred_format) {
+            (ProcessQuery::Top { .. }, DataFormat::List)
+            | (
+                ProcessQuery::Count,
+                DataFormat::U32
+                | DataFormat::U64
+                | DataFormat::UInt
+                | DataFormat::I32
+                | DataFormat::I64
+                | DataFormat::Int,
+            ) => {}
+            (_, f) => return Err(ProcessError::UnsupportedFormat(f.clone())),
+        }
+        let state = self
+            .state
+            .get_mut()
+            .map_err(|_| ProcessError::MutexLockPoisoned)?;
+
+        Ok(query.evaluate(&state.samples).to_format(preferred_format)?)
+    }
+}
+
+impl Snapshot {
+    fn take() -> Result<Snapshot, ProcessError> {
+        let mut processes = HashMap::new();
+        for entry in fs::read_dir("/proc")? {
+            let entry = entry?;
+            let pid = match entry.file_name().to_str().and_then(|s| s.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            // Processes may exit while we are reading them, so a vanished entry is skipped.
+            if let Some(process) = RawProcess::read(pid) {
+                processes.insert(pid, process);
+            }
+        }
+
+        Ok(Snapshot {
+            taken_at: Instant::now(),
+            total_jiffies: read_total_jiffies()?,
+            processes,
+        })
+    }
+}
+
+impl RawProcess {
+    fn read(pid: u32) -> Option<RawProcess> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // The comm field is parenthesized and may itself contain spaces or parentheses.
+        let name = &stat[stat.find('(')? + 1..stat.rfind(')')?];
+        let fields: Vec<_> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+
+        let memory = fs::read_to_string(format!("/proc/{}/status", pid))
+            .ok()
+            .and_then(|status| read_kv(&status, "VmRSS:"))
+            .map(|kb| kb * 1024)
+            .unwrap_or(0);
+
+        // /proc/[pid]/io is only readable for our own processes unless privileged.
+        let io = fs::read_to_string(format!("/proc/{}/io", pid)).unwrap_or_default();
+
+        Some(RawProcess {
+            name: name.to_string(),
+            jiffies: utime + stime,
+            memory,
+            io_read: read_kv(&io, "read_bytes:").unwrap_or(0),
+            io_write: read_kv(&io, "write_bytes:").unwrap_or(0),
+        })
+    }
+}
+
+fn read_kv(content: &str, key: &str) -> Option<u64> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix(key))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|value| value.parse().ok())
+}
+
+fn read_total_jiffies() -> Result<u64, ProcessError> {
+    let stat = fs::read_to_string("/proc/stat")?;
+    let cpu = stat
+        .lines()
+        .find_map(|line| line.strip_prefix("cpu "))
+        .ok_or(ProcessError::MalformedStat)?;
+    // guest and guest_nice are already accounted in user and nice.
+    cpu.split_whitespace()
+        .take(8)
+        .map(|jiffies| jiffies.parse::<u64>().map_err(|_| ProcessError::MalformedStat))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_in_the_format_asked() {
+        let mut process = Process::new();
+        assert!(matches!(
+            process.query("count", &DataFormat::U32),
+            Ok(Data::U32(0))
+        ));
+        assert!(matches!(
+            process.query("count", &DataFormat::Int),
+            Ok(Data::I64(0))
+        ));
+        assert!(matches!(
+            process.query("count", &DataFormat::String),
+            Err(ProcessError::UnsupportedFormat(DataFormat::String))
+        ));
+    }
+}
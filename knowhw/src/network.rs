@@ -0,0 +1,462 @@
+use std::{collections::HashMap, str::FromStr, time::Instant};
+
+use declarrred::rt::Data;
+use strum::EnumString;
+use thiserror::Error;
+
+/// Raw counters of a network interface, as reported by the platform.
+#[derive(Clone, Debug)]
+pub struct InterfaceCounters {
+    pub name: String,
+    pub is_up: bool,
+    pub is_loopback: bool,
+    pub is_virtual: bool,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// Selects which interfaces are taken into account.
+///
+/// Empty `include` means every interface. `exclude` always wins over `include`.
+#[derive(Clone, Debug)]
+pub struct InterfaceFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub only_up: bool,
+    pub skip_loopback: bool,
+    pub skip_virtual: bool,
+}
+
+impl Default for InterfaceFilter {
+    fn default() -> Self {
+        InterfaceFilter {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            only_up: false,
+            skip_loopback: true,
+            skip_virtual: false,
+        }
+    }
+}
+
+impl InterfaceFilter {
+    pub fn accepts(&self, interface: &InterfaceCounters) -> bool {
+        (!self.only_up || interface.is_up)
+            && (!self.skip_loopback || !interface.is_loopback)
+            && (!self.skip_virtual || !interface.is_virtual)
+            && (self.include.is_empty()
+                || self
+                    .include
+                    .iter()
+                    .any(|pattern| glob_match(pattern, &interface.name)))
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| glob_match(pattern, &interface.name))
+    }
+}
+
+/// Matches `text` against a glob `pattern` supporting `*` and `?`, case-insensitively.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<_> = pattern.to_lowercase().chars().collect();
+    let text: Vec<_> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[derive(Clone, Copy, Debug, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum NetworkMetric {
+    RxRate,
+    TxRate,
+    RxTotal,
+    TxTotal,
+    Up,
+    Interfaces,
+}
+
+/// `<metric>` aggregates over the filtered interfaces, `<metric>(<interface>)` picks one.
+pub struct NetworkQuery {
+    pub metric: NetworkMetric,
+    pub interface: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum NetworkQueryError {
+    #[error("Unknown network metric {0}")]
+    UnknownMetric(String),
+
+    #[error("{0} does not match with network query syntax")]
+    Syntax(String),
+
+    #[error("There are no network interface named {0}")]
+    UnknownInterface(String),
+}
+
+impl FromStr for NetworkQuery {
+    type Err = NetworkQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (metric, interface) = match s.split_once('(') {
+            Some((metric, rest)) => match rest.strip_suffix(')') {
+                Some(interface) => (metric, Some(interface.to_string())),
+                None => return Err(NetworkQueryError::Syntax(s.to_string())),
+            },
+            None => (s, None),
+        };
+        let metric = NetworkMetric::from_str(metric.trim())
+            .map_err(|_| NetworkQueryError::UnknownMetric(metric.to_string()))?;
+        if matches!(metric, NetworkMetric::Interfaces) && interface.is_some() {
+            return Err(NetworkQueryError::Syntax(s.to_string()));
+        }
+
+        Ok(NetworkQuery { metric, interface })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct InterfaceSample {
+    name: String,
+    is_up: bool,
+    rx_rate: f64,
+    tx_rate: f64,
+    rx_total: u64,
+    tx_total: u64,
+}
+
+/// Turns successive [`InterfaceCounters`] into rates and totals since the first sampling.
+pub struct NetworkTracker {
+    filter: InterfaceFilter,
+    /// Bytes received and sent by each interface since it was first sampled.
+    totals: HashMap<String, (u64, u64)>,
+    last: Option<(Instant, HashMap<String, InterfaceCounters>)>,
+    samples: Vec<InterfaceSample>,
+}
+
+impl NetworkTracker {
+    pub fn new(filter: InterfaceFilter) -> Self {
+        NetworkTracker {
+            filter,
+            totals: HashMap::new(),
+            last: None,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn feed(&mut self, interfaces: Vec<InterfaceCounters>) {
+        self.feed_at(interfaces, Instant::now());
+    }
+
+    fn feed_at(&mut self, interfaces: Vec<InterfaceCounters>, now: Instant) {
+        let mut samples = Vec::new();
+
+        for interface in interfaces.iter().filter(|it| self.filter.accepts(it)) {
+            // Counters start over when a driver is reloaded, having counted from 0 since.
+            let delta = |now: u64, before: u64| if now < before { now } else { now - before };
+            let (rx_delta, tx_delta, elapsed) = match &self.last {
+                Some((taken_at, last)) => match last.get(&interface.name) {
+                    Some(before) => (
+                        delta(interface.rx_bytes, before.rx_bytes),
+                        delta(interface.tx_bytes, before.tx_bytes),
+                        now.saturating_duration_since(*taken_at).as_secs_f64(),
+                    ),
+                    None => (0, 0, 0.0),
+                },
+                None => (0, 0, 0.0),
+            };
+            let rate = |delta: u64| {
+                if elapsed > 0.0 {
+                    delta as f64 / elapsed
+                } else {
+                    0.0
+                }
+            };
+
+            let total = self.totals.entry(interface.name.clone()).or_default();
+            total.0 += rx_delta;
+            total.1 += tx_delta;
+
+            samples.push(InterfaceSample {
+                name: interface.name.clone(),
+                is_up: interface.is_up,
+                rx_rate: rate(rx_delta),
+                tx_rate: rate(tx_delta),
+                rx_total: total.0,
+                tx_total: total.1,
+            });
+        }
+
+        self.samples = samples;
+        self.last = Some((
+            now,
+            interfaces
+                .into_iter()
+                .map(|interface| (interface.name.clone(), interface))
+                .collect(),
+        ));
+    }
+
+    pub fn query(&self, query: &NetworkQuery) -> Result<Data, NetworkQueryError> {
+        let selected: Vec<_> = match &query.interface {
+            Some(name) => {
+                let sample = self
+                    .samples
+                    .iter()
+                    .find(|sample| &sample.name == name)
+                    .ok_or_else(|| NetworkQueryError::UnknownInterface(name.clone()))?;
+                vec![sample]
+            }
+            None => self.samples.iter().collect(),
+        };
+
+        Ok(match query.metric {
+            NetworkMetric::RxRate => Data::F64(selected.iter().map(|it| it.rx_rate).sum()),
+            NetworkMetric::TxRate => Data::F64(selected.iter().map(|it| it.tx_rate).sum()),
+            NetworkMetric::RxTotal => Data::U64(selected.iter().map(|it| it.rx_total).sum()),
+            NetworkMetric::TxTotal => Data::U64(selected.iter().map(|it| it.tx_total).sum()),
+            NetworkMetric::Up => Data::Bool(selected.iter().any(|it| it.is_up)),
+            NetworkMetric::Interfaces => Data::List(
+                selected
+                    .iter()
+                    .map(|it| {
+                        Data::Map(
+                            [
+                                ("name", Data::String(it.name.clone())),
+                                ("up", Data::Bool(it.is_up)),
+                                ("rx-rate", Data::F64(it.rx_rate)),
+                                ("tx-rate", Data::F64(it.tx_rate)),
+                                ("rx-total", Data::U64(it.rx_total)),
+                                ("tx-total", Data::U64(it.tx_total)),
+                            ]
+                            .into_iter()
+                            .map(|(key, value)| (key.to_string(), value))
+                            .collect(),
+                        )
+                    })
+                    .collect(),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn interface(name: &str, rx_bytes: u64, tx_bytes: u64) -> InterfaceCounters {
+        InterfaceCounters {
+            name: name.to_string(),
+            is_up: true,
+            is_loopback: false,
+            is_virtual: false,
+            rx_bytes,
+            tx_bytes,
+        }
+    }
+
+    fn query(tracker: &NetworkTracker, query: &str) -> Data {
+        tracker
+            .query(&NetworkQuery::from_str(query).unwrap())
+            .unwrap()
+    }
+
+    fn float(data: Data) -> f64 {
+        match data {
+            Data::F64(value) => value,
+            other => panic!("Expected a float but got {:?}", other),
+        }
+    }
+
+    fn unsigned(data: Data) -> u64 {
+        match data {
+            Data::U64(value) => value,
+            other => panic!("Expected an unsigned integer but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob_match("Ethernet*", "ethernet 2"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("wlan?", "wlan0"));
+        assert!(!glob_match("wlan?", "wlan10"));
+        assert!(glob_match(
+            "*Virtual*Adapter",
+            "Hyper-V Virtual Ethernet Adapter"
+        ));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+        assert!(!glob_match("eth0", "eth01"));
+        assert!(!glob_match("", "eth0"));
+    }
+
+    #[test]
+    fn filters_interfaces() {
+        let filter = InterfaceFilter::default();
+        assert!(filter.accepts(&interface("eth0", 0, 0)));
+        assert!(!filter.accepts(&InterfaceCounters {
+            is_loopback: true,
+            ..interface("lo", 0, 0)
+        }));
+        assert!(filter.accepts(&InterfaceCounters {
+            is_up: false,
+            is_virtual: true,
+            ..interface("vEthernet", 0, 0)
+        }));
+
+        let filter = InterfaceFilter {
+            only_up: true,
+            skip_virtual: true,
+            ..InterfaceFilter::default()
+        };
+        assert!(!filter.accepts(&InterfaceCounters {
+            is_up: false,
+            ..interface("eth0", 0, 0)
+        }));
+        assert!(!filter.accepts(&InterfaceCounters {
+            is_virtual: true,
+            ..interface("vEthernet", 0, 0)
+        }));
+
+        let filter = InterfaceFilter {
+            include: vec!["eth*".to_string(), "wlan*".to_string()],
+            exclude: vec!["eth1".to_string()],
+            ..InterfaceFilter::default()
+        };
+        assert!(filter.accepts(&interface("eth0", 0, 0)));
+        assert!(filter.accepts(&interface("WLAN0", 0, 0)));
+        assert!(!filter.accepts(&interface("eth1", 0, 0)));
+        assert!(!filter.accepts(&interface("docker0", 0, 0)));
+    }
+
+    #[test]
+    fn parses_queries() {
+        let parsed = NetworkQuery::from_str("rx-rate(eth0)").unwrap();
+        assert!(matches!(parsed.metric, NetworkMetric::RxRate));
+        assert_eq!(parsed.interface.as_deref(), Some("eth0"));
+        assert!(NetworkQuery::from_str("up").unwrap().interface.is_none());
+        assert!(matches!(
+            NetworkQuery::from_str("speed"),
+            Err(NetworkQueryError::UnknownMetric(_))
+        ));
+        assert!(matches!(
+            NetworkQuery::from_str("rx-rate(eth0"),
+            Err(NetworkQueryError::Syntax(_))
+        ));
+        assert!(matches!(
+            NetworkQuery::from_str("interfaces(eth0)"),
+            Err(NetworkQueryError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn tracks_rates_and_totals() {
+        let mut tracker = NetworkTracker::new(InterfaceFilter::default());
+        let begin = Instant::now();
+        tracker.feed_at(
+            vec![interface("eth0", 1000, 500), interface("wlan0", 0, 0)],
+            begin,
+        );
+        assert_eq!(float(query(&tracker, "rx-rate")), 0.0);
+        assert_eq!(unsigned(query(&tracker, "rx-total")), 0);
+
+        tracker.feed_at(
+            vec![interface("eth0", 3000, 1500), interface("wlan0", 400, 100)],
+            begin + Duration::from_secs(2),
+        );
+        assert_eq!(float(query(&tracker, "rx-rate(eth0)")), 1000.0);
+        assert_eq!(float(query(&tracker, "tx-rate(eth0)")), 500.0);
+        assert_eq!(float(query(&tracker, "rx-rate")), 1200.0);
+        assert_eq!(float(query(&tracker, "tx-rate")), 550.0);
+        assert_eq!(unsigned(query(&tracker, "rx-total")), 2400);
+        assert_eq!(unsigned(query(&tracker, "tx-total(wlan0)")), 100);
+        assert!(matches!(query(&tracker, "up"), Data::Bool(true)));
+        match query(&tracker, "interfaces") {
+            Data::List(interfaces) => assert_eq!(interfaces.len(), 2),
+            other => panic!("Expected a list but got {:?}", other),
+        }
+        assert!(matches!(
+            tracker.query(&NetworkQuery::from_str("rx-rate(eth9)").unwrap()),
+            Err(NetworkQueryError::UnknownInterface(_))
+        ));
+    }
+
+    #[test]
+    fn keeps_totals_when_counters_reset() {
+        let mut tracker = NetworkTracker::new(InterfaceFilter::default());
+        let begin = Instant::now();
+        tracker.feed_at(vec![interface("eth0", 5000, 5000)], begin);
+        tracker.feed_at(
+            vec![interface("eth0", 7000, 6000)],
+            begin + Duration::from_secs(1),
+        );
+        assert_eq!(unsigned(query(&tracker, "rx-total")), 2000);
+
+        // The driver was reloaded, and has counted from 0 since.
+        tracker.feed_at(
+            vec![interface("eth0", 300, 100)],
+            begin + Duration::from_secs(2),
+        );
+        assert_eq!(float(query(&tracker, "rx-rate")), 300.0);
+        assert_eq!(unsigned(query(&tracker, "rx-total")), 2300);
+        assert_eq!(unsigned(query(&tracker, "tx-total")), 1100);
+
+        tracker.feed_at(
+            vec![interface("eth0", 800, 400)],
+            begin + Duration::from_secs(3),
+        );
+        assert_eq!(float(query(&tracker, "rx-rate")), 500.0);
+        assert_eq!(unsigned(query(&tracker, "rx-total")), 2800);
+        assert_eq!(unsigned(query(&tracker, "tx-total")), 1400);
+    }
+
+    #[test]
+    fn leaves_out_filtered_interfaces() {
+        let mut tracker = NetworkTracker::new(InterfaceFilter {
+            exclude: vec!["vEthernet*".to_string()],
+            ..InterfaceFilter::default()
+        });
+        let begin = Instant::now();
+        tracker.feed_at(
+            vec![interface("eth0", 0, 0), interface("vEthernet (WSL)", 0, 0)],
+            begin,
+        );
+        tracker.feed_at(
+            vec![
+                interface("eth0", 100, 0),
+                interface("vEthernet (WSL)", 9000, 0),
+            ],
+            begin + Duration::from_secs(1),
+        );
+        assert_eq!(float(query(&tracker, "rx-rate")), 100.0);
+        assert!(matches!(
+            tracker.query(&NetworkQuery::from_str("rx-rate(vEthernet (WSL))").unwrap()),
+            Err(NetworkQueryError::UnknownInterface(_))
+        ));
+    }
+}
@@ -0,0 +1,230 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use declarrred::rt::Data;
+use strum::EnumString;
+use thiserror::Error;
+
+/// A single process observed between two consecutive samplings.
+#[derive(Clone, Debug)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub name: String,
+    /// Share of the whole machine's CPU time, in percent.
+    pub cpu: f64,
+    /// Working set (resident memory), in bytes.
+    pub memory: u64,
+    /// Bytes read per second.
+    pub io_read: f64,
+    /// Bytes written per second.
+    pub io_write: f64,
+}
+
+#[derive(Clone, Copy, Debug, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ProcessMetric {
+    Cpu,
+    Memory,
+    Io,
+    IoRead,
+    IoWrite,
+}
+
+impl ProcessMetric {
+    pub fn of(&self, sample: &ProcessSample) -> f64 {
+        match self {
+            ProcessMetric::Cpu => sample.cpu,
+            ProcessMetric::Memory => sample.memory as f64,
+            ProcessMetric::Io => sample.io_read + sample.io_write,
+            ProcessMetric::IoRead => sample.io_read,
+            ProcessMetric::IoWrite => sample.io_write,
+        }
+    }
+}
+
+pub enum ProcessQuery {
+    /// `top <count> by <metric>`
+    Top { count: usize, by: ProcessMetric },
+    /// `count`
+    Count,
+}
+
+#[derive(Debug, Error)]
+pub enum ProcessQueryError {
+    #[error("Invalid process count {0}")]
+    InvalidCount(String),
+
+    #[error("Unknown process metric {0}")]
+    UnknownMetric(String),
+
+    #[error("{0} does not match with process query syntax")]
+    Syntax(String),
+}
+
+impl FromStr for ProcessQuery {
+    type Err = ProcessQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let splitted: Vec<_> = s.split_whitespace().collect();
+        match splitted[..] {
+            ["count"] => Ok(ProcessQuery::Count),
+            ["top", count, "by", metric] => Ok(ProcessQuery::Top {
+                count: count
+                    .parse()
+                    .map_err(|_| ProcessQueryError::InvalidCount(count.to_string()))?,
+                by: ProcessMetric::from_str(metric)
+                    .map_err(|_| ProcessQueryError::UnknownMetric(metric.to_string()))?,
+            }),
+            _ => Err(ProcessQueryError::Syntax(s.to_string())),
+        }
+    }
+}
+
+impl ProcessQuery {
+    /// Evaluates the query over the latest samples.
+    ///
+    /// `top` yields a list of maps with `name`, `pid`, `cpu`, `memory`, `io-read`, `io-write`
+    /// and `value`, the last one being the metric the list was ranked by.
+    pub fn evaluate(&self, samples: &[ProcessSample]) -> Data {
+        match self {
+            ProcessQuery::Count => Data::U64(samples.len() as u64),
+            ProcessQuery::Top { count, by } => {
+                let mut ranked: Vec<_> = samples.iter().collect();
+                ranked.sort_by(|a, b| by.of(b).total_cmp(&by.of(a)));
+                Data::List(
+                    ranked
+                        .into_iter()
+                        .take(*count)
+                        .map(|sample| {
+                            Data::Map(BTreeMap::from([
+                                ("name".to_string(), Data::String(sample.name.clone())),
+                                ("pid".to_string(), Data::U32(sample.pid)),
+                                ("cpu".to_string(), Data::F64(sample.cpu)),
+                                ("memory".to_string(), Data::U64(sample.memory)),
+                                ("io-read".to_string(), Data::F64(sample.io_read)),
+                                ("io-write".to_string(), Data::F64(sample.io_write)),
+                                ("value".to_string(), Data::F64(by.of(sample))),
+                            ]))
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(
+        pid: u32,
+        name: &str,
+        cpu: f64,
+        memory: u64,
+        io_read: f64,
+        io_write: f64,
+    ) -> ProcessSample {
+        ProcessSample {
+            pid,
+            name: name.to_string(),
+            cpu,
+            memory,
+            io_read,
+            io_write,
+        }
+    }
+
+    fn ranked(query: &str, samples: &[ProcessSample]) -> Vec<(u32, f64)> {
+        match ProcessQuery::from_str(query).unwrap().evaluate(samples) {
+            Data::List(list) => list
+                .into_iter()
+                .map(|item| match item {
+                    Data::Map(map) => match (&map["pid"], &map["value"]) {
+                        (Data::U32(pid), Data::F64(value)) => (*pid, *value),
+                        other => panic!("Unexpected fields {:?}", other),
+                    },
+                    other => panic!("Expected a map but got {:?}", other),
+                })
+                .collect(),
+            other => panic!("Expected a list but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_queries() {
+        assert!(matches!(
+            ProcessQuery::from_str("count").unwrap(),
+            ProcessQuery::Count
+        ));
+        assert!(matches!(
+            ProcessQuery::from_str(" top  5 by io-write ").unwrap(),
+            ProcessQuery::Top {
+                count: 5,
+                by: ProcessMetric::IoWrite
+            }
+        ));
+        assert!(matches!(
+            ProcessQuery::from_str("top five by cpu"),
+            Err(ProcessQueryError::InvalidCount(_))
+        ));
+        assert!(matches!(
+            ProcessQuery::from_str("top 5 by disk"),
+            Err(ProcessQueryError::UnknownMetric(_))
+        ));
+        assert!(matches!(
+            ProcessQuery::from_str("top 5 cpu"),
+            Err(ProcessQueryError::Syntax(_))
+        ));
+        assert!(matches!(
+            ProcessQuery::from_str(""),
+            Err(ProcessQueryError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn ranks_by_metric() {
+        let samples = [
+            sample(1, "chrome", 12.5, 300, 10.0, 0.0),
+            sample(2, "chrome", 30.0, 100, 0.0, 5.0),
+            sample(3, "code", 2.0, 900, 40.0, 40.0),
+        ];
+
+        assert_eq!(ranked("top 2 by cpu", &samples), vec![(2, 30.0), (1, 12.5)]);
+        assert_eq!(
+            ranked("top 5 by memory", &samples),
+            vec![(3, 900.0), (1, 300.0), (2, 100.0)]
+        );
+        assert_eq!(ranked("top 1 by io", &samples), vec![(3, 80.0)]);
+        assert_eq!(
+            ranked("top 2 by io-write", &samples),
+            vec![(3, 40.0), (2, 5.0)]
+        );
+        assert!(ranked("top 0 by cpu", &samples).is_empty());
+        assert!(matches!(
+            ProcessQuery::Count.evaluate(&samples),
+            Data::U64(3)
+        ));
+    }
+
+    #[test]
+    fn lists_every_field() {
+        let samples = [sample(7, "explorer", 1.5, 2048, 3.0, 4.0)];
+        match ProcessQuery::from_str("top 1 by cpu")
+            .unwrap()
+            .evaluate(&samples)
+        {
+            Data::List(list) => match &list[..] {
+                [Data::Map(map)] => {
+                    assert_eq!(
+                        map.keys().map(String::as_str).collect::<Vec<_>>(),
+                        vec!["cpu", "io-read", "io-write", "memory", "name", "pid", "value"]
+                    );
+                    assert!(matches!(&map["name"], Data::String(name) if name == "explorer"));
+                    assert!(matches!(map["memory"], Data::U64(2048)));
+                }
+                other => panic!("Expected one map but got {:?}", other),
+            },
+            other => panic!("Expected a list but got {:?}", other),
+        }
+    }
+}
@@ -4,8 +4,12 @@ pub use battery_report::{BatteryReport, BatteryReportError, BatteryReportQuery};
 pub use global_memory_status::{
     GlobalMemoryStatus, GlobalMemoryStatusError, GlobalMemoryStatusQuery,
 };
+pub use network::{Network, NetworkError};
 pub use pdh::{Pdh, PdhError};
+pub use process::{Process, ProcessError};
 
 mod battery_report;
 mod global_memory_status;
+mod network;
 mod pdh;
+mod process;
@@ -0,0 +1,98 @@
+use std::{ptr::null_mut, str::FromStr, sync::Mutex};
+
+use declarrred::rt::{Data, DataConversionError, DataFormat};
+use thiserror::Error;
+use windows::Win32::NetworkManagement::{
+    IpHelper::{FreeMibTable, GetIfTable2, IF_TYPE_SOFTWARE_LOOPBACK, MIB_IF_TABLE2},
+    Ndis::IfOperStatusUp,
+};
+
+use crate::{
+    network::{InterfaceCounters, InterfaceFilter, NetworkQuery, NetworkQueryError, NetworkTracker},
+    Knowhw,
+};
+
+/// `InterfaceAndOperStatusFlags.HardwareInterface`
+const HARDWARE_INTERFACE: u8 = 0x1;
+/// `InterfaceAndOperStatusFlags.FilterInterface`
+const FILTER_INTERFACE: u8 = 0x2;
+
+pub struct Network {
+    tracker: Mutex<NetworkTracker>,
+}
+
+#[derive(Debug, Error)]
+pub enum NetworkError {
+    #[error("Invalid query: {0}")]
+    InvalidQuery(#[from] NetworkQueryError),
+
+    #[error("{0}")]
+    Conversion(#[from] DataConversionError),
+
+    #[error("Win32 error: {0}")]
+    WindowsError(#[from] windows::core::Error),
+
+    #[error("Mutex lock poisoned")]
+    MutexLockPoisoned,
+}
+
+impl Network {
+    pub fn new(filter: InterfaceFilter) -> Self {
+        Network {
+            tracker: Mutex::new(NetworkTracker::new(filter)),
+        }
+    }
+}
+
+impl Knowhw for Network {
+    type Error = NetworkError;
+
+    fn update(&self) -> Result<(), Self::Error> {
+        let interfaces = read_interfaces()?;
+        self.tracker
+            .lock()
+            .map_err(|_| NetworkError::MutexLockPoisoned)?
+            .feed(interfaces);
+
+        Ok(())
+    }
+
+    fn query(&mut self, query: &str, preferred_format: &DataFormat) -> Result<Data, Self::Error> {
+        let query = NetworkQuery::from_str(query)?;
+        let tracker = self
+            .tracker
+            .get_mut()
+            .map_err(|_| NetworkError::MutexLockPoisoned)?;
+
+        Ok(tracker.query(&query)?.to_format(preferred_format)?)
+    }
+}
+
+fn read_interfaces() -> Result<Vec<InterfaceCounters>, NetworkError> {
+    let mut table: *mut MIB_IF_TABLE2 = null_mut();
+    unsafe { GetIfTable2(&mut table) }.ok()?;
+
+    let rows = unsafe {
+        std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize)
+    };
+    let interfaces = rows
+        .iter()
+        // Filter drivers (e.g. `Wi-Fi-WFP Native MAC Layer LightWeight Filter-0000`) mirror the
+        // counters of the interface they are attached to.
+        .filter(|row| row.InterfaceAndOperStatusFlags._bitfield & FILTER_INTERFACE == 0)
+        .map(|row| InterfaceCounters {
+            name: String::from_utf16_lossy(
+                &row.Alias[..row.Alias.iter().position(|&c| c == 0).unwrap_or(row.Alias.len())],
+            ),
+            is_up: row.OperStatus == IfOperStatusUp,
+            is_loopback: row.Type == IF_TYPE_SOFTWARE_LOOPBACK,
+            is_virtual: row.InterfaceAndOperStatusFlags._bitfield & HARDWARE_INTERFACE == 0,
+            rx_bytes: row.InOctets,
+            tx_bytes: row.OutOctets,
+        })
+        .collect();
+
+    unsafe { FreeMibTable(table as _) };
+
+    Ok(interfaces)
+}
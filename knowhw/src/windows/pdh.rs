@@ -99,7 +99,10 @@ impl Knowhw for Pdh {
                 PdhGetFormattedCounterValue(
                     counter,
                     match &preferred_format {
-                        f @ DataFormat::String | f @ DataFormat::Bool => {
+                        f @ DataFormat::String
+                        | f @ DataFormat::Bool
+                        | f @ DataFormat::List
+                        | f @ DataFormat::Map => {
                             return Err(PdhError::UnsupportedFormat(DataFormat::clone(f)))
                         }
                         DataFormat::I32 | DataFormat::U32 => PDH_FMT_LONG,
@@ -129,7 +132,10 @@ impl Knowhw for Pdh {
 
             let data = unsafe {
                 match &preferred_format {
-                    f @ DataFormat::String | f @ DataFormat::Bool => {
+                    f @ DataFormat::String
+                    | f @ DataFormat::Bool
+                    | f @ DataFormat::List
+                    | f @ DataFormat::Map => {
                         return Err(PdhError::UnsupportedFormat(DataFormat::clone(f)))
                     }
                     DataFormat::I32 => Data::I32(value.Anonymous.longValue),
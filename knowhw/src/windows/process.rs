@@ -0,0 +1,215 @@
+use std::{ptr::null_mut, str::FromStr, sync::Mutex, thread};
+
+use declarrred::rt::{Data, DataConversionError, DataFormat};
+use thiserror::Error;
+use windows::{
+    core::{HSTRING, PCWSTR},
+    Win32::System::Performance::{
+        PdhAddEnglishCounterW, PdhCollectQueryData, PdhGetFormattedCounterArrayW, PdhOpenQueryW,
+        PDH_CSTATUS_INVALID_DATA, PDH_FMT, PDH_FMT_COUNTERVALUE_ITEM_W, PDH_FMT_DOUBLE,
+        PDH_INVALID_DATA, PDH_MORE_DATA, PDH_NO_DATA,
+    },
+};
+
+use crate::{
+    process::{ProcessQuery, ProcessQueryError, ProcessSample},
+    Knowhw,
+};
+
+/// `PDH_FMT_NOCAP100`, so `% Processor Time` can exceed 100 on multi-core machines.
+const PDH_FMT_NOCAP100: u32 = 0x8000;
+
+pub struct Process {
+    query: isize,
+    counters: ProcessCounters,
+    samples: Mutex<Vec<ProcessSample>>,
+}
+
+struct ProcessCounters {
+    pid: isize,
+    cpu: isize,
+    memory: isize,
+    io_read: isize,
+    io_write: isize,
+}
+
+#[derive(Debug, Error)]
+pub enum ProcessError {
+    #[error("Failed to parse query: {0}")]
+    InvalidQuery(#[from] ProcessQueryError),
+
+    #[error("Unsupported Format: {0:?}")]
+    UnsupportedFormat(DataFormat),
+
+    #[error("{0}")]
+    Conversion(#[from] DataConversionError),
+
+    #[error("Win32 error: {0}")]
+    WindowsError(#[from] windows::core::Error),
+
+    #[error("Mutex lock poisoned")]
+    MutexLockPoisoned,
+}
+
+impl Process {
+    pub fn new() -> Result<Process, ProcessError> {
+        let mut query = 0;
+
+        let result = unsafe { PdhOpenQueryW(PCWSTR(null_mut()), 0, &mut query) };
+        if result != 0 {
+            Err(windows::core::Error::from_win32())?;
+        }
+
+        let add_counter = |path: &str| -> Result<isize, ProcessError> {
+            let mut counter = 0;
+            let result =
+                unsafe { PdhAddEnglishCounterW(query, &HSTRING::from(path), 0, &mut counter) };
+            if result != 0 {
+                Err(windows::core::Error::from_win32())?;
+            }
+            Ok(counter)
+        };
+
+        let counters = ProcessCounters {
+            pid: add_counter(r"\Process(*)\ID Process")?,
+            cpu: add_counter(r"\Process(*)\% Processor Time")?,
+            memory: add_counter(r"\Process(*)\Working Set")?,
+            io_read: add_counter(r"\Process(*)\IO Read Bytes/sec")?,
+            io_write: add_counter(r"\Process(*)\IO Write Bytes/sec")?,
+        };
+
+        Ok(Process {
+            query,
+            counters,
+            samples: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+impl Knowhw for Process {
+    type Error = ProcessError;
+
+    fn update(&self) -> Result<(), Self::Error> {
+        unsafe {
+            let result = PdhCollectQueryData(self.query);
+            if result != 0 {
+                Err(::windows::core::Error::from_win32())?;
+            }
+        }
+
+        let processors = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1) as f64;
+
+        let pid = read_counter_array(self.counters.pid)?;
+        let cpu = read_counter_array(self.counters.cpu)?;
+        let memory = read_counter_array(self.counters.memory)?;
+        let io_read = read_counter_array(self.counters.io_read)?;
+        let io_write = read_counter_array(self.counters.io_write)?;
+
+        // Counters of one query expand `*` alike, so a process sits at the same position in every
+        // array. Names can't be joined on, as PDH may leave processes sharing an image unsuffixed.
+        let value = |values: &[(String, Option<f64>)], index: usize, instance: &str| {
+            values
+                .get(index)
+                .filter(|(name, _)| name == instance)
+                .and_then(|(_, value)| *value)
+                .unwrap_or(0.0)
+        };
+        let samples = pid
+            .iter()
+            .enumerate()
+            .filter(|(_, (instance, _))| !matches!(instance.as_str(), "_Total" | "Idle"))
+            .filter_map(|(index, (instance, pid))| {
+                Some(ProcessSample {
+                    pid: (*pid)? as u32,
+                    // Instances sharing an image name may be suffixed like `chrome#3`.
+                    name: instance
+                        .split_once('#')
+                        .map_or(instance.as_str(), |(name, _)| name)
+                        .to_string(),
+                    cpu: value(&cpu, index, instance) / processors,
+                    memory: value(&memory, index, instance) as u64,
+                    io_read: value(&io_read, index, instance),
+                    io_write: value(&io_write, index, instance),
+                })
+            })
+            .collect();
+
+        *self
+            .samples
+            .lock()
+            .map_err(|_| ProcessError::MutexLockPoisoned)? = samples;
+
+        Ok(())
+    }
+
+    fn query(&mut self, query: &str, preferred_format: &DataFormat) -> Result<Data, Self::Error> {
+        let query = ProcessQuery::from_str(query)?;
+        match (&query, preferred_format) {
+            (ProcessQuery::Top { .. }, DataFormat::List)
+            | (
+                ProcessQuery::Count,
+                DataFormat::U32
+                | DataFormat::U64
+                | DataFormat::UInt
+                | DataFormat::I32
+                | DataFormat::I64
+                | DataFormat::Int,
+            ) => {}
+            (_, f) => return Err(ProcessError::UnsupportedFormat(f.clone())),
+        }
+        let samples = self
+            .samples
+            .get_mut()
+            .map_err(|_| ProcessError::MutexLockPoisoned)?;
+
+        Ok(query.evaluate(samples).to_format(preferred_format)?)
+    }
+}
+
+/// Every instance of `counter` in the order PDH expanded it, with no value when it has none.
+fn read_counter_array(counter: isize) -> Result<Vec<(String, Option<f64>)>, ProcessError> {
+    let format = PDH_FMT(PDH_FMT_DOUBLE.0 | PDH_FMT_NOCAP100);
+    let mut buffer_size = 0;
+    let mut item_count = 0;
+
+    let result = unsafe {
+        PdhGetFormattedCounterArrayW(counter, format, &mut buffer_size, &mut item_count, None)
+    };
+    match result {
+        PDH_MORE_DATA => {}
+        PDH_INVALID_DATA | PDH_NO_DATA | PDH_CSTATUS_INVALID_DATA => return Ok(Vec::new()),
+        _ => Err(::windows::core::Error::from_win32())?,
+    }
+
+    // The instance names are packed right after the items in the same buffer.
+    let item_size = std::mem::size_of::<PDH_FMT_COUNTERVALUE_ITEM_W>();
+    let mut buffer = vec![
+        PDH_FMT_COUNTERVALUE_ITEM_W::default();
+        (buffer_size as usize).div_ceil(item_size)
+    ];
+    let result = unsafe {
+        PdhGetFormattedCounterArrayW(
+            counter,
+            format,
+            &mut buffer_size,
+            &mut item_count,
+            Some(buffer.as_mut_ptr()),
+        )
+    };
+    if result != 0 {
+        Err(::windows::core::Error::from_win32())?;
+    }
+
+    Ok(buffer[..item_count as usize]
+        .iter()
+        .map(|item| {
+            let name = unsafe { item.szName.to_string() }.unwrap_or_default();
+            // Only PDH_CSTATUS_VALID_DATA (0) and PDH_CSTATUS_NEW_DATA (1) carry a value.
+            let value = (item.FmtValue.CStatus <= 1)
+                .then_some(unsafe { item.FmtValue.Anonymous.doubleValue });
+            (name, value)
+        })
+        .collect())
+}
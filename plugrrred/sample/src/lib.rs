@@ -0,0 +1,109 @@
+//! A sample plugin, providing a `sample/counter` data source and a `sample/meter` component.
+
+use std::{collections::BTreeMap, time::Instant};
+
+use plugrrred::{
+    declare_plugin,
+    plugin::{Component, DataSource, Registry},
+    DataQuery, RenderContext,
+};
+use serde_json::Value;
+
+declare_plugin!("sample", env!("CARGO_PKG_VERSION"), register);
+
+fn register(registry: &mut Registry) -> Result<(), String> {
+    registry.data_source(
+        "sample/counter",
+        Counter {
+            updates: 0,
+            started_at: Instant::now(),
+        },
+    );
+    registry.component("sample/meter", Meter::new);
+    Ok(())
+}
+
+struct Counter {
+    updates: u64,
+    started_at: Instant,
+}
+
+impl DataSource for Counter {
+    fn update(&mut self) -> Result<(), String> {
+        self.updates += 1;
+        Ok(())
+    }
+
+    fn query(&mut self, query: &str, _format: &str) -> Result<Value, String> {
+        match query {
+            "updates" => Ok(self.updates.into()),
+            "uptime" => Ok(self.started_at.elapsed().as_secs_f64().into()),
+            _ => Err(format!("Unknown query: {}", query)),
+        }
+    }
+}
+
+/// A horizontal bar filled by `value / max`.
+struct Meter {
+    query: DataQuery,
+    max: f64,
+    width: f64,
+    color: Option<String>,
+}
+
+impl Meter {
+    fn new(attributes: &BTreeMap<String, String>) -> Result<Self, String> {
+        let required = |key: &str| {
+            attributes
+                .get(key)
+                .cloned()
+                .ok_or_else(|| format!("Attribute {} is required", key))
+        };
+        let number = |key: &str, default: f64| {
+            attributes.get(key).map_or(Ok(default), |value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("Attribute {} is not a number: {}", key, value))
+            })
+        };
+
+        Ok(Meter {
+            query: DataQuery {
+                name: required("name")?,
+                source: required("source")?,
+                query: required("query")?,
+                format: "float".to_string(),
+            },
+            max: number("max", 100.0)?,
+            width: number("width", 32.0)?,
+            color: attributes.get("color").cloned(),
+        })
+    }
+}
+
+impl Component for Meter {
+    fn setup(&mut self) -> Result<Vec<DataQuery>, String> {
+        Ok(vec![self.query.clone()])
+    }
+
+    fn render(&mut self, context: &RenderContext) -> Result<String, String> {
+        let value = context
+            .variables
+            .get(&self.query.name)
+            .and_then(Value::as_f64)
+            .unwrap_or(0.0);
+        let ratio = (value / self.max).clamp(0.0, 1.0);
+        let height = context.viewbox_height / 4.0;
+        let y = (context.viewbox_height - height) / 2.0;
+        let color = self.color.as_ref().unwrap_or(&context.foreground_color);
+
+        Ok(format!(
+            r#"<rect x="0" y="{y}" width="{width}" height="{height}" fill="none" stroke="{color}" stroke-width="1" /><rect x="0" y="{y}" width="{filled}" height="{height}" fill="{color}" />"#,
+            y = y,
+            width = self.width,
+            height = height,
+            filled = self.width * ratio,
+            color = color,
+        ))
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    collections::BTreeMap,
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    path::PathBuf,
+};
+
+use plugrrred::{
+    ffi::{ABI_VERSION, MIN_ABI_VERSION},
+    host::{self, HostError, LoadedPlugin},
+    DataQuery, RenderContext,
+};
+use serde_json::json;
+
+/// Cargo builds the `cdylib` next to the test binary, or a level above it.
+fn library_path() -> PathBuf {
+    let name = format!("{}plugrrred_sample{}", DLL_PREFIX, DLL_SUFFIX);
+    let deps = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    [deps.join(&name), deps.parent().unwrap().join(&name)]
+        .into_iter()
+        .find(|path| path.is_file())
+        .expect("sample plugin is not built")
+}
+
+fn load() -> LoadedPlugin {
+    host::load(&library_path()).unwrap()
+}
+
+#[test]
+fn declares_itself() {
+    let plugin = load();
+    assert_eq!(plugin.name, "sample");
+    assert_eq!(plugin.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(plugin.abi_version, ABI_VERSION);
+    assert_eq!(
+        plugin
+            .data_sources
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>(),
+        ["sample/counter"]
+    );
+    assert_eq!(
+        plugin
+            .components
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>(),
+        ["sample/meter"]
+    );
+}
+
+#[test]
+fn negotiates_abi_version() {
+    assert!(host::negotiate(ABI_VERSION).is_ok());
+    assert!(host::negotiate(MIN_ABI_VERSION).is_ok());
+    assert!(matches!(
+        host::negotiate(ABI_VERSION + 1),
+        Err(HostError::IncompatibleAbi { found, .. }) if found == ABI_VERSION + 1
+    ));
+    assert!(host::negotiate(MIN_ABI_VERSION - 1).is_err());
+}
+
+#[test]
+fn queries_data_source() {
+    let (_, mut counter) = load().data_sources.remove(0);
+    assert_eq!(counter.query("updates", "int").unwrap(), json!(0));
+    counter.update().unwrap();
+    counter.update().unwrap();
+    assert_eq!(counter.query("updates", "int").unwrap(), json!(2));
+    assert!(counter.query("uptime", "float").unwrap().is_f64());
+
+    match counter.query("nope", "int") {
+        Err(HostError::Plugin(message)) => assert_eq!(message, "Unknown query: nope"),
+        other => panic!("Unexpected {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn renders_component() {
+    let (_, factory) = load().components.remove(0);
+    let attributes = BTreeMap::from([
+        ("name".to_string(), "cpu%".to_string()),
+        ("source".to_string(), "windows/pdh".to_string()),
+        ("query".to_string(), "cpu".to_string()),
+        ("width".to_string(), "40".to_string()),
+    ]);
+    let mut meter = factory.create(&attributes).unwrap();
+
+    assert_eq!(
+        meter.setup().unwrap(),
+        [DataQuery {
+            name: "cpu%".to_string(),
+            source: "windows/pdh".to_string(),
+            query: "cpu".to_string(),
+            format: "float".to_string(),
+        }]
+    );
+    meter.update().unwrap();
+
+    let svg = meter
+        .render(&RenderContext {
+            viewbox_width: 200.0,
+            viewbox_height: 40.0,
+            foreground_color: "white".to_string(),
+            font_family: "Inter".to_string(),
+            variables: BTreeMap::from([("cpu%".to_string(), json!(25.0))]),
+        })
+        .unwrap();
+    assert!(svg.contains(r#"width="10""#), "{}", svg);
+    assert!(svg.contains(r#"fill="white""#), "{}", svg);
+}
+
+#[test]
+fn reports_factory_errors() {
+    let (_, factory) = load().components.remove(0);
+    let error = factory.create(&BTreeMap::new()).err().unwrap();
+    assert!(matches!(error, HostError::Plugin(message) if message == "Attribute name is required"));
+}
+
+#[test]
+fn outlives_loaded_plugin() {
+    // Registered objects keep the library loaded on their own.
+    let mut counter = {
+        let mut plugin = load();
+        plugin.data_sources.remove(0).1
+    };
+    counter.update().unwrap();
+    assert_eq!(counter.query("updates", "int").unwrap(), json!(1));
+}
@@ -0,0 +1,159 @@
+//! The `#[repr(C)]` types crossing the library boundary.
+//!
+//! Anything but strings and function pointers is exchanged as JSON text, so the layout here is
+//! all there is to keep stable. Any change to it must bump [`ABI_VERSION`].
+
+use std::{borrow::Cow, ffi::c_void, slice};
+
+/// The version of the ABI this crate speaks.
+pub const ABI_VERSION: u32 = 1;
+/// The oldest ABI version a host built from this crate still loads.
+pub const MIN_ABI_VERSION: u32 = 1;
+
+/// The name of the [`PluginDeclaration`] static every plugin exports.
+pub const DECLARATION_SYMBOL: &[u8] = b"PLUGRRRED_DECLARATION\0";
+
+/// A borrowed UTF-8 string, valid for the duration of the call it is passed to.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FfiStr {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl FfiStr {
+    pub const fn new(s: &str) -> Self {
+        FfiStr {
+            ptr: s.as_ptr(),
+            len: s.len(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` must point to `len` readable bytes living for `'a`.
+    pub unsafe fn to_str<'a>(self) -> Cow<'a, str> {
+        if self.ptr.is_null() {
+            return Cow::Borrowed("");
+        }
+        String::from_utf8_lossy(slice::from_raw_parts(self.ptr, self.len))
+    }
+}
+
+/// Receives a string from the other side, which is how every output is returned.
+///
+/// The callee may call `write` any number of times; the pieces are concatenated.
+#[repr(C)]
+pub struct FfiWriter {
+    pub context: *mut c_void,
+    pub write: unsafe extern "C" fn(context: *mut c_void, s: FfiStr),
+}
+
+impl FfiWriter {
+    pub fn new(target: &mut String) -> Self {
+        unsafe extern "C" fn write(context: *mut c_void, s: FfiStr) {
+            (*(context as *mut String)).push_str(&s.to_str());
+        }
+
+        FfiWriter {
+            context: target as *mut String as *mut c_void,
+            write,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The writer must be passed by the other side during the current call.
+    pub unsafe fn write_str(&self, s: &str) {
+        (self.write)(self.context, FfiStr::new(s))
+    }
+}
+
+/// Exported by every plugin as [`DECLARATION_SYMBOL`].
+///
+/// `abi_version` is the first field of every version, so a host can always read it before
+/// trusting the rest.
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub abi_version: u32,
+    pub name: FfiStr,
+    pub version: FfiStr,
+    /// Registers everything the plugin provides. Returns `false` to refuse the host, writing
+    /// the reason into `error`.
+    pub register: unsafe extern "C" fn(
+        host_abi_version: u32,
+        registrar: *mut Registrar,
+        error: FfiWriter,
+    ) -> bool,
+}
+
+// Only points to static data.
+unsafe impl Sync for PluginDeclaration {}
+
+/// Handed by the host to [`PluginDeclaration::register`]. The host takes the ownership of
+/// every object registered.
+#[repr(C)]
+pub struct Registrar {
+    pub context: *mut c_void,
+    pub data_source:
+        unsafe extern "C" fn(context: *mut c_void, name: FfiStr, object: DataSourceObject),
+    pub component:
+        unsafe extern "C" fn(context: *mut c_void, name: FfiStr, factory: ComponentFactoryObject),
+}
+
+/// Mirrors `DataSource` of measurrred. Failing calls write the error message into `out`.
+#[repr(C)]
+pub struct DataSourceVTable {
+    pub update: unsafe extern "C" fn(instance: *mut c_void, out: FfiWriter) -> bool,
+    /// Writes the value as JSON into `out`.
+    pub query: unsafe extern "C" fn(
+        instance: *mut c_void,
+        query: FfiStr,
+        format: FfiStr,
+        out: FfiWriter,
+    ) -> bool,
+    pub drop: unsafe extern "C" fn(instance: *mut c_void),
+}
+
+#[repr(C)]
+pub struct DataSourceObject {
+    pub instance: *mut c_void,
+    pub vtable: DataSourceVTable,
+}
+
+/// Creates a component from the attributes of its element, given as a JSON object of strings.
+#[repr(C)]
+pub struct ComponentFactoryVTable {
+    pub create: unsafe extern "C" fn(
+        instance: *mut c_void,
+        attributes: FfiStr,
+        component: *mut ComponentObject,
+        error: FfiWriter,
+    ) -> bool,
+    pub drop: unsafe extern "C" fn(instance: *mut c_void),
+}
+
+#[repr(C)]
+pub struct ComponentFactoryObject {
+    pub instance: *mut c_void,
+    pub vtable: ComponentFactoryVTable,
+}
+
+/// Mirrors `ComponentAction` of measurrred. Failing calls write the error message into `out`.
+#[repr(C)]
+pub struct ComponentVTable {
+    /// Writes a JSON array of [`DataQuery`](crate::DataQuery) into `out`.
+    pub setup: unsafe extern "C" fn(instance: *mut c_void, out: FfiWriter) -> bool,
+    pub update: unsafe extern "C" fn(instance: *mut c_void, out: FfiWriter) -> bool,
+    /// Takes a [`RenderContext`](crate::RenderContext) as JSON and writes SVG elements into
+    /// `out`.
+    pub render:
+        unsafe extern "C" fn(instance: *mut c_void, context: FfiStr, out: FfiWriter) -> bool,
+    pub drop: unsafe extern "C" fn(instance: *mut c_void),
+}
+
+#[repr(C)]
+pub struct ComponentObject {
+    pub instance: *mut c_void,
+    pub vtable: ComponentVTable,
+}
@@ -0,0 +1,244 @@
+//! Loading plugins into measurrred.
+
+use std::{collections::BTreeMap, ffi::c_void, mem::MaybeUninit, path::Path, sync::Arc};
+
+use libloading::Library;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    ffi::{
+        ComponentFactoryObject, ComponentObject, DataSourceObject, FfiStr, FfiWriter,
+        PluginDeclaration, Registrar, ABI_VERSION, DECLARATION_SYMBOL, MIN_ABI_VERSION,
+    },
+    DataQuery, RenderContext,
+};
+
+#[derive(Debug, Error)]
+pub enum HostError {
+    #[error("Failed to load the library: {0}")]
+    Library(#[from] libloading::Error),
+
+    #[error("Plugin speaks ABI version {found}, but only {min} to {max} are supported")]
+    IncompatibleAbi { found: u32, min: u32, max: u32 },
+
+    #[error("Plugin refused to load: {0}")]
+    Refused(String),
+
+    #[error("Plugin failed: {0}")]
+    Plugin(String),
+
+    #[error("Malformed JSON from the plugin: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub struct LoadedPlugin {
+    pub name: String,
+    pub version: String,
+    pub abi_version: u32,
+    pub data_sources: Vec<(String, ForeignDataSource)>,
+    pub components: Vec<(String, ForeignComponentFactory)>,
+}
+
+/// Checks whether a plugin built for `plugin_abi_version` can be loaded.
+pub fn negotiate(plugin_abi_version: u32) -> Result<(), HostError> {
+    if (MIN_ABI_VERSION..=ABI_VERSION).contains(&plugin_abi_version) {
+        Ok(())
+    } else {
+        Err(HostError::IncompatibleAbi {
+            found: plugin_abi_version,
+            min: MIN_ABI_VERSION,
+            max: ABI_VERSION,
+        })
+    }
+}
+
+/// Loads the plugin at `path` and collects everything it registers.
+///
+/// The library stays loaded as long as anything it registered is alive.
+pub fn load(path: &Path) -> Result<LoadedPlugin, HostError> {
+    // Running the initializers of a library is inherently unsafe; plugins are trusted.
+    let library = Arc::new(unsafe { Library::new(path)? });
+    let declaration: &PluginDeclaration = unsafe {
+        let symbol = library.get::<*const PluginDeclaration>(DECLARATION_SYMBOL)?;
+        &**symbol
+    };
+    negotiate(declaration.abi_version)?;
+
+    let mut collected = Collected {
+        library: library.clone(),
+        data_sources: Vec::new(),
+        components: Vec::new(),
+    };
+    let mut registrar = Registrar {
+        context: &mut collected as *mut Collected as *mut c_void,
+        data_source: collect_data_source,
+        component: collect_component,
+    };
+    let mut error = String::new();
+    let registered =
+        unsafe { (declaration.register)(ABI_VERSION, &mut registrar, FfiWriter::new(&mut error)) };
+    if !registered {
+        return Err(HostError::Refused(error));
+    }
+
+    Ok(LoadedPlugin {
+        name: unsafe { declaration.name.to_str() }.into_owned(),
+        version: unsafe { declaration.version.to_str() }.into_owned(),
+        abi_version: declaration.abi_version,
+        data_sources: collected.data_sources,
+        components: collected.components,
+    })
+}
+
+struct Collected {
+    library: Arc<Library>,
+    data_sources: Vec<(String, ForeignDataSource)>,
+    components: Vec<(String, ForeignComponentFactory)>,
+}
+
+unsafe extern "C" fn collect_data_source(
+    context: *mut c_void,
+    name: FfiStr,
+    object: DataSourceObject,
+) {
+    let collected = &mut *(context as *mut Collected);
+    let source = ForeignDataSource {
+        object,
+        _library: collected.library.clone(),
+    };
+    collected
+        .data_sources
+        .push((name.to_str().into_owned(), source));
+}
+
+unsafe extern "C" fn collect_component(
+    context: *mut c_void,
+    name: FfiStr,
+    object: ComponentFactoryObject,
+) {
+    let collected = &mut *(context as *mut Collected);
+    let factory = ForeignComponentFactory {
+        object,
+        library: collected.library.clone(),
+    };
+    collected
+        .components
+        .push((name.to_str().into_owned(), factory));
+}
+
+/// Calls into the plugin with a writer, turning what is written into the result.
+fn call(f: impl FnOnce(FfiWriter) -> bool) -> Result<String, HostError> {
+    let mut out = String::new();
+    if f(FfiWriter::new(&mut out)) {
+        Ok(out)
+    } else {
+        Err(HostError::Plugin(out))
+    }
+}
+
+pub struct ForeignDataSource {
+    object: DataSourceObject,
+    _library: Arc<Library>,
+}
+
+// The plugin side requires `DataSource: Send`.
+unsafe impl Send for ForeignDataSource {}
+
+impl ForeignDataSource {
+    pub fn update(&mut self) -> Result<(), HostError> {
+        call(|out| unsafe { (self.object.vtable.update)(self.object.instance, out) })?;
+        Ok(())
+    }
+
+    pub fn query(&mut self, query: &str, format: &str) -> Result<Value, HostError> {
+        let value = call(|out| unsafe {
+            (self.object.vtable.query)(
+                self.object.instance,
+                FfiStr::new(query),
+                FfiStr::new(format),
+                out,
+            )
+        })?;
+        Ok(serde_json::from_str(&value)?)
+    }
+}
+
+impl Drop for ForeignDataSource {
+    fn drop(&mut self) {
+        unsafe { (self.object.vtable.drop)(self.object.instance) }
+    }
+}
+
+pub struct ForeignComponentFactory {
+    object: ComponentFactoryObject,
+    library: Arc<Library>,
+}
+
+// The plugin side requires the factory to be `Send + Sync`.
+unsafe impl Send for ForeignComponentFactory {}
+unsafe impl Sync for ForeignComponentFactory {}
+
+impl ForeignComponentFactory {
+    pub fn create(
+        &self,
+        attributes: &BTreeMap<String, String>,
+    ) -> Result<ForeignComponent, HostError> {
+        let attributes = serde_json::to_string(attributes)?;
+        let mut component = MaybeUninit::<ComponentObject>::uninit();
+        call(|error| unsafe {
+            (self.object.vtable.create)(
+                self.object.instance,
+                FfiStr::new(&attributes),
+                component.as_mut_ptr(),
+                error,
+            )
+        })?;
+
+        Ok(ForeignComponent {
+            // Initialized by `create` on success.
+            object: unsafe { component.assume_init() },
+            _library: self.library.clone(),
+        })
+    }
+}
+
+impl Drop for ForeignComponentFactory {
+    fn drop(&mut self) {
+        unsafe { (self.object.vtable.drop)(self.object.instance) }
+    }
+}
+
+pub struct ForeignComponent {
+    object: ComponentObject,
+    _library: Arc<Library>,
+}
+
+// The plugin side requires `Component: Send`.
+unsafe impl Send for ForeignComponent {}
+
+impl ForeignComponent {
+    pub fn setup(&mut self) -> Result<Vec<DataQuery>, HostError> {
+        let queries = call(|out| unsafe { (self.object.vtable.setup)(self.object.instance, out) })?;
+        Ok(serde_json::from_str(&queries)?)
+    }
+
+    pub fn update(&mut self) -> Result<(), HostError> {
+        call(|out| unsafe { (self.object.vtable.update)(self.object.instance, out) })?;
+        Ok(())
+    }
+
+    /// Returns SVG elements.
+    pub fn render(&mut self, context: &RenderContext) -> Result<String, HostError> {
+        let context = serde_json::to_string(context)?;
+        call(|out| unsafe {
+            (self.object.vtable.render)(self.object.instance, FfiStr::new(&context), out)
+        })
+    }
+}
+
+impl Drop for ForeignComponent {
+    fn drop(&mut self) {
+        unsafe { (self.object.vtable.drop)(self.object.instance) }
+    }
+}
@@ -0,0 +1,30 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub mod ffi;
+#[cfg(feature = "host")]
+pub mod host;
+pub mod plugin;
+
+/// A variable a component asks the host to fetch, like `<fetch-data>` does.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DataQuery {
+    pub name: String,
+    pub source: String,
+    pub query: String,
+    /// A `DataFormat` in kebab-case, like `float`.
+    pub format: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RenderContext {
+    pub viewbox_width: f64,
+    pub viewbox_height: f64,
+    pub foreground_color: String,
+    pub font_family: String,
+    pub variables: BTreeMap<String, Value>,
+}
@@ -0,0 +1,232 @@
+//! The safe side for plugin authors. Implement [`DataSource`] or [`Component`], register them
+//! in a function taking [`Registry`], and export it with [`declare_plugin!`](crate::declare_plugin).
+
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    ffi::c_void,
+    panic::{self, AssertUnwindSafe},
+};
+
+use serde_json::Value;
+
+use crate::{
+    ffi::{
+        ComponentFactoryObject, ComponentFactoryVTable, ComponentObject, ComponentVTable,
+        DataSourceObject, DataSourceVTable, FfiStr, FfiWriter, Registrar, ABI_VERSION,
+    },
+    DataQuery, RenderContext,
+};
+
+pub trait DataSource: Send + 'static {
+    fn update(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// `format` is the `DataFormat` the widget asked for, in kebab-case. The host converts the
+    /// value anyway, so it is only a hint.
+    fn query(&mut self, query: &str, format: &str) -> Result<Value, String>;
+}
+
+pub trait Component: Send + 'static {
+    fn setup(&mut self) -> Result<Vec<DataQuery>, String> {
+        Ok(Vec::new())
+    }
+
+    fn update(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Returns SVG elements, laid out from the origin like any other component.
+    fn render(&mut self, context: &RenderContext) -> Result<String, String>;
+}
+
+pub struct Registry {
+    registrar: *mut Registrar,
+}
+
+impl Registry {
+    pub fn data_source<T: DataSource>(&mut self, name: &str, source: T) {
+        let object = DataSourceObject {
+            instance: Box::into_raw(Box::new(source)) as *mut c_void,
+            vtable: DataSourceVTable {
+                update: data_source_update::<T>,
+                query: data_source_query::<T>,
+                drop: drop_box::<T>,
+            },
+        };
+        unsafe {
+            ((*self.registrar).data_source)((*self.registrar).context, FfiStr::new(name), object)
+        };
+    }
+
+    /// Registers a component, used as `<plugin component="name" .. />`. `factory` receives the
+    /// other attributes of the element.
+    pub fn component<C, F>(&mut self, name: &str, factory: F)
+    where
+        C: Component,
+        F: Fn(&BTreeMap<String, String>) -> Result<C, String> + Send + Sync + 'static,
+    {
+        let object = ComponentFactoryObject {
+            instance: Box::into_raw(Box::new(factory)) as *mut c_void,
+            vtable: ComponentFactoryVTable {
+                create: component_create::<C, F>,
+                drop: drop_box::<F>,
+            },
+        };
+        unsafe {
+            ((*self.registrar).component)((*self.registrar).context, FfiStr::new(name), object)
+        };
+    }
+}
+
+/// Used by [`declare_plugin!`](crate::declare_plugin).
+///
+/// # Safety
+///
+/// Must only be called by a host through [`PluginDeclaration`](crate::ffi::PluginDeclaration).
+#[doc(hidden)]
+pub unsafe fn register(
+    host_abi_version: u32,
+    registrar: *mut Registrar,
+    error: FfiWriter,
+    f: fn(&mut Registry) -> Result<(), String>,
+) -> bool {
+    if host_abi_version < ABI_VERSION {
+        error.write_str(&format!(
+            "Plugin requires ABI version {}, but the host speaks {}",
+            ABI_VERSION, host_abi_version
+        ));
+        return false;
+    }
+
+    respond(error, || {
+        f(&mut Registry { registrar })?;
+        Ok(String::new())
+    })
+}
+
+/// Exports the plugin, given its name, version, and a `fn(&mut Registry) -> Result<(), String>`.
+#[macro_export]
+macro_rules! declare_plugin {
+    ($name:expr, $version:expr, $register:path) => {
+        #[no_mangle]
+        pub static PLUGRRRED_DECLARATION: $crate::ffi::PluginDeclaration =
+            $crate::ffi::PluginDeclaration {
+                abi_version: $crate::ffi::ABI_VERSION,
+                name: $crate::ffi::FfiStr::new($name),
+                version: $crate::ffi::FfiStr::new($version),
+                register: {
+                    unsafe extern "C" fn plugrrred_register(
+                        host_abi_version: u32,
+                        registrar: *mut $crate::ffi::Registrar,
+                        error: $crate::ffi::FfiWriter,
+                    ) -> bool {
+                        $crate::plugin::register(host_abi_version, registrar, error, $register)
+                    }
+                    plugrrred_register
+                },
+            };
+    };
+}
+
+/// Runs `f` and writes either the output or the error into `out`. Panics must not unwind
+/// into the host.
+unsafe fn respond(out: FfiWriter, f: impl FnOnce() -> Result<String, String>) -> bool {
+    let result = panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|panic| Err(format!("Plugin panicked: {}", panic_message(&panic))));
+    match result {
+        Ok(output) => {
+            out.write_str(&output);
+            true
+        }
+        Err(error) => {
+            out.write_str(&error);
+            false
+        }
+    }
+}
+
+fn panic_message(panic: &Box<dyn Any + Send>) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown")
+}
+
+unsafe extern "C" fn drop_box<T>(instance: *mut c_void) {
+    drop(Box::from_raw(instance as *mut T));
+}
+
+unsafe extern "C" fn data_source_update<T: DataSource>(
+    instance: *mut c_void,
+    out: FfiWriter,
+) -> bool {
+    let source = &mut *(instance as *mut T);
+    respond(out, || source.update().map(|_| String::new()))
+}
+
+unsafe extern "C" fn data_source_query<T: DataSource>(
+    instance: *mut c_void,
+    query: FfiStr,
+    format: FfiStr,
+    out: FfiWriter,
+) -> bool {
+    let source = &mut *(instance as *mut T);
+    respond(out, || {
+        let value = source.query(&query.to_str(), &format.to_str())?;
+        serde_json::to_string(&value).map_err(|e| e.to_string())
+    })
+}
+
+unsafe extern "C" fn component_create<C, F>(
+    instance: *mut c_void,
+    attributes: FfiStr,
+    component: *mut ComponentObject,
+    error: FfiWriter,
+) -> bool
+where
+    C: Component,
+    F: Fn(&BTreeMap<String, String>) -> Result<C, String>,
+{
+    let factory = &*(instance as *const F);
+    respond(error, || {
+        let attributes = serde_json::from_str(&attributes.to_str()).map_err(|e| e.to_string())?;
+        let instance = factory(&attributes)?;
+        component.write(ComponentObject {
+            instance: Box::into_raw(Box::new(instance)) as *mut c_void,
+            vtable: ComponentVTable {
+                setup: component_setup::<C>,
+                update: component_update::<C>,
+                render: component_render::<C>,
+                drop: drop_box::<C>,
+            },
+        });
+        Ok(String::new())
+    })
+}
+
+unsafe extern "C" fn component_setup<C: Component>(instance: *mut c_void, out: FfiWriter) -> bool {
+    let component = &mut *(instance as *mut C);
+    respond(out, || {
+        serde_json::to_string(&component.setup()?).map_err(|e| e.to_string())
+    })
+}
+
+unsafe extern "C" fn component_update<C: Component>(instance: *mut c_void, out: FfiWriter) -> bool {
+    let component = &mut *(instance as *mut C);
+    respond(out, || component.update().map(|_| String::new()))
+}
+
+unsafe extern "C" fn component_render<C: Component>(
+    instance: *mut c_void,
+    context: FfiStr,
+    out: FfiWriter,
+) -> bool {
+    let component = &mut *(instance as *mut C);
+    respond(out, || {
+        let context = serde_json::from_str(&context.to_str()).map_err(|e| e.to_string())?;
+        component.render(&context)
+    })
+}